@@ -1,4 +1,3 @@
-use std::ops::Deref;
 use std::time::Instant;
 
 use glutin::event::{
@@ -16,25 +15,19 @@ use glutin::event_loop::{
 use glutin::window::WindowBuilder;
 use glutin::ContextBuilder;
 
-use svg::node::element::path::{
-    Command,
-    Data,
-};
-use svg::node::element::tag::Path;
-use svg::parser::Event as SvgEvent;
-
 use femtovg::{
     renderer::OpenGl,
     Align,
     Baseline,
     Canvas,
     Color,
-    FillRule,
     FontId,
     ImageFlags,
     Paint,
     Path,
     Renderer,
+    SvgDocument,
+    Transform2D,
 };
 
 fn main() {
@@ -68,26 +61,7 @@ fn main() {
 
     let mut perf = PerfGraph::new();
 
-    let tree = usvg::Tree::from_file("examples/assets/Ghostscript_Tiger.svg", &usvg::Options::default()).unwrap();
-
-    let xml_opt = usvg::XmlOptions {
-        use_single_quote: false,
-        indent: usvg::XmlIndent::Spaces(4),
-        attributes_indent: usvg::XmlIndent::Spaces(4),
-    };
-
-    let svg = tree.to_string(xml_opt);
-
-    let mut paths = render_svg(&svg);
-
-    // print memory usage
-    let mut total_sisze_bytes = 0;
-
-    for path in &paths {
-        total_sisze_bytes += path.0.size();
-    }
-
-    println!("Path mem usage: {}kb", total_sisze_bytes / 1024);
+    let tiger = SvgDocument::from_file("examples/assets/Ghostscript_Tiger.svg").expect("Cannot load svg");
 
     el.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Poll;
@@ -169,23 +143,11 @@ fn main() {
                 canvas.save();
                 canvas.translate(200.0, 200.0);
 
-                for (path, fill, stroke) in &mut paths {
-                    if let Some(fill) = fill {
-                        fill.set_anti_alias(true);
-                        canvas.fill_path(path, *fill);
-                    }
-
-                    if let Some(stroke) = stroke {
-                        stroke.set_anti_alias(true);
-                        canvas.stroke_path(path, *stroke);
-                    }
+                canvas.draw_svg(&tiger, Transform2D::identity());
 
-                    if canvas.contains_point(path, mousex, mousey, FillRule::NonZero) {
-                        let mut paint = Paint::color(Color::rgb(32, 240, 32));
-                        paint.set_line_width(1.0);
-                        canvas.stroke_path(path, paint);
-                    }
-                }
+                let mut highlight = Paint::color(Color::rgb(32, 240, 32));
+                highlight.set_line_width(1.0);
+                canvas.stroke_svg_paths_at(&tiger, Transform2D::identity(), mousex, mousey, highlight);
 
                 canvas.restore();
 
@@ -203,64 +165,6 @@ fn main() {
     });
 }
 
-fn render_svg(svg: &str) -> Vec<(Path, Option<Paint>, Option<Paint>)> {
-    let svg = svg::read(std::io::Cursor::new(&svg)).unwrap();
-
-    let mut paths = Vec::new();
-
-    for event in svg {
-        match event {
-            SvgEvent::Tag(Path, _, attributes) => {
-                let data = attributes.get("d").unwrap();
-                let data = Data::parse(data).unwrap();
-
-                let mut path = Path::new();
-
-                for command in data.iter() {
-                    match command {
-                        Command::Move(_pos, par) => path.move_to(par[0], par[1]),
-                        Command::Line(_pos, par) => path.line_to(par[0], par[1]),
-                        Command::CubicCurve(_pos, par) => {
-                            for points in par.chunks_exact(6) {
-                                path.bezier_to(points[0], points[1], points[2], points[3], points[4], points[5]);
-                            }
-                        }
-                        Command::Close => path.close(),
-                        _ => {}
-                    }
-                }
-
-                let fill = if let Some(fill) = attributes.get("fill") {
-                    Some(Paint::color(Color::hex(fill)))
-                } else {
-                    None
-                };
-
-                let stroke = if let Some(stroke) = attributes.get("stroke") {
-                    if "none" != stroke.deref() {
-                        let mut stroke_paint = Paint::color(Color::hex(stroke));
-
-                        if let Some(stroke_width) = attributes.get("stroke-width") {
-                            stroke_paint.set_line_width(stroke_width.parse().unwrap());
-                        }
-
-                        Some(stroke_paint)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                };
-
-                paths.push((path, fill, stroke));
-            }
-            _ => {}
-        }
-    }
-
-    paths
-}
-
 struct PerfGraph {
     history_count: usize,
     values: Vec<f32>,
@@ -25,9 +25,11 @@ use femtovg::{
     FontId,
     ImageFlags,
     ImageId,
+    ImageSampler,
     Paint,
     Path,
     Renderer,
+    WrapMode,
 };
 
 struct Fonts {
@@ -71,11 +73,16 @@ fn main() {
     // searching for fallbacks
     let _ = canvas.add_font("examples/assets/amiri-regular.ttf");
 
-    let flags = ImageFlags::GENERATE_MIPMAPS | ImageFlags::REPEAT_X | ImageFlags::REPEAT_Y;
     let image_id = canvas
-        .load_image_file("examples/assets/pattern.jpg", flags)
+        .load_image_file("examples/assets/pattern.jpg", ImageFlags::GENERATE_MIPMAPS)
         .expect("Cannot create image");
 
+    let mut sampler = ImageSampler::new();
+    sampler.set_wrap(WrapMode::Repeat, WrapMode::Repeat);
+    canvas
+        .set_image_sampler(image_id, sampler)
+        .expect("Cannot set image sampler");
+
     let start = Instant::now();
     let mut prevt = start;
 
@@ -0,0 +1,229 @@
+use crate::{
+    YuvMatrix,
+    YuvRange,
+};
+
+/// 4x5 matrix used to transform the colors of image paints.
+///
+/// The matrix is stored in row-major order and operates on unpremultiplied colors
+/// with components in the range [0..1]:
+///
+/// ```text
+/// R' = m[0] * R + m[1] * G + m[2] * B + m[3] * A + m[4]
+/// G' = m[5] * R + m[6] * G + m[7] * B + m[8] * A + m[9]
+/// B' = m[10] * R + m[11] * G + m[12] * B + m[13] * A + m[14]
+/// A' = m[15] * R + m[16] * G + m[17] * B + m[18] * A + m[19]
+/// ```
+///
+/// The predefined filters follow the CSS filter effects specification.
+///
+/// # Example
+/// ```
+/// use femtovg::ColorMatrix;
+///
+/// // Desaturated and darkened, like a disabled icon
+/// let matrix = ColorMatrix::grayscale(1.0).then(&ColorMatrix::brightness(0.6));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ColorMatrix(pub [f32; 20]);
+
+impl ColorMatrix {
+    /// Creates a matrix from 20 values in row-major order.
+    pub fn new(values: [f32; 20]) -> Self {
+        Self(values)
+    }
+
+    /// Creates a matrix that leaves colors unchanged.
+    pub fn identity() -> Self {
+        Self([
+            1.0, 0.0, 0.0, 0.0, 0.0, //
+            0.0, 1.0, 0.0, 0.0, 0.0, //
+            0.0, 0.0, 1.0, 0.0, 0.0, //
+            0.0, 0.0, 0.0, 1.0, 0.0, //
+        ])
+    }
+
+    /// Converts colors to grayscale. An amount of 1.0 is completely gray, 0.0 leaves colors unchanged.
+    pub fn grayscale(amount: f32) -> Self {
+        let s = 1.0 - amount.max(0.0).min(1.0);
+
+        Self::from_rgb_rows([
+            [0.2126 + 0.7874 * s, 0.7152 - 0.7152 * s, 0.0722 - 0.0722 * s],
+            [0.2126 - 0.2126 * s, 0.7152 + 0.2848 * s, 0.0722 - 0.0722 * s],
+            [0.2126 - 0.2126 * s, 0.7152 - 0.7152 * s, 0.0722 + 0.9278 * s],
+        ])
+    }
+
+    /// Converts colors to sepia. An amount of 1.0 is completely sepia, 0.0 leaves colors unchanged.
+    pub fn sepia(amount: f32) -> Self {
+        let s = 1.0 - amount.max(0.0).min(1.0);
+
+        Self::from_rgb_rows([
+            [0.393 + 0.607 * s, 0.769 - 0.769 * s, 0.189 - 0.189 * s],
+            [0.349 - 0.349 * s, 0.686 + 0.314 * s, 0.168 - 0.168 * s],
+            [0.272 - 0.272 * s, 0.534 - 0.534 * s, 0.131 + 0.869 * s],
+        ])
+    }
+
+    /// Saturates colors. An amount of 0.0 is completely unsaturated, 1.0 leaves colors unchanged
+    /// and values above 1.0 produce super-saturated colors.
+    pub fn saturate(amount: f32) -> Self {
+        let s = amount.max(0.0);
+
+        Self::from_rgb_rows([
+            [0.213 + 0.787 * s, 0.715 - 0.715 * s, 0.072 - 0.072 * s],
+            [0.213 - 0.213 * s, 0.715 + 0.285 * s, 0.072 - 0.072 * s],
+            [0.213 - 0.213 * s, 0.715 - 0.715 * s, 0.072 + 0.928 * s],
+        ])
+    }
+
+    /// Multiplies the color components by the provided amount. 1.0 leaves colors unchanged.
+    pub fn brightness(amount: f32) -> Self {
+        let b = amount.max(0.0);
+
+        Self::from_rgb_rows([[b, 0.0, 0.0], [0.0, b, 0.0], [0.0, 0.0, b]])
+    }
+
+    /// Adjusts the contrast around the middle gray. 1.0 leaves colors unchanged, 0.0 is completely gray.
+    pub fn contrast(amount: f32) -> Self {
+        let c = amount.max(0.0);
+        let offset = 0.5 - 0.5 * c;
+
+        Self([
+            c, 0.0, 0.0, 0.0, offset, //
+            0.0, c, 0.0, 0.0, offset, //
+            0.0, 0.0, c, 0.0, offset, //
+            0.0, 0.0, 0.0, 1.0, 0.0, //
+        ])
+    }
+
+    /// Rotates the hue of colors. Angle is specified in radians.
+    pub fn hue_rotate(angle: f32) -> Self {
+        let cs = angle.cos();
+        let sn = angle.sin();
+
+        Self::from_rgb_rows([
+            [
+                0.213 + cs * 0.787 - sn * 0.213,
+                0.715 - cs * 0.715 - sn * 0.715,
+                0.072 - cs * 0.072 + sn * 0.928,
+            ],
+            [
+                0.213 - cs * 0.213 + sn * 0.143,
+                0.715 + cs * 0.285 + sn * 0.140,
+                0.072 - cs * 0.072 - sn * 0.283,
+            ],
+            [
+                0.213 - cs * 0.213 - sn * 0.787,
+                0.715 - cs * 0.715 + sn * 0.715,
+                0.072 + cs * 0.928 + sn * 0.072,
+            ],
+        ])
+    }
+
+    /// Creates a matrix converting (Y, U, V, 1) samples to RGB using the provided coefficients and range.
+    pub fn yuv_to_rgb(matrix: YuvMatrix, range: YuvRange) -> Self {
+        let (kr, kb) = match matrix {
+            YuvMatrix::Bt601 => (0.299, 0.114),
+            YuvMatrix::Bt709 => (0.2126, 0.0722),
+        };
+
+        let kg = 1.0 - kr - kb;
+
+        // Scale and offset bringing luma to [0..1] and chroma to [-0.5..0.5]
+        let (y_scale, y_offset, c_scale) = match range {
+            YuvRange::Limited => (255.0 / 219.0, 16.0 / 255.0, 255.0 / 224.0),
+            YuvRange::Full => (1.0, 0.0, 1.0),
+        };
+
+        let c_offset = 128.0 / 255.0;
+
+        let rv = 2.0 * (1.0 - kr) * c_scale;
+        let gu = -2.0 * kb * (1.0 - kb) / kg * c_scale;
+        let gv = -2.0 * kr * (1.0 - kr) / kg * c_scale;
+        let bu = 2.0 * (1.0 - kb) * c_scale;
+
+        let y_bias = -y_scale * y_offset;
+
+        Self([
+            y_scale,
+            0.0,
+            rv,
+            0.0,
+            y_bias - rv * c_offset, //
+            y_scale,
+            gu,
+            gv,
+            0.0,
+            y_bias - (gu + gv) * c_offset, //
+            y_scale,
+            bu,
+            0.0,
+            0.0,
+            y_bias - bu * c_offset, //
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+            0.0, //
+        ])
+    }
+
+    /// Returns a matrix that applies this matrix first and then `other`.
+    pub fn then(&self, other: &Self) -> Self {
+        let a = &other.0;
+        let b = &self.0;
+
+        let mut out = [0.0; 20];
+
+        for row in 0..4 {
+            for col in 0..5 {
+                let mut value = 0.0;
+
+                for k in 0..4 {
+                    value += a[row * 5 + k] * b[k * 5 + col];
+                }
+
+                if col == 4 {
+                    value += a[row * 5 + 4];
+                }
+
+                out[row * 5 + col] = value;
+            }
+        }
+
+        Self(out)
+    }
+
+    // Expands a 3x3 matrix operating on the color channels, keeping alpha unchanged
+    fn from_rgb_rows(rows: [[f32; 3]; 3]) -> Self {
+        let mut m = Self::identity();
+
+        for (i, row) in rows.iter().enumerate() {
+            m.0[i * 5..i * 5 + 3].copy_from_slice(row);
+        }
+
+        m
+    }
+
+    // Splits the matrix into a column-major 4x4 matrix and an offset vector, as used by the shaders
+    pub(crate) fn to_mat4_offset(&self) -> ([f32; 16], [f32; 4]) {
+        let m = &self.0;
+
+        let mut mat = [0.0; 16];
+
+        for col in 0..4 {
+            for row in 0..4 {
+                mat[col * 4 + row] = m[row * 5 + col];
+            }
+        }
+
+        (mat, [m[4], m[9], m[14], m[19]])
+    }
+}
+
+impl Default for ColorMatrix {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
@@ -15,6 +15,10 @@ pub enum ErrorKind {
     GeneralError(String),
     #[cfg(feature = "image-loading")]
     ImageError(::image::ImageError),
+    #[cfg(feature = "svg")]
+    SvgError(::usvg::Error),
+    #[cfg(feature = "lottie")]
+    LottieParseError(String),
     IoError(io::Error),
     FontParseError,
     NoFontFound,
@@ -26,7 +30,10 @@ pub enum ErrorKind {
     ImageIdNotFound,
     ImageUpdateOutOfBounds,
     ImageUpdateWithDifferentFormat,
+    ImageUpdateWithOddOffset,
+    ImageUpdateWithInvalidPlanes,
     UnsuportedImageFromat,
+    InvalidImageFlags,
 }
 
 impl Display for ErrorKind {
@@ -42,6 +49,13 @@ impl From<::image::ImageError> for ErrorKind {
     }
 }
 
+#[cfg(feature = "svg")]
+impl From<::usvg::Error> for ErrorKind {
+    fn from(error: ::usvg::Error) -> Self {
+        Self::SvgError(error)
+    }
+}
+
 impl From<io::Error> for ErrorKind {
     fn from(error: io::Error) -> Self {
         Self::IoError(error)
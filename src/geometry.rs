@@ -280,6 +280,18 @@ impl Size {
 //     }
 // }
 
+#[derive(Copy, Clone, Default, Debug, PartialEq, PartialOrd)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+}
+
 #[derive(Copy, Clone, Default, Debug, PartialEq, PartialOrd)]
 pub struct Rect {
     pub x: f32,
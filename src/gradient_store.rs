@@ -11,8 +11,10 @@ use crate::{
     ImageFlags,
     ImageId,
     ImageInfo,
+    ImageSampler,
     ImageSource,
     Renderer,
+    WrapMode,
 };
 
 /// GradientStore holds image ids for multi-stop gradients. The actual image/textures
@@ -52,7 +54,12 @@ impl GradientStore {
             Ok(*gradient_image_id)
         } else {
             // We need to allocate a texture and synthesize the gradient image.
-            let info = ImageInfo::new(ImageFlags::REPEAT_Y, 256, 1, crate::PixelFormat::Rgba8);
+            let mut info = ImageInfo::new(ImageFlags::empty(), 256, 1, crate::PixelFormat::Rgba8);
+
+            let mut sampler = ImageSampler::new();
+            sampler.set_wrap(WrapMode::ClampToEdge, WrapMode::Repeat);
+            info.set_sampler(sampler);
+
             let gradient_image_id = images.alloc(renderer, info)?;
             let image = linear_gradient_stops(&colors);
             images.update(renderer, gradient_image_id, ImageSource::Rgba(image.as_ref()), 0, 0)?;
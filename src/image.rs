@@ -4,7 +4,11 @@ use generational_arena::{
     Index,
 };
 use imgref::*;
-use rgb::alt::GRAY8;
+use rgb::alt::{
+    BGRA8,
+    GRAY8,
+    GRAYA8,
+};
 use rgb::*;
 
 #[cfg(feature = "image-loading")]
@@ -23,26 +27,272 @@ use crate::{
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct ImageId(pub Index);
 
-/// Image format: `Rgb8`, `Rgba8`, `Gray8`.
+/// Image format: `Rgb8`, `Rgba8`, `Gray8`, `GrayAlpha8`, `Bgra8`, `Rgba16F`.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum PixelFormat {
     Rgb8,
     Rgba8,
     Gray8,
+    /// 8 bit gray with a separate 8 bit alpha channel.
+    GrayAlpha8,
+    /// 8 bit per channel with blue and red swapped, as produced by many video decoders and screen capture APIs.
+    Bgra8,
+    /// 16 bit floating point per channel, for HDR content.
+    Rgba16F,
+    /// 8 bit luma plane followed by a half resolution plane of interleaved U and V samples.
+    Nv12,
+    /// 8 bit luma plane followed by separate half resolution U and V planes.
+    I420,
+}
+
+impl PixelFormat {
+    /// Returns true for formats storing luma and chroma in separate planes.
+    pub fn is_yuv(&self) -> bool {
+        matches!(self, Self::Nv12 | Self::I420)
+    }
+
+    /// Size of a single pixel in bytes
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            Self::Rgb8 => 3,
+            Self::Rgba8 | Self::Bgra8 => 4,
+            Self::Gray8 => 1,
+            Self::GrayAlpha8 => 2,
+            Self::Rgba16F => 8,
+            // Size of a luma sample, the chroma planes add another half of the luma plane size
+            Self::Nv12 | Self::I420 => 1,
+        }
+    }
 }
 
 bitflags! {
-    /// Image flags (eg. repeat, flip, mipmaps, etc.)
+    /// Image flags (eg. flip, mipmaps, etc.)
     pub struct ImageFlags: u32 {
         const GENERATE_MIPMAPS = 1;     // Generate mipmaps during creation of the image.
-        const REPEAT_X = 1 << 1;        // Repeat image in X direction.
-        const REPEAT_Y = 1 << 2;        // Repeat image in Y direction.
         const FLIP_Y = 1 << 3;          // Flips (inverses) image in Y direction when rendered.
         const PREMULTIPLIED = 1 << 4;   // Image data has premultiplied alpha.
-        const NEAREST = 1 << 5;         // Image interpolation is Nearest instead Linear
+        const PURGEABLE = 1 << 6;       // Image may be evicted to keep the image memory within budget.
+        const EXTERNAL = 1 << 7;        // Image wraps an imported texture that stays owned by the application. Set on import only.
+    }
+}
+
+/// Texture filter used when an image is sampled: `Nearest`, `Linear` (default).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ImageFilter {
+    Nearest,
+    Linear,
+}
+
+impl Default for ImageFilter {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+/// How mipmap levels are sampled: `None`, `Nearest`, `Linear` (default).
+///
+/// Only used for images created with `ImageFlags::GENERATE_MIPMAPS`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum MipmapMode {
+    /// Only the base level is sampled.
+    None,
+    /// The closest mipmap level is sampled.
+    Nearest,
+    /// The two closest mipmap levels are sampled and blended, also known as trilinear filtering.
+    Linear,
+}
+
+impl Default for MipmapMode {
+    fn default() -> Self {
+        Self::Linear
     }
 }
 
+/// How texture coordinates outside of the image are handled: `ClampToEdge` (default), `Repeat`,
+/// `MirroredRepeat`, `ClampToBorder`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum WrapMode {
+    /// The edge pixels are extended.
+    ClampToEdge,
+    /// The image is tiled.
+    Repeat,
+    /// The image is tiled, with every other tile mirrored.
+    MirroredRepeat,
+    /// Transparent black is used outside of the image. Falls back to ClampToEdge on WebGL 1.
+    ClampToBorder,
+}
+
+impl Default for WrapMode {
+    fn default() -> Self {
+        Self::ClampToEdge
+    }
+}
+
+/// Describes how an image is sampled when drawn.
+///
+/// # Example
+/// ```
+/// use femtovg::{ImageFilter, ImageSampler, WrapMode};
+///
+/// // Crisp pixel art tiled horizontally
+/// let mut sampler = ImageSampler::nearest();
+/// sampler.set_wrap(WrapMode::Repeat, WrapMode::ClampToEdge);
+///
+/// assert_eq!(sampler.mag_filter(), ImageFilter::Nearest);
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ImageSampler {
+    min_filter: ImageFilter,
+    mag_filter: ImageFilter,
+    mipmap_mode: MipmapMode,
+    wrap_x: WrapMode,
+    wrap_y: WrapMode,
+    bicubic: bool,
+}
+
+impl ImageSampler {
+    /// Creates a sampler with linear filtering, linear mipmap sampling and edge clamping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a sampler with nearest filtering for both minification and magnification.
+    pub fn nearest() -> Self {
+        let mut sampler = Self::default();
+        sampler.set_filter(ImageFilter::Nearest);
+        sampler.set_mipmap_mode(MipmapMode::Nearest);
+        sampler
+    }
+
+    /// Returns the filter used when the image is drawn smaller than its size.
+    pub fn min_filter(&self) -> ImageFilter {
+        self.min_filter
+    }
+
+    /// Returns the filter used when the image is drawn larger than its size.
+    pub fn mag_filter(&self) -> ImageFilter {
+        self.mag_filter
+    }
+
+    /// Sets the filter used when the image is drawn smaller than its size.
+    pub fn set_min_filter(&mut self, filter: ImageFilter) {
+        self.min_filter = filter;
+    }
+
+    /// Sets the filter used when the image is drawn larger than its size.
+    pub fn set_mag_filter(&mut self, filter: ImageFilter) {
+        self.mag_filter = filter;
+    }
+
+    /// Sets both the minification and the magnification filter.
+    pub fn set_filter(&mut self, filter: ImageFilter) {
+        self.min_filter = filter;
+        self.mag_filter = filter;
+    }
+
+    /// Returns how mipmap levels are sampled.
+    pub fn mipmap_mode(&self) -> MipmapMode {
+        self.mipmap_mode
+    }
+
+    /// Sets how mipmap levels are sampled.
+    pub fn set_mipmap_mode(&mut self, mode: MipmapMode) {
+        self.mipmap_mode = mode;
+    }
+
+    /// Returns the wrap mode in X direction.
+    pub fn wrap_x(&self) -> WrapMode {
+        self.wrap_x
+    }
+
+    /// Returns the wrap mode in Y direction.
+    pub fn wrap_y(&self) -> WrapMode {
+        self.wrap_y
+    }
+
+    /// Sets the wrap modes in X and Y direction.
+    pub fn set_wrap(&mut self, wrap_x: WrapMode, wrap_y: WrapMode) {
+        self.wrap_x = wrap_x;
+        self.wrap_y = wrap_y;
+    }
+
+    /// Returns true if the image is filtered with a bicubic kernel in the shader.
+    pub fn bicubic(&self) -> bool {
+        self.bicubic
+    }
+
+    /// Enables filtering with a cubic B-spline kernel in the shader, giving smoother results than
+    /// linear filtering when images are scaled. The kernel is built from linear samples,
+    /// so the filters are treated as `Linear` while it's enabled. Ignored for YUV images.
+    pub fn set_bicubic(&mut self, bicubic: bool) {
+        self.bicubic = bicubic;
+    }
+}
+
+/// Matrix used to convert YUV images to RGB: `Bt601`, `Bt709` (default).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum YuvMatrix {
+    /// ITU-R BT.601, used by standard definition video.
+    Bt601,
+    /// ITU-R BT.709, used by high definition video.
+    Bt709,
+}
+
+impl Default for YuvMatrix {
+    fn default() -> Self {
+        Self::Bt709
+    }
+}
+
+/// Value range of YUV samples: `Limited` (default), `Full`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum YuvRange {
+    /// Luma in [16..235] and chroma in [16..240], also known as video or TV range.
+    Limited,
+    /// All components use [0..255], also known as PC or JPEG range.
+    Full,
+}
+
+impl Default for YuvRange {
+    fn default() -> Self {
+        Self::Limited
+    }
+}
+
+/// Returns the size of the chroma planes of a YUV image with the provided luma size.
+pub(crate) fn chroma_size(width: usize, height: usize) -> (usize, usize) {
+    ((width + 1) / 2, (height + 1) / 2)
+}
+
+/// Checks that the chroma planes of a YUV source have the size implied by its luma plane and fit
+/// in the image when placed at `x`, `y`. Chroma samples cover 2x2 pixels, so the offsets have to
+/// be even.
+pub(crate) fn validate_chroma_planes(src: &ImageSource, info: &ImageInfo, x: usize, y: usize) -> Result<(), ErrorKind> {
+    let (luma, planes) = match src {
+        ImageSource::Nv12 { y: luma, uv } => (luma, [(uv.width(), uv.height()); 2]),
+        ImageSource::I420 { y: luma, u, v } => (luma, [(u.width(), u.height()), (v.width(), v.height())]),
+        _ => return Ok(()),
+    };
+
+    if x & 1 != 0 || y & 1 != 0 {
+        return Err(ErrorKind::ImageUpdateWithOddOffset);
+    }
+
+    let (plane_width, plane_height) = chroma_size(luma.width(), luma.height());
+
+    if planes.iter().any(|&plane| plane != (plane_width, plane_height)) {
+        return Err(ErrorKind::ImageUpdateWithInvalidPlanes);
+    }
+
+    let (width, height) = chroma_size(info.width(), info.height());
+
+    if x / 2 + plane_width > width || y / 2 + plane_height > height {
+        return Err(ErrorKind::ImageUpdateOutOfBounds);
+    }
+
+    Ok(())
+}
+
 /// Image source
 #[derive(Copy, Clone, Debug)]
 #[non_exhaustive]
@@ -50,6 +300,21 @@ pub enum ImageSource<'a> {
     Rgb(ImgRef<'a, RGB8>),
     Rgba(ImgRef<'a, RGBA8>),
     Gray(ImgRef<'a, GRAY8>),
+    GrayAlpha(ImgRef<'a, GRAYA8>),
+    Bgra(ImgRef<'a, BGRA8>),
+    /// Half precision floats (IEEE 754 binary16), each component stored as its raw bits.
+    Rgba16F(ImgRef<'a, RGBA<u16>>),
+    /// Full resolution luma plane and a half resolution plane of interleaved U (gray) and V (alpha) samples.
+    Nv12 {
+        y: ImgRef<'a, GRAY8>,
+        uv: ImgRef<'a, GRAYA8>,
+    },
+    /// Full resolution luma plane and half resolution U and V planes.
+    I420 {
+        y: ImgRef<'a, GRAY8>,
+        u: ImgRef<'a, GRAY8>,
+        v: ImgRef<'a, GRAY8>,
+    },
     #[cfg(target_arch = "wasm32")]
     HtmlImageElement(&'a web_sys::HtmlImageElement),
 }
@@ -61,6 +326,11 @@ impl ImageSource<'_> {
             Self::Rgb(_) => PixelFormat::Rgb8,
             Self::Rgba(_) => PixelFormat::Rgba8,
             Self::Gray(_) => PixelFormat::Gray8,
+            Self::GrayAlpha(_) => PixelFormat::GrayAlpha8,
+            Self::Bgra(_) => PixelFormat::Bgra8,
+            Self::Rgba16F(_) => PixelFormat::Rgba16F,
+            Self::Nv12 { .. } => PixelFormat::Nv12,
+            Self::I420 { .. } => PixelFormat::I420,
             #[cfg(target_arch = "wasm32")]
             Self::HtmlImageElement(_) => PixelFormat::Rgba8,
         }
@@ -74,6 +344,11 @@ impl ImageSource<'_> {
             Self::Rgb(imgref) => (imgref.width(), imgref.height()),
             Self::Rgba(imgref) => (imgref.width(), imgref.height()),
             Self::Gray(imgref) => (imgref.width(), imgref.height()),
+            Self::GrayAlpha(imgref) => (imgref.width(), imgref.height()),
+            Self::Bgra(imgref) => (imgref.width(), imgref.height()),
+            Self::Rgba16F(imgref) => (imgref.width(), imgref.height()),
+            Self::Nv12 { y, .. } => (y.width(), y.height()),
+            Self::I420 { y, .. } => (y.width(), y.height()),
             #[cfg(target_arch = "wasm32")]
             Self::HtmlImageElement(element) => (element.width() as usize, element.height() as usize),
         }
@@ -98,6 +373,18 @@ impl<'a> From<ImgRef<'a, GRAY8>> for ImageSource<'a> {
     }
 }
 
+impl<'a> From<ImgRef<'a, GRAYA8>> for ImageSource<'a> {
+    fn from(src: ImgRef<'a, GRAYA8>) -> Self {
+        Self::GrayAlpha(src)
+    }
+}
+
+impl<'a> From<ImgRef<'a, BGRA8>> for ImageSource<'a> {
+    fn from(src: ImgRef<'a, BGRA8>) -> Self {
+        Self::Bgra(src)
+    }
+}
+
 #[cfg(target_arch = "wasm32")]
 impl<'a> From<&'a web_sys::HtmlImageElement> for ImageSource<'a> {
     fn from(src: &'a web_sys::HtmlImageElement) -> Self {
@@ -117,6 +404,18 @@ impl<'a> TryFrom<&'a DynamicImage> for ImageSource<'a> {
 
                 Ok(Self::from(src))
             }
+            ::image::DynamicImage::ImageLumaA8(img) => {
+                let src: Img<&[GRAYA8]> =
+                    Img::new(img.as_ref().as_pixels(), img.width() as usize, img.height() as usize);
+
+                Ok(Self::from(src))
+            }
+            ::image::DynamicImage::ImageBgra8(img) => {
+                let src: Img<&[BGRA8]> =
+                    Img::new(img.as_ref().as_pixels(), img.width() as usize, img.height() as usize);
+
+                Ok(Self::from(src))
+            }
             ::image::DynamicImage::ImageRgb8(img) => {
                 let src = Img::new(img.as_ref().as_rgb(), img.width() as usize, img.height() as usize);
                 Ok(Self::from(src))
@@ -139,6 +438,7 @@ pub struct ImageInfo {
     width: usize,
     height: usize,
     format: PixelFormat,
+    sampler: ImageSampler,
 }
 
 impl ImageInfo {
@@ -148,6 +448,7 @@ impl ImageInfo {
             width,
             height,
             format,
+            sampler: ImageSampler::default(),
         }
     }
 
@@ -163,6 +464,10 @@ impl ImageInfo {
         self.flags
     }
 
+    pub(crate) fn set_flags(&mut self, flags: ImageFlags) {
+        self.flags = flags;
+    }
+
     /// Image width in pixels
     pub fn width(&self) -> usize {
         self.width
@@ -181,9 +486,66 @@ impl ImageInfo {
     pub fn set_format(&mut self, format: PixelFormat) {
         self.format = format;
     }
+
+    /// Image sampling options
+    pub fn sampler(&self) -> ImageSampler {
+        self.sampler
+    }
+
+    pub fn set_sampler(&mut self, sampler: ImageSampler) {
+        self.sampler = sampler;
+    }
+
+    /// Approximate amount of GPU memory used by the image in bytes, including mipmaps and chroma planes
+    pub fn memory_size(&self) -> usize {
+        let mut size = self.width * self.height * self.format.bytes_per_pixel();
+
+        if self.format.is_yuv() {
+            let (width, height) = chroma_size(self.width, self.height);
+            size += width * height * 2;
+        }
+
+        if self.flags.contains(ImageFlags::GENERATE_MIPMAPS) {
+            size += size / 3;
+        }
+
+        size
+    }
+
+    // First texture row of a region given top down. Images with FLIP_Y are drawn flipped, so their rows are stored
+    // bottom up in the texture.
+    pub(crate) fn texture_y(&self, y: usize, height: usize) -> usize {
+        if self.flags.contains(ImageFlags::FLIP_Y) {
+            self.height - y - height
+        } else {
+            y
+        }
+    }
+}
+
+// Textures owned by the application don't count against the memory budget
+fn budgeted_size(info: &ImageInfo) -> usize {
+    if info.flags().contains(ImageFlags::EXTERNAL) {
+        0
+    } else {
+        info.memory_size()
+    }
+}
+
+struct StoredImage<T> {
+    info: ImageInfo,
+    image: T,
+    last_used: u64,
 }
 
-pub struct ImageStore<T>(Arena<(ImageInfo, T)>);
+/// The images of a canvas, passed to renderers to look up the textures referenced by commands.
+pub struct ImageStore<T> {
+    images: Arena<StoredImage<T>>,
+    memory_usage: usize,
+    memory_budget: Option<usize>,
+    eviction_callback: Option<Box<dyn FnMut(ImageId) + Send>>,
+    frame: u64,
+}
 
 impl<T> Default for ImageStore<T> {
     fn default() -> Self {
@@ -192,29 +554,61 @@ impl<T> Default for ImageStore<T> {
 }
 
 impl<T> ImageStore<T> {
-    pub fn new() -> Self {
-        Self(Arena::new())
+    pub(crate) fn new() -> Self {
+        Self {
+            images: Arena::new(),
+            memory_usage: 0,
+            memory_budget: None,
+            eviction_callback: None,
+            frame: 0,
+        }
     }
 
-    pub fn alloc<R: Renderer<Image = T>>(&mut self, renderer: &mut R, info: ImageInfo) -> Result<ImageId, ErrorKind> {
+    pub(crate) fn alloc<R: Renderer<Image = T>>(
+        &mut self,
+        renderer: &mut R,
+        info: ImageInfo,
+    ) -> Result<ImageId, ErrorKind> {
+        // Only imported textures are external
+        if info.flags().contains(ImageFlags::EXTERNAL) {
+            return Err(ErrorKind::InvalidImageFlags);
+        }
+
         let image = renderer.alloc_image(info)?;
 
-        Ok(ImageId(self.0.insert((info, image))))
+        Ok(self.import(info, image))
+    }
+
+    /// Adds an image created outside of the renderer, e.g. from an imported texture.
+    pub(crate) fn import(&mut self, info: ImageInfo, image: T) -> ImageId {
+        self.memory_usage += budgeted_size(&info);
+
+        ImageId(self.images.insert(StoredImage {
+            info,
+            image,
+            last_used: self.frame,
+        }))
     }
 
     ///
     /// Reallocates the image without changing the id.
     ///
-    pub fn realloc<R: Renderer<Image = T>>(
+    pub(crate) fn realloc<R: Renderer<Image = T>>(
         &mut self,
         renderer: &mut R,
         id: ImageId,
         info: ImageInfo,
     ) -> Result<(), ErrorKind> {
-        if let Some(old) = self.0.get_mut(id.0) {
+        if let Some(old) = self.images.get_mut(id.0) {
+            // External textures can't be replaced by one owned by the canvas and vice versa
+            if (old.info.flags() | info.flags()).contains(ImageFlags::EXTERNAL) {
+                return Err(ErrorKind::InvalidImageFlags);
+            }
+
             let new = renderer.alloc_image(info)?;
-            old.0 = info;
-            old.1 = new;
+            self.memory_usage = self.memory_usage - budgeted_size(&old.info) + budgeted_size(&info);
+            old.info = info;
+            old.image = new;
             Ok(())
         } else {
             Err(ErrorKind::ImageIdNotFound)
@@ -222,14 +616,14 @@ impl<T> ImageStore<T> {
     }
 
     pub fn get(&self, id: ImageId) -> Option<&T> {
-        self.0.get(id.0).map(|inner| &inner.1)
+        self.images.get(id.0).map(|inner| &inner.image)
     }
 
-    pub fn get_mut(&mut self, id: ImageId) -> Option<&mut T> {
-        self.0.get_mut(id.0).map(|inner| &mut inner.1)
+    pub(crate) fn get_mut(&mut self, id: ImageId) -> Option<&mut T> {
+        self.images.get_mut(id.0).map(|inner| &mut inner.image)
     }
 
-    pub fn update<R: Renderer<Image = T>>(
+    pub(crate) fn update<R: Renderer<Image = T>>(
         &mut self,
         renderer: &mut R,
         id: ImageId,
@@ -237,8 +631,23 @@ impl<T> ImageStore<T> {
         x: usize,
         y: usize,
     ) -> Result<(), ErrorKind> {
-        if let Some(image) = self.0.get_mut(id.0) {
-            renderer.update_image(&mut image.1, data, x, y)?;
+        if let Some(image) = self.images.get_mut(id.0) {
+            renderer.update_image(&mut image.image, data, x, y)?;
+            Ok(())
+        } else {
+            Err(ErrorKind::ImageIdNotFound)
+        }
+    }
+
+    pub(crate) fn set_sampler<R: Renderer<Image = T>>(
+        &mut self,
+        renderer: &mut R,
+        id: ImageId,
+        sampler: ImageSampler,
+    ) -> Result<(), ErrorKind> {
+        if let Some(image) = self.images.get_mut(id.0) {
+            renderer.set_image_sampler(&mut image.image, sampler);
+            image.info.set_sampler(sampler);
             Ok(())
         } else {
             Err(ErrorKind::ImageIdNotFound)
@@ -246,18 +655,99 @@ impl<T> ImageStore<T> {
     }
 
     pub fn info(&self, id: ImageId) -> Option<ImageInfo> {
-        self.0.get(id.0).map(|inner| inner.0)
+        self.images.get(id.0).map(|inner| inner.info)
+    }
+
+    pub(crate) fn remove<R: Renderer<Image = T>>(&mut self, renderer: &mut R, id: ImageId) {
+        if let Some(image) = self.images.remove(id.0) {
+            self.memory_usage -= budgeted_size(&image.info);
+            renderer.delete_image(image.image);
+        }
     }
 
-    pub fn remove<R: Renderer<Image = T>>(&mut self, renderer: &mut R, id: ImageId) {
-        if let Some(image) = self.0.remove(id.0) {
-            renderer.delete_image(image.1);
+    pub(crate) fn clear<R: Renderer<Image = T>>(&mut self, renderer: &mut R) {
+        for (_idx, image) in self.images.drain() {
+            renderer.delete_image(image.image);
         }
+
+        self.memory_usage = 0;
+    }
+
+    /// Total amount of GPU memory used by all images in bytes, not counting external textures.
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage
+    }
+
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.memory_budget
+    }
+
+    pub(crate) fn set_memory_budget(&mut self, budget: Option<usize>) {
+        self.memory_budget = budget;
+    }
+
+    pub(crate) fn set_eviction_callback(&mut self, callback: Option<Box<dyn FnMut(ImageId) + Send>>) {
+        self.eviction_callback = callback;
     }
 
-    pub fn clear<R: Renderer<Image = T>>(&mut self, renderer: &mut R) {
-        for (_idx, image) in self.0.drain() {
-            renderer.delete_image(image.1);
+    /// Marks the image as drawn in the current frame.
+    pub(crate) fn touch(&mut self, id: ImageId) {
+        if let Some(image) = self.images.get_mut(id.0) {
+            image.last_used = self.frame;
         }
     }
+
+    /// Ends the current frame. When the memory usage is over budget, purgeable images that were not
+    /// drawn in this frame are evicted, least recently drawn first.
+    pub(crate) fn end_frame<R: Renderer<Image = T>>(&mut self, renderer: &mut R) {
+        if let Some(budget) = self.memory_budget {
+            if self.memory_usage > budget {
+                let frame = self.frame;
+
+                let mut candidates: Vec<(u64, ImageId)> = self
+                    .images
+                    .iter()
+                    .filter(|(_, image)| {
+                        image.info.flags.contains(ImageFlags::PURGEABLE)
+                            && !image.info.flags.contains(ImageFlags::EXTERNAL)
+                            && image.last_used < frame
+                    })
+                    .map(|(idx, image)| (image.last_used, ImageId(idx)))
+                    .collect();
+
+                candidates.sort();
+
+                for (_, id) in candidates {
+                    if self.memory_usage <= budget {
+                        break;
+                    }
+
+                    self.remove(renderer, id);
+
+                    if let Some(callback) = self.eviction_callback.as_mut() {
+                        callback(id);
+                    }
+                }
+            }
+        }
+
+        self.frame += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texture_rows() {
+        let uploaded = ImageInfo::new(ImageFlags::empty(), 4, 8, PixelFormat::Rgba8);
+        assert_eq!(uploaded.texture_y(0, 2), 0);
+        assert_eq!(uploaded.texture_y(5, 3), 5);
+
+        let flipped = ImageInfo::new(ImageFlags::FLIP_Y, 4, 8, PixelFormat::Rgba8);
+        assert_eq!(flipped.texture_y(0, 2), 6);
+        assert_eq!(flipped.texture_y(5, 3), 0);
+        assert_eq!(flipped.texture_y(0, 8), 0);
+    }
 }
@@ -0,0 +1,157 @@
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::path::PathBuf;
+use std::sync::mpsc::{
+    self,
+    Receiver,
+    Sender,
+    TryRecvError,
+};
+use std::thread;
+
+use ::image::DynamicImage;
+
+use crate::{
+    DynamicImageExt,
+    ErrorKind,
+    ImageFlags,
+    ImageSource,
+};
+
+type DecodeResult = Result<DynamicImage, ErrorKind>;
+
+pub(crate) enum DecodeSource {
+    File(PathBuf),
+    Memory(Vec<u8>),
+}
+
+impl DecodeSource {
+    fn decode(self) -> DecodeResult {
+        let image = match self {
+            Self::File(path) => ::image::open(path)?,
+            Self::Memory(data) => ::image::load_from_memory(&data)?,
+        };
+
+        let image = image.convert_rgb_if_needed();
+
+        // We're off the render thread here, so formats the renderer can't take get converted now
+        if ImageSource::try_from(&image).is_err() {
+            return Ok(DynamicImage::ImageRgba8(image.to_rgba8()));
+        }
+
+        Ok(image)
+    }
+}
+
+struct DecodeJob {
+    source: DecodeSource,
+    sender: Sender<DecodeResult>,
+}
+
+impl DecodeJob {
+    fn run(self) {
+        // The pending image may have been dropped already, nobody is interested in the result then
+        let _ = self.sender.send(self.source.decode());
+    }
+}
+
+/// Decodes images on a worker thread that's started on first use and
+/// stops once the decoder (and with it the canvas) is dropped.
+#[derive(Default)]
+pub(crate) struct ImageDecoder {
+    jobs: Option<Sender<DecodeJob>>,
+}
+
+impl ImageDecoder {
+    pub fn decode(&mut self, source: DecodeSource, flags: ImageFlags) -> PendingImage {
+        let (sender, receiver) = mpsc::channel();
+        let job = DecodeJob { source, sender };
+
+        let job = match self.worker() {
+            Some(jobs) => jobs.send(job).err().map(|err| err.0),
+            None => Some(job),
+        };
+
+        // Either threads are not available on this platform or the worker died
+        // in a decoder panic. Decode in place, the result is the same, just slower.
+        if let Some(job) = job {
+            self.jobs = None;
+            job.run();
+        }
+
+        PendingImage {
+            flags,
+            receiver,
+            decoded: RefCell::new(None),
+        }
+    }
+
+    fn worker(&mut self) -> Option<&Sender<DecodeJob>> {
+        if self.jobs.is_none() {
+            let (sender, receiver) = mpsc::channel::<DecodeJob>();
+
+            let spawned = thread::Builder::new()
+                .name("femtovg image decoder".to_string())
+                .spawn(move || {
+                    for job in receiver {
+                        job.run();
+                    }
+                });
+
+            if spawned.is_ok() {
+                self.jobs = Some(sender);
+            }
+        }
+
+        self.jobs.as_ref()
+    }
+}
+
+/// An image that is being decoded in the background.
+///
+/// Returned by [`Canvas::load_image_file_async`](struct.Canvas.html#method.load_image_file_async)
+/// and [`Canvas::load_image_mem_async`](struct.Canvas.html#method.load_image_mem_async). Once
+/// [`is_ready`](#method.is_ready) returns true, pass it to
+/// [`Canvas::upload_pending_image`](struct.Canvas.html#method.upload_pending_image) on the render
+/// thread to get an `ImageId`.
+pub struct PendingImage {
+    flags: ImageFlags,
+    receiver: Receiver<DecodeResult>,
+    decoded: RefCell<Option<DecodeResult>>,
+}
+
+impl PendingImage {
+    /// Returns true if decoding has finished, successfully or not. Never blocks.
+    pub fn is_ready(&self) -> bool {
+        if self.decoded.borrow().is_some() {
+            return true;
+        }
+
+        let result = match self.receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => Err(decoder_failed()),
+        };
+
+        *self.decoded.borrow_mut() = Some(result);
+
+        true
+    }
+
+    /// The flags the image will be created with.
+    pub fn flags(&self) -> ImageFlags {
+        self.flags
+    }
+
+    /// Blocks until decoding has finished and returns the decoded image.
+    pub(crate) fn wait(self) -> DecodeResult {
+        match self.decoded.into_inner() {
+            Some(result) => result,
+            None => self.receiver.recv().unwrap_or_else(|_| Err(decoder_failed())),
+        }
+    }
+}
+
+fn decoder_failed() -> ErrorKind {
+    ErrorKind::GeneralError("image decoder stopped before finishing".to_string())
+}
@@ -35,12 +35,24 @@ pub use error::ErrorKind;
 pub use text::{
     Align,
     Baseline,
+    FontFeature,
     FontId,
     FontMetrics,
+    FontQuery,
+    FontStyle,
+    FontVariation,
+    GlyphRendering,
+    LineMetrics,
+    Paragraph,
+    ParagraphStyle,
+    RichText,
+    TextLanguage,
     TextMetrics,
+    TextSpan,
 };
 
 use text::{
+    AtlasKind,
     RenderMode,
     TextContext,
 };
@@ -48,11 +60,27 @@ use text::{
 mod image;
 use crate::image::ImageStore;
 pub use crate::image::{
+    ImageFilter,
     ImageFlags,
     ImageId,
     ImageInfo,
+    ImageSampler,
     ImageSource,
+    MipmapMode,
     PixelFormat,
+    WrapMode,
+    YuvMatrix,
+    YuvRange,
+};
+
+#[cfg(feature = "image-loading")]
+mod image_decoder;
+#[cfg(feature = "image-loading")]
+pub use image_decoder::PendingImage;
+#[cfg(feature = "image-loading")]
+use image_decoder::{
+    DecodeSource,
+    ImageDecoder,
 };
 
 mod color;
@@ -76,13 +104,19 @@ use renderer::{
 pub(crate) mod geometry;
 use geometry::*;
 pub use geometry::{
+    Point,
+    Rect,
     Size,
     Transform2D,
 };
 
 mod paint;
-pub use paint::Paint;
 use paint::PaintFlavor;
+pub use paint::{
+    Mask,
+    MaskMode,
+    Paint,
+};
 
 mod path;
 use path::Convexity;
@@ -94,6 +128,28 @@ pub use path::{
 mod gradient_store;
 use gradient_store::GradientStore;
 
+mod nine_slice;
+pub use nine_slice::{
+    NineSlice,
+    NineSliceMode,
+};
+
+mod sprite_batch;
+pub use sprite_batch::SpriteBatch;
+
+#[cfg(feature = "svg")]
+mod svg;
+#[cfg(feature = "svg")]
+pub use svg::SvgDocument;
+
+#[cfg(feature = "lottie")]
+mod lottie;
+#[cfg(feature = "lottie")]
+pub use lottie::LottieAnimation;
+
+mod color_matrix;
+pub use color_matrix::ColorMatrix;
+
 /// The fill rule used when filling paths: `EvenOdd`, `NonZero` (default).
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -274,6 +330,16 @@ impl Default for LineJoin {
     }
 }
 
+// How text quads are masked by their glyph atlas
+#[derive(Copy, Clone, Debug)]
+enum GlyphMask {
+    Coverage,
+    // The screen pixels covered by the range of distances of the field
+    DistanceField(f32),
+    // Coverage of each color channel
+    Subpixel,
+}
+
 #[derive(Copy, Clone, Debug)]
 struct State {
     composite_operation: CompositeOperationState,
@@ -297,6 +363,7 @@ pub trait DynamicImageExt {
     fn convert_rgb_if_needed(self) -> Self;
 }
 
+#[cfg(feature = "image-loading")]
 impl DynamicImageExt for ::image::DynamicImage {
     fn convert_rgb_if_needed(self) -> Self {
         #[cfg(not(feature = "convert-rgb"))]
@@ -329,6 +396,9 @@ pub struct Canvas<T: Renderer> {
     tess_tol: f32,
     dist_tol: f32,
     gradients: GradientStore,
+    scratch_images: Vec<ImageId>,
+    #[cfg(feature = "image-loading")]
+    image_decoder: ImageDecoder,
 }
 
 impl<T> Canvas<T>
@@ -352,6 +422,9 @@ where
             tess_tol: 0.25,
             dist_tol: 0.01,
             gradients: GradientStore::new(),
+            scratch_images: Vec::new(),
+            #[cfg(feature = "image-loading")]
+            image_decoder: Default::default(),
         };
 
         canvas.save();
@@ -404,15 +477,28 @@ where
 
     /// Tells the renderer to execute all drawing commands and clears the current internal state
     ///
-    /// Call this at the end of each frame.
+    /// Call this at the end of each frame, followed by end_frame().
     pub fn flush(&mut self, target: Option<&T::Target>) {
         self.renderer.render(target, &self.images, &self.verts, &self.commands);
         self.commands.clear();
         self.verts.clear();
+
+        for id in self.scratch_images.drain(..) {
+            self.images.remove(&mut self.renderer, id);
+        }
+
         self.gradients
             .release_old_gradients(&mut self.images, &mut self.renderer);
     }
 
+    /// Ends the current frame. A frame may be flushed several times, e.g. once per render target.
+    ///
+    /// When the image memory is over budget, purgeable images that were neither drawn nor rendered into
+    /// since the previous end_frame() are evicted, see set_image_memory_budget().
+    pub fn end_frame(&mut self) {
+        self.images.end_frame(&mut self.renderer);
+    }
+
     pub fn screenshot(&mut self, target: Option<&T::Target>) -> Result<ImgVec<RGBA8>, ErrorKind> {
         self.flush(target);
         self.renderer.screenshot()
@@ -493,13 +579,46 @@ where
 
     /// Sets a new render target. All drawing operations after this call will happen on the provided render target
     pub fn set_render_target(&mut self, target: RenderTarget) {
+        if let RenderTarget::Image(id) = target {
+            self.images.touch(id);
+        }
+
         if self.current_render_target != target {
             self.append_cmd(Command::new(CommandType::SetRenderTarget(target)));
             self.current_render_target = target;
         }
     }
 
+    // Creates an image the size of the current render target to render into, e.g. clip coverage.
+    // It's deleted once the commands referencing it were rendered on the next flush.
+    #[cfg(any(feature = "svg", feature = "lottie"))]
+    pub(crate) fn create_scratch_image(&mut self, mut flags: ImageFlags) -> Result<ImageId, ErrorKind> {
+        let width = self.width().ceil() as usize;
+        let height = self.height().ceil() as usize;
+
+        if self.renderer.flip_y() {
+            flags |= ImageFlags::FLIP_Y;
+        }
+
+        let image = self.create_image_empty(width, height, PixelFormat::Rgba8, flags)?;
+        self.scratch_images.push(image);
+
+        Ok(image)
+    }
+
+    // A mask covering the whole render target regardless of the current transform
+    #[cfg(any(feature = "svg", feature = "lottie"))]
+    pub(crate) fn device_mask(&self, image: ImageId, mode: MaskMode) -> Mask {
+        let mut mask = Mask::new(image, mode, 0.0, 0.0, self.width(), self.height());
+        mask.set_transform(self.transform().inversed());
+        mask
+    }
+
     fn append_cmd(&mut self, cmd: Command) {
+        for id in cmd.image.iter().chain(cmd.alpha_mask.iter()) {
+            self.images.touch(*id);
+        }
+
         self.commands.push(cmd);
     }
 
@@ -548,10 +667,33 @@ where
         format: PixelFormat,
         flags: ImageFlags,
     ) -> Result<(), ErrorKind> {
-        let info = ImageInfo::new(flags, width, height, format);
+        let mut info = ImageInfo::new(flags, width, height, format);
+
+        // Keep the sampling options of the image
+        if let Some(old_info) = self.images.info(id) {
+            info.set_sampler(old_info.sampler());
+        }
+
         self.images.realloc(&mut self.renderer, id, info)
     }
 
+    /// Sets how the image is filtered and wrapped when drawn.
+    pub fn set_image_sampler(&mut self, id: ImageId, sampler: ImageSampler) -> Result<(), ErrorKind> {
+        self.images.set_sampler(&mut self.renderer, id, sampler)
+    }
+
+    /// Regenerates the mipmaps of an image created with `ImageFlags::GENERATE_MIPMAPS`.
+    ///
+    /// Mipmaps are refreshed automatically after `update_image`, `copy_image` and after rendering
+    /// into the image, so this is only needed when the texture was modified by other means, e.g. an
+    /// imported texture. Pending draw commands are not flushed; call `flush()` first if the
+    /// image is being rendered into.
+    pub fn generate_mipmaps(&mut self, id: ImageId) -> Result<(), ErrorKind> {
+        let image = self.images.get(id).ok_or(ErrorKind::ImageIdNotFound)?;
+        self.renderer.generate_mipmaps(image);
+        Ok(())
+    }
+
     pub fn raw_image(&self, id: ImageId) -> Option<&T::Image> {
         self.images.get(id)
     }
@@ -582,6 +724,51 @@ where
         self.create_image(src, flags)
     }
 
+    /// Starts decoding an image file on a background thread.
+    ///
+    /// Unlike `load_image_file` this returns right away. Poll the returned handle with
+    /// `PendingImage::is_ready` and hand it to `upload_pending_image` to create the image.
+    #[cfg(feature = "image-loading")]
+    pub fn load_image_file_async<P: AsRef<FilePath>>(&mut self, filename: P, flags: ImageFlags) -> PendingImage {
+        let path = filename.as_ref().to_path_buf();
+        self.image_decoder.decode(DecodeSource::File(path), flags)
+    }
+
+    /// Starts decoding an image from memory on a background thread.
+    ///
+    /// See `load_image_file_async`.
+    #[cfg(feature = "image-loading")]
+    pub fn load_image_mem_async<D: Into<Vec<u8>>>(&mut self, data: D, flags: ImageFlags) -> PendingImage {
+        self.image_decoder.decode(DecodeSource::Memory(data.into()), flags)
+    }
+
+    /// Uploads an image decoded in the background and returns its handle.
+    ///
+    /// Blocks if decoding hasn't finished yet, check `PendingImage::is_ready` first to avoid that.
+    /// Decoding errors are reported here.
+    ///
+    /// ```no_run
+    /// # use femtovg::{Canvas, ImageFlags, renderer::Void};
+    /// # let mut canvas = Canvas::new(Void).unwrap();
+    /// let mut pending = Some(canvas.load_image_file_async("photo.jpg", ImageFlags::empty()));
+    ///
+    /// // every frame
+    /// if pending.as_ref().map_or(false, |image| image.is_ready()) {
+    ///     let image = canvas.upload_pending_image(pending.take().unwrap());
+    /// }
+    /// ```
+    #[cfg(feature = "image-loading")]
+    pub fn upload_pending_image(&mut self, pending: PendingImage) -> Result<ImageId, ErrorKind> {
+        use std::convert::TryFrom;
+
+        let flags = pending.flags();
+        let image = pending.wait()?;
+
+        let src = ImageSource::try_from(&image)?;
+
+        self.create_image(src, flags)
+    }
+
     /// Updates image data specified by image handle.
     pub fn update_image<'a, S: Into<ImageSource<'a>>>(
         &mut self,
@@ -613,6 +800,206 @@ where
         Ok((info.width(), info.height()))
     }
 
+    /// Returns the approximate amount of GPU memory in bytes used by all images, including
+    /// the ones created internally for gradients and glyph atlases. Imported textures the
+    /// application keeps ownership of (`ImageFlags::EXTERNAL`) are not counted.
+    pub fn image_memory_usage(&self) -> usize {
+        self.images.memory_usage()
+    }
+
+    /// Returns the image memory budget set with set_image_memory_budget().
+    pub fn image_memory_budget(&self) -> Option<usize> {
+        self.images.memory_budget()
+    }
+
+    /// Sets the amount of image memory in bytes the canvas should try to stay within.
+    ///
+    /// When the memory usage is over budget in end_frame(), images created with
+    /// `ImageFlags::PURGEABLE` are deleted, least recently drawn first, until the usage fits
+    /// the budget. Images drawn or rendered into during the ending frame are never evicted. Pass `None` to disable eviction.
+    pub fn set_image_memory_budget(&mut self, budget: Option<usize>) {
+        self.images.set_memory_budget(budget);
+    }
+
+    /// Sets a callback invoked with the id of every image evicted to stay within the image memory budget.
+    ///
+    /// The image is already deleted when the callback runs, so its id must not be used anymore.
+    pub fn set_image_eviction_callback<F: FnMut(ImageId) + Send + 'static>(&mut self, callback: F) {
+        self.images.set_eviction_callback(Some(Box::new(callback)));
+    }
+
+    /// Reads back the pixels of an image, for example after rendering into it with `RenderTarget::Image`.
+    ///
+    /// The rectangle is in the orientation the image is drawn in and is rounded out to whole pixels: rows of
+    /// uploaded images come back in the order they were uploaded, and images with `ImageFlags::FLIP_Y`, such
+    /// as render targets of renderers whose `flip_y()` is true, are flipped back. Drawing commands only execute when the canvas is flushed, so call flush() before
+    /// reading back content that was just drawn.
+    pub fn read_image(&mut self, id: ImageId, rect: Rect) -> Result<ImgVec<RGBA8>, ErrorKind> {
+        let info = self.image_info(id)?;
+
+        let (x, y, width, height) = image_region(info, rect)?;
+
+        self.renderer.read_image(&self.images, id, x, y, width, height)
+    }
+
+    /// Copies a region of the src image into the dst image with its top left corner at `dst_point`,
+    /// without any resampling.
+    ///
+    /// Both images must have the same pixel format and must be distinct. Coordinates are in the orientation
+    /// the images are drawn in, the same as for read_image(), and the destination point is rounded
+    /// to whole pixels. The copy happens immediately, so call flush() before copying content that was just
+    /// drawn. Mipmaps of the dst image are regenerated afterwards.
+    pub fn copy_image(
+        &mut self,
+        src: ImageId,
+        src_rect: Rect,
+        dst: ImageId,
+        dst_point: Point,
+    ) -> Result<(), ErrorKind> {
+        let src_info = self.image_info(src)?;
+        let dst_info = self.image_info(dst)?;
+
+        if src == dst {
+            return Err(ErrorKind::GeneralError(
+                "Source and destination of an image copy must be different images".to_owned(),
+            ));
+        }
+
+        if src_info.format() != dst_info.format() {
+            return Err(ErrorKind::ImageUpdateWithDifferentFormat);
+        }
+
+        let (x, y, width, height) = image_region(src_info, src_rect)?;
+
+        if dst_point.x < 0.0 || dst_point.y < 0.0 {
+            return Err(ErrorKind::ImageUpdateOutOfBounds);
+        }
+
+        let dst_x = dst_point.x.round() as usize;
+        let dst_y = dst_point.y.round() as usize;
+
+        if dst_x + width > dst_info.width() || dst_y + height > dst_info.height() {
+            return Err(ErrorKind::ImageUpdateOutOfBounds);
+        }
+
+        self.renderer
+            .copy_image(&self.images, src, dst, x, y, width, height, dst_x, dst_y)?;
+
+        // Lower mip levels still show the old content
+        self.generate_mipmaps(dst)
+    }
+
+    /// Draws an image as a nine-slice (nine-patch) filling the destination rectangle.
+    ///
+    /// The corners of the image keep their size, while the edges and the center are stretched
+    /// or tiled according to the provided NineSlice. All parts are submitted in a single draw call.
+    pub fn draw_nine_slice(&mut self, id: ImageId, slice: NineSlice, dest: Rect) -> Result<(), ErrorKind> {
+        let info = self.image_info(id)?;
+        let transform = self.state().transform;
+
+        let verts = slice.vertices(&info, dest, &transform);
+
+        if verts.is_empty() {
+            return Ok(());
+        }
+
+        let mut paint = Paint::image(id, 0.0, 0.0, info.width() as f32, info.height() as f32, 0.0, 1.0);
+
+        // Apply global alpha
+        paint.mul_alpha(self.state().alpha);
+
+        self.render_image_triangles(&verts, &paint);
+
+        Ok(())
+    }
+
+    /// Draws all sprites in the batch with a single draw call.
+    ///
+    /// Sprites are transformed by the current transform and clipped by the current scissor.
+    pub fn draw_sprite_batch(&mut self, batch: &SpriteBatch) -> Result<(), ErrorKind> {
+        let info = self.image_info(batch.image())?;
+
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let transform = self.state().transform;
+        let verts = batch.vertices(&info, &transform);
+
+        let mut paint = Paint::image_tint(
+            batch.image(),
+            0.0,
+            0.0,
+            info.width() as f32,
+            info.height() as f32,
+            0.0,
+            batch.tint(),
+        );
+
+        // Apply global alpha
+        paint.mul_alpha(self.state().alpha);
+
+        self.render_image_triangles(&verts, &paint);
+
+        Ok(())
+    }
+
+    /// Draws an SVG document with its top left corner at the origin of the current coordinate
+    /// system, transformed by `transform` followed by the current transform.
+    ///
+    /// Clip paths and groups with opacity are rendered into temporary images the size of the current
+    /// render target, which are released on the next `flush()`.
+    ///
+    /// ```no_run
+    /// # use femtovg::{Canvas, SvgDocument, Transform2D, renderer::Void};
+    /// # let mut canvas = Canvas::new(Void).unwrap();
+    /// let tiger = SvgDocument::from_file("examples/assets/Ghostscript_Tiger.svg").unwrap();
+    ///
+    /// canvas.draw_svg(&tiger, Transform2D::new_translation(200.0, 200.0));
+    /// ```
+    #[cfg(feature = "svg")]
+    pub fn draw_svg(&mut self, svg: &SvgDocument, transform: Transform2D) {
+        svg.draw(self, transform);
+    }
+
+    /// Strokes the outline of every path of an SVG document drawn with the same `transform` that
+    /// contains the point (x, y), given in device coordinates like for
+    /// [contains_point](#method.contains_point). Returns true if any path was hit.
+    ///
+    /// Useful for highlighting the shapes under the mouse cursor. Clip paths are not taken into account.
+    #[cfg(feature = "svg")]
+    pub fn stroke_svg_paths_at(
+        &mut self,
+        svg: &SvgDocument,
+        transform: Transform2D,
+        x: f32,
+        y: f32,
+        paint: Paint,
+    ) -> bool {
+        svg.stroke_paths_at(self, transform, x, y, paint)
+    }
+
+    /// Draws the frame of a Lottie animation shown `time` seconds after its start, with its top
+    /// left corner at the origin of the current coordinate system. Times past the end of the
+    /// animation show the last frame.
+    ///
+    /// Layer masks and track mattes are rendered into temporary images the size of the current
+    /// render target, which are released on the next `flush()`.
+    ///
+    /// ```no_run
+    /// # use femtovg::{Canvas, LottieAnimation, renderer::Void};
+    /// # let mut canvas = Canvas::new(Void).unwrap();
+    /// # let start = std::time::Instant::now();
+    /// let animation = LottieAnimation::from_file("animation.json").unwrap();
+    ///
+    /// let time = start.elapsed().as_secs_f32() % animation.duration();
+    /// canvas.draw_lottie(&animation, time);
+    /// ```
+    #[cfg(feature = "lottie")]
+    pub fn draw_lottie(&mut self, animation: &LottieAnimation, time: f32) {
+        animation.draw(self, time);
+    }
+
     // Transforms
 
     pub fn premultiply_transform(&mut self, transform: Transform2D) {
@@ -837,6 +1224,7 @@ where
         let mut cmd = Command::new(flavor);
         cmd.fill_rule = paint.fill_rule;
         cmd.composite_operation = self.state().composite_operation;
+        cmd.alpha_mask = paint.mask_image();
 
         if let PaintFlavor::Image { id, .. } = paint.flavor {
             cmd.image = Some(id);
@@ -975,6 +1363,7 @@ where
         // GPU command
         let mut cmd = Command::new(flavor);
         cmd.composite_operation = self.state().composite_operation;
+        cmd.alpha_mask = paint.mask_image();
 
         if let PaintFlavor::Image { id, .. } = paint.flavor {
             cmd.image = Some(id);
@@ -1007,6 +1396,10 @@ where
     // Text
 
     /// Adds a font file to the canvas
+    ///
+    /// Color glyphs are drawn instead of the outlines if the font has them: solid colored layers
+    /// from version 0 of the `COLR` table and PNG images from the `sbix` or `CBDT` tables. Images
+    /// need the `png-glyphs` feature, without it the outlines are drawn.
     pub fn add_font<P: AsRef<FilePath>>(&mut self, file_path: P) -> Result<FontId, ErrorKind> {
         self.text_context.add_font_file(file_path)
     }
@@ -1021,6 +1414,64 @@ where
         self.text_context.add_font_dir(dir_path)
     }
 
+    /// Adds an instance of a variable font with fixed values for some of its axes.
+    ///
+    /// The instance is a font of its own, so it can be combined with other fonts in
+    /// [`Paint::set_font`](struct.Paint.html#method.set_font). Variations set on the paint apply on
+    /// top of the instance's ones. Fonts that aren't variable keep their default look.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use femtovg::{Canvas, FontVariation, renderer::Void};
+    /// # let mut canvas = Canvas::new(Void).unwrap();
+    /// let inter = canvas.add_font("Inter.ttf").unwrap();
+    /// let condensed_bold = canvas
+    ///     .add_font_instance(inter, &[FontVariation::new(b"wght", 700.0), FontVariation::new(b"wdth", 75.0)])
+    ///     .unwrap();
+    /// ```
+    pub fn add_font_instance(&mut self, font: FontId, variations: &[FontVariation]) -> Result<FontId, ErrorKind> {
+        self.text_context.add_font_instance(font, variations)
+    }
+
+    /// Indexes the font files in a directory and its subdirectories for [`find_font`](#method.find_font).
+    ///
+    /// Only the table directories and the naming and style tables of the files are read, a font is
+    /// loaded when a query first matches it. Files already added with [`add_font`](#method.add_font)
+    /// or indexed through another directory aren't listed twice.
+    pub fn add_font_search_dir<P: AsRef<FilePath>>(&mut self, dir_path: P) -> Result<(), ErrorKind> {
+        self.text_context.add_font_search_dir(dir_path)
+    }
+
+    /// Indexes the fonts installed on the system for [`find_font`](#method.find_font).
+    ///
+    /// On Linux these are the fonts in `/usr/share/fonts`, `/usr/local/share/fonts` and the fonts
+    /// directories of the user. Directories that don't exist are skipped.
+    pub fn add_system_font_dirs(&mut self) {
+        self.text_context.add_system_font_dirs()
+    }
+
+    /// Returns the font best matching the query, following the CSS font matching rules.
+    ///
+    /// Both the fonts added to the canvas and the fonts in the search directories are considered.
+    /// Returns `ErrorKind::NoFontFound` if none of the query's families is available.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use femtovg::{Canvas, renderer::Void};
+    /// # let mut canvas = Canvas::new(Void).unwrap();
+    /// canvas.add_system_font_dirs();
+    ///
+    /// let bold_italic = canvas.find_font(&"Inter, sans-serif, bold, italic".parse().unwrap()).unwrap();
+    /// ```
+    pub fn find_font(&mut self, query: &FontQuery) -> Result<FontId, ErrorKind> {
+        self.text_context.find_font(query)
+    }
+
+    /// Returns the names of the font families known to [`find_font`](#method.find_font).
+    pub fn font_families(&self) -> Vec<String> {
+        self.text_context.font_families()
+    }
+
     /// Returns information on how the provided text will be drawn with the specified paint.
     pub fn measure_text<S: AsRef<str>>(
         &mut self,
@@ -1120,6 +1571,62 @@ where
         self.draw_text(x, y, text.as_ref(), paint, RenderMode::Stroke)
     }
 
+    /// Breaks text into lines and positions them as described by `style`.
+    ///
+    /// Newlines always start a new line. The returned paragraph is drawn with `paint` by
+    /// [`fill_paragraph`](#method.fill_paragraph) and [`stroke_paragraph`](#method.stroke_paragraph).
+    pub fn layout_paragraph<S: AsRef<str>>(
+        &mut self,
+        text: S,
+        style: &ParagraphStyle,
+        paint: Paint,
+    ) -> Result<Paragraph, ErrorKind> {
+        let mut rich_text = RichText::new();
+        rich_text.push(text, paint);
+
+        Paragraph::new(self, rich_text, style)
+    }
+
+    /// Breaks text made of differently styled spans into lines and positions them as described
+    /// by `style`. Each span is drawn with its own paint.
+    ///
+    /// ```
+    /// # use femtovg::{Canvas, Color, Paint, ParagraphStyle, RichText, renderer::Void};
+    /// # let mut canvas = Canvas::new(Void).unwrap();
+    /// # let regular_font = canvas.add_font("examples/assets/Roboto-Regular.ttf").unwrap();
+    /// # let bold_font = canvas.add_font("examples/assets/Roboto-Bold.ttf").unwrap();
+    /// let mut regular = Paint::color(Color::black());
+    /// regular.set_font(&[regular_font]);
+    ///
+    /// let mut bold = regular;
+    /// bold.set_font(&[bold_font]);
+    ///
+    /// let mut link = regular;
+    /// link.set_color(Color::rgb(0, 0, 255));
+    ///
+    /// let mut text = RichText::new();
+    /// text.push("Read the ", regular);
+    /// text.push("full", bold);
+    /// text.push(" ", regular);
+    /// text.push("release notes", link);
+    ///
+    /// let paragraph = canvas.layout_rich_text(&text, &ParagraphStyle::new()).unwrap();
+    /// canvas.fill_paragraph(10.0, 10.0, &paragraph).unwrap();
+    /// ```
+    pub fn layout_rich_text(&mut self, text: &RichText, style: &ParagraphStyle) -> Result<Paragraph, ErrorKind> {
+        Paragraph::new(self, text.clone(), style)
+    }
+
+    /// Fills a paragraph with its top left corner at x, y.
+    pub fn fill_paragraph(&mut self, x: f32, y: f32, paragraph: &Paragraph) -> Result<(), ErrorKind> {
+        paragraph.draw(self, x, y, RenderMode::Fill)
+    }
+
+    /// Strokes a paragraph with its top left corner at x, y.
+    pub fn stroke_paragraph(&mut self, x: f32, y: f32, paragraph: &Paragraph) -> Result<(), ErrorKind> {
+        paragraph.draw(self, x, y, RenderMode::Stroke)
+    }
+
     // Private
 
     fn transform_text_paint(&self, paint: &mut Paint) {
@@ -1137,7 +1644,6 @@ where
         mut paint: Paint,
         render_mode: RenderMode,
     ) -> Result<TextMetrics, ErrorKind> {
-        let transform = self.state().transform;
         let scale = self.font_scale() * self.device_px_ratio;
         let invscale = 1.0 / scale;
 
@@ -1146,60 +1652,170 @@ where
         let mut layout = text::shape(x * scale, y * scale, &mut self.text_context, &paint, text, None)?;
         //let layout = self.layout_text(x, y, text, paint)?;
 
+        self.render_text(&layout, paint, render_mode)?;
+
+        layout.scale(invscale);
+
+        Ok(layout)
+    }
+
+    // Draws shaped text, with glyph positions and paint already scaled to device pixels
+    fn render_text(
+        &mut self,
+        layout: &TextMetrics,
+        mut paint: Paint,
+        render_mode: RenderMode,
+    ) -> Result<(), ErrorKind> {
+        let transform = self.state().transform;
+        let scale = self.font_scale() * self.device_px_ratio;
+        let invscale = 1.0 / scale;
+
         // TODO: Early out if text is outside the canvas bounds, or maybe even check for each character in layout.
 
-        if paint.font_size > 92.0 {
-            text::render_direct(self, &layout, &paint, render_mode, invscale)?;
+        // Subpixels only line up with the screen for upright text, and their channels can only be
+        // blended separately over the destination
+        if paint.glyph_rendering.is_subpixel() {
+            let upright = transform[1] == 0.0 && transform[2] == 0.0 && transform[0] > 0.0 && transform[3] > 0.0;
+            let source_over =
+                self.state().composite_operation == CompositeOperationState::new(CompositeOperation::SourceOver);
+
+            if !upright || !source_over {
+                paint.glyph_rendering = GlyphRendering::Grayscale;
+            }
+        }
+
+        // Distance fields are scaled up instead, they are drawn from the atlas at any size
+        let cmds = if paint.font_size > 92.0 && !text::uses_distance_field(&paint, render_mode) {
+            text::render_direct(self, &layout, &paint, render_mode, invscale)?
         } else {
-            let cmds = text::render_atlas(self, &layout, &paint, render_mode)?;
-
-            for cmd in &cmds {
-                let mut verts = Vec::with_capacity(cmd.quads.len() * 6);
-
-                for quad in &cmd.quads {
-                    let (p0, p1) = transform.transform_point(quad.x0 * invscale, quad.y0 * invscale);
-                    let (p2, p3) = transform.transform_point(quad.x1 * invscale, quad.y0 * invscale);
-                    let (p4, p5) = transform.transform_point(quad.x1 * invscale, quad.y1 * invscale);
-                    let (p6, p7) = transform.transform_point(quad.x0 * invscale, quad.y1 * invscale);
-
-                    verts.push(Vertex::new(p0, p1, quad.s0, quad.t0));
-                    verts.push(Vertex::new(p4, p5, quad.s1, quad.t1));
-                    verts.push(Vertex::new(p2, p3, quad.s1, quad.t0));
-                    verts.push(Vertex::new(p0, p1, quad.s0, quad.t0));
-                    verts.push(Vertex::new(p6, p7, quad.s0, quad.t1));
-                    verts.push(Vertex::new(p4, p5, quad.s1, quad.t1));
-                }
+            text::render_atlas(self, &layout, &paint, render_mode)?
+        };
 
-                paint.set_alpha_mask(Some(cmd.image_id));
+        for cmd in &cmds {
+            let mut verts = Vec::with_capacity(cmd.quads.len() * 6);
+
+            for quad in &cmd.quads {
+                let (p0, p1) = transform.transform_point(quad.x0 * invscale, quad.y0 * invscale);
+                let (p2, p3) = transform.transform_point(quad.x1 * invscale, quad.y0 * invscale);
+                let (p4, p5) = transform.transform_point(quad.x1 * invscale, quad.y1 * invscale);
+                let (p6, p7) = transform.transform_point(quad.x0 * invscale, quad.y1 * invscale);
+
+                verts.push(Vertex::new(p0, p1, quad.s0, quad.t0));
+                verts.push(Vertex::new(p4, p5, quad.s1, quad.t1));
+                verts.push(Vertex::new(p2, p3, quad.s1, quad.t0));
+                verts.push(Vertex::new(p0, p1, quad.s0, quad.t0));
+                verts.push(Vertex::new(p6, p7, quad.s0, quad.t1));
+                verts.push(Vertex::new(p4, p5, quad.s1, quad.t1));
+            }
+
+            if cmd.atlas == AtlasKind::Color {
+                // Color glyphs keep their colors, only the global alpha applies
+                let info = self.image_info(cmd.image_id)?;
+                let mut image_paint = Paint::image(
+                    cmd.image_id,
+                    0.0,
+                    0.0,
+                    info.width() as f32,
+                    info.height() as f32,
+                    0.0,
+                    1.0,
+                );
+                image_paint.mul_alpha(self.state().alpha);
+
+                self.render_image_triangles(&verts, &image_paint);
+                continue;
+            }
+
+            paint.set_alpha_mask(Some(cmd.image_id));
+
+            // Apply global alpha
+            paint.mul_alpha(self.state().alpha);
+
+            let mask = if cmd.atlas == AtlasKind::DistanceField {
+                // Distance fields are antialiased over a pixel on screen
+                GlyphMask::DistanceField(
+                    text::distance_field_range(paint.font_size) * transform.average_scale() * invscale,
+                )
+            } else if paint.glyph_rendering.is_subpixel() {
+                GlyphMask::Subpixel
+            } else {
+                GlyphMask::Coverage
+            };
+
+            self.render_triangles(&verts, &paint, mask);
+        }
+
+        Ok(())
+    }
+
+    // Draws text quads masked by a glyph atlas
+    fn render_triangles(&mut self, verts: &[Vertex], paint: &Paint, mask: GlyphMask) {
+        let scissor = self.state().scissor;
 
-                // Apply global alpha
-                paint.mul_alpha(self.state().alpha);
+        let mut params = Params::new(&self.images, paint, &scissor, 1.0, 1.0, -1.0);
 
-                self.render_triangles(&verts, &paint);
+        match mask {
+            GlyphMask::Coverage => {}
+            GlyphMask::DistanceField(distance_range) => {
+                params.has_mask = 4.0;
+                params.distance_range = distance_range;
+            }
+            GlyphMask::Subpixel => {
+                // Component alpha: the first pass darkens each channel of the destination by its
+                // coverage and the second one adds the color, without needing dual source blending
+                params.has_mask = 5.0;
+
+                let mut cmd = Command::new(CommandType::Triangles { params });
+                cmd.composite_operation =
+                    CompositeOperationState::with_blend_factors(BlendFactor::Zero, BlendFactor::OneMinusSrcColor);
+                cmd.alpha_mask = paint.mask_image();
+                cmd.image = self.paint_image(paint);
+                cmd.triangles_verts = Some((self.verts.len(), verts.len()));
+                self.append_cmd(cmd);
+
+                params.has_mask = 6.0;
             }
         }
 
-        layout.scale(invscale);
+        let mut cmd = Command::new(CommandType::Triangles { params });
+        cmd.composite_operation = match mask {
+            GlyphMask::Subpixel => CompositeOperationState::with_blend_factors(BlendFactor::One, BlendFactor::One),
+            _ => self.state().composite_operation,
+        };
+        cmd.alpha_mask = paint.mask_image();
+        cmd.image = self.paint_image(paint);
 
-        Ok(layout)
+        cmd.triangles_verts = Some((self.verts.len(), verts.len()));
+        self.append_cmd(cmd);
+
+        self.verts.extend_from_slice(verts);
+    }
+
+    // The image sampled by the shader for image and multi stop gradient paints
+    fn paint_image(&mut self, paint: &Paint) -> Option<ImageId> {
+        if let PaintFlavor::Image { id, .. } = paint.flavor {
+            Some(id)
+        } else if let Some(paint::GradientColors::MultiStop { stops }) = paint.flavor.gradient_colors() {
+            self.gradients
+                .lookup_or_add(*stops, &mut self.images, &mut self.renderer)
+                .ok()
+        } else {
+            None
+        }
     }
 
-    fn render_triangles(&mut self, verts: &[Vertex], paint: &Paint) {
+    // Draws triangles textured with the paint's image using the per vertex texture coordinates
+    fn render_image_triangles(&mut self, verts: &[Vertex], paint: &Paint) {
         let scissor = self.state().scissor;
 
-        let params = Params::new(&self.images, paint, &scissor, 1.0, 1.0, -1.0);
+        let mut params = Params::new(&self.images, paint, &scissor, 1.0, 1.0, -1.0);
+        params.shader_type = ShaderType::TexturedTris.to_f32();
 
         let mut cmd = Command::new(CommandType::Triangles { params });
         cmd.composite_operation = self.state().composite_operation;
-        cmd.alpha_mask = paint.alpha_mask();
 
         if let PaintFlavor::Image { id, .. } = paint.flavor {
             cmd.image = Some(id);
-        } else if let Some(paint::GradientColors::MultiStop { stops }) = paint.flavor.gradient_colors() {
-            cmd.image = self
-                .gradients
-                .lookup_or_add(*stops, &mut self.images, &mut self.renderer)
-                .map_or(None, |id| Some(id));
         }
 
         cmd.triangles_verts = Some((self.verts.len(), verts.len()));
@@ -1241,8 +1857,134 @@ where
     }
 }
 
+#[cfg(feature = "glow-renderer")]
+impl Canvas<renderer::OpenGl> {
+    /// Wraps an existing OpenGL texture as an image without copying its contents.
+    ///
+    /// The texture must be a 2D texture created on the renderer's context, matching the size and format
+    /// of the provided info. The texture stays owned by the application: the image gets `ImageFlags::EXTERNAL`,
+    /// is never deleted by the canvas, isn't counted against the image memory budget and is never evicted.
+    ///
+    /// The parameters and contents of the texture are left untouched. Its sampling options are applied with
+    /// a sampler object while drawing, except on WebGL 1 which has none and uses the texture's own parameters.
+    /// The mip chain is owned by the application as well, so `ImageFlags::GENERATE_MIPMAPS` is ignored.
+    pub fn import_gl_texture(
+        &mut self,
+        texture: <glow::Context as glow::HasContext>::Texture,
+        mut info: ImageInfo,
+    ) -> Result<ImageId, ErrorKind> {
+        info.set_flags((info.flags() - ImageFlags::GENERATE_MIPMAPS) | ImageFlags::EXTERNAL);
+
+        let image = self.renderer.import_texture(texture, info)?;
+
+        Ok(self.images.import(info, image))
+    }
+}
+
+#[cfg(feature = "wgpu-renderer")]
+impl Canvas<renderer::WGPU> {
+    /// Wraps an existing wgpu texture as an image without copying its contents.
+    ///
+    /// The texture must have the `SAMPLED` usage and match the size and format of the provided info.
+    /// The canvas shares the texture with the application through the `Arc`, the image gets
+    /// `ImageFlags::EXTERNAL`, isn't counted against the image memory budget and is never evicted.
+    /// The number of mip levels of the texture is unknown, so `ImageFlags::GENERATE_MIPMAPS` is ignored.
+    pub fn import_wgpu_texture(
+        &mut self,
+        texture: std::sync::Arc<wgpu::Texture>,
+        mut info: ImageInfo,
+    ) -> Result<ImageId, ErrorKind> {
+        info.set_flags((info.flags() - ImageFlags::GENERATE_MIPMAPS) | ImageFlags::EXTERNAL);
+
+        let image = self.renderer.import_texture(texture, info)?;
+
+        Ok(self.images.import(info, image))
+    }
+}
+
+// Rounds out the rectangle to whole pixels, making sure it lies within the image
+fn image_region(info: ImageInfo, rect: Rect) -> Result<(usize, usize, usize, usize), ErrorKind> {
+    if info.format().is_yuv() {
+        return Err(ErrorKind::UnsuportedImageFromat);
+    }
+
+    if rect.x < 0.0 || rect.y < 0.0 || rect.w < 0.0 || rect.h < 0.0 {
+        return Err(ErrorKind::ImageUpdateOutOfBounds);
+    }
+
+    let x = rect.x.floor() as usize;
+    let y = rect.y.floor() as usize;
+    let width = (rect.x + rect.w).ceil() as usize - x;
+    let height = (rect.y + rect.h).ceil() as usize - y;
+
+    if x + width > info.width() || y + height > info.height() {
+        return Err(ErrorKind::ImageUpdateOutOfBounds);
+    }
+
+    Ok((x, y, width, height))
+}
+
 impl<T: Renderer> Drop for Canvas<T> {
     fn drop(&mut self) {
         self.images.clear(&mut self.renderer);
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::renderer::Recorder;
+
+    #[test]
+    fn copy_image_region() {
+        let recorder = Recorder::default();
+        let mut canvas = Canvas::new(recorder.clone()).unwrap();
+
+        let src = canvas
+            .create_image_empty(32, 32, PixelFormat::Rgba8, ImageFlags::empty())
+            .unwrap();
+        let dst = canvas
+            .create_image_empty(16, 16, PixelFormat::Rgba8, ImageFlags::empty())
+            .unwrap();
+        let gray = canvas
+            .create_image_empty(16, 16, PixelFormat::Gray8, ImageFlags::empty())
+            .unwrap();
+
+        assert!(canvas
+            .copy_image(src, Rect::new(8.0, 8.0, 16.0, 16.0), dst, Point::new(0.0, 0.0))
+            .is_ok());
+        assert!(canvas
+            .copy_image(src, Rect::new(0.0, 0.0, 8.0, 8.0), dst, Point::new(8.0, 8.0))
+            .is_ok());
+
+        // Out of the bounds of the source or the destination
+        assert!(canvas
+            .copy_image(src, Rect::new(24.0, 0.0, 16.0, 16.0), dst, Point::new(0.0, 0.0))
+            .is_err());
+        assert!(canvas
+            .copy_image(src, Rect::new(0.0, 0.0, 8.0, 8.0), dst, Point::new(12.0, 0.0))
+            .is_err());
+
+        assert!(canvas
+            .copy_image(src, Rect::new(0.0, 0.0, 8.0, 8.0), src, Point::new(16.0, 16.0))
+            .is_err());
+        assert!(canvas
+            .copy_image(src, Rect::new(0.0, 0.0, 8.0, 8.0), gray, Point::new(0.0, 0.0))
+            .is_err());
+        assert!(canvas
+            .copy_image(src, Rect::new(0.0, 0.0, 8.0, 8.0), dst, Point::new(-1.0, 0.0))
+            .is_err());
+
+        // The mip chain of the destination is regenerated after the copy
+        let mipmapped = canvas
+            .create_image_empty(16, 16, PixelFormat::Rgba8, ImageFlags::GENERATE_MIPMAPS)
+            .unwrap();
+        let mipmaps = recorder.0.borrow().mipmaps;
+
+        canvas
+            .copy_image(src, Rect::new(0.0, 0.0, 8.0, 8.0), mipmapped, Point::new(4.0, 4.0))
+            .unwrap();
+        assert_eq!(recorder.0.borrow().mipmaps, mipmaps + 1);
+    }
+}
@@ -0,0 +1,1053 @@
+use std::collections::HashMap;
+use std::path::Path as FilePath;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::{
+    Canvas,
+    Color,
+    CompositeOperation,
+    ErrorKind,
+    FillRule,
+    ImageFlags,
+    ImageId,
+    LineCap,
+    LineJoin,
+    MaskMode,
+    Paint,
+    Path,
+    RenderTarget,
+    Renderer,
+    Transform2D,
+};
+
+mod json;
+use json::{
+    Json,
+    JsonExt,
+};
+
+mod property;
+use property::{
+    Animated,
+    Bezier,
+};
+
+// Limits precomposition nesting and parent chains, which could otherwise be cyclic
+const MAX_DEPTH: usize = 32;
+
+// Length of bezier tangents approximating a quarter circle of radius 1
+const KAPPA: f32 = 0.552_284_8;
+
+/// A Lottie (Bodymovin) animation that can be drawn with
+/// [Canvas::draw_lottie](struct.Canvas.html#method.draw_lottie).
+///
+/// Shape, solid, null and precomposition layers are supported, with parenting, layer masks and
+/// alpha and luma track mattes. Shape layers can contain groups, paths, rectangles, ellipses,
+/// solid and gradient fills and strokes. Image and text layers, trim paths, repeaters, dashes,
+/// expressions, time remapping and effects are ignored.
+///
+/// # Example
+/// ```
+/// use femtovg::{Canvas, LottieAnimation, renderer::Void};
+///
+/// let animation: LottieAnimation = r#"{
+///     "v": "5.7.0", "fr": 30, "ip": 0, "op": 60, "w": 100, "h": 100,
+///     "layers": [{
+///         "ty": 4, "ip": 0, "op": 60, "st": 0,
+///         "ks": { "p": { "a": 0, "k": [50, 50] } },
+///         "shapes": [
+///             { "ty": "el", "p": { "a": 0, "k": [0, 0] }, "s": { "a": 0, "k": [40, 40] } },
+///             { "ty": "fl", "c": { "a": 0, "k": [1, 0, 0, 1] }, "o": { "a": 0, "k": 100 } }
+///         ]
+///     }]
+/// }"#.parse().expect("Cannot parse animation");
+///
+/// assert_eq!(animation.duration(), 2.0);
+///
+/// let mut canvas = Canvas::new(Void).expect("Cannot create canvas");
+/// canvas.set_size(100, 100, 1.0);
+/// canvas.draw_lottie(&animation, 0.5);
+/// ```
+pub struct LottieAnimation {
+    width: f32,
+    height: f32,
+    frame_rate: f32,
+    in_point: f32,
+    out_point: f32,
+    layers: Vec<Layer>,
+}
+
+impl LottieAnimation {
+    /// Parses an animation from Lottie JSON.
+    pub fn from_data(data: &[u8]) -> Result<Self, ErrorKind> {
+        let json = json::parse(data)?;
+
+        let number = |key| {
+            json.get(key)
+                .and_then(Json::as_f32)
+                .ok_or_else(|| ErrorKind::LottieParseError(format!("missing \"{}\"", key)))
+        };
+
+        let width = number("w")?;
+        let height = number("h")?;
+        let frame_rate = number("fr")?;
+        let in_point = number("ip")?;
+        let out_point = number("op")?;
+
+        if frame_rate <= 0.0 || out_point <= in_point {
+            return Err(ErrorKind::LottieParseError(
+                "invalid frame rate or duration".to_string(),
+            ));
+        }
+
+        let mut assets = Assets::new(json.get("assets"));
+        let layers = parse_layers(json.get("layers"), &mut assets, 0);
+
+        Ok(Self {
+            width,
+            height,
+            frame_rate,
+            in_point,
+            out_point,
+            layers,
+        })
+    }
+
+    /// Reads and parses a Lottie JSON file.
+    pub fn from_file<P: AsRef<FilePath>>(path: P) -> Result<Self, ErrorKind> {
+        let data = std::fs::read(path)?;
+        Self::from_data(&data)
+    }
+
+    /// Returns the width of the animation.
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    /// Returns the height of the animation.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// Returns the number of frames per second.
+    pub fn frame_rate(&self) -> f32 {
+        self.frame_rate
+    }
+
+    /// Returns the duration of the animation in seconds.
+    pub fn duration(&self) -> f32 {
+        (self.out_point - self.in_point) / self.frame_rate
+    }
+
+    pub(crate) fn draw<T: Renderer>(&self, canvas: &mut Canvas<T>, time: f32) {
+        // The out point is the first frame that's no longer shown
+        let frame = (self.in_point + time * self.frame_rate)
+            .min(self.out_point - 0.001)
+            .max(self.in_point);
+
+        canvas.save();
+        canvas.intersect_scissor(0.0, 0.0, self.width, self.height);
+        draw_layers(canvas, &self.layers, frame, None);
+        canvas.restore();
+    }
+}
+
+impl FromStr for LottieAnimation {
+    type Err = ErrorKind;
+
+    fn from_str(text: &str) -> Result<Self, ErrorKind> {
+        Self::from_data(text.as_bytes())
+    }
+}
+
+// Layers
+
+struct Layer {
+    index: Option<i64>,
+    parent: Option<i64>,
+    in_point: f32,
+    out_point: f32,
+    start_time: f32,
+    stretch: f32,
+    hidden: bool,
+    matte_source: bool,
+    matte: Option<Matte>,
+    transform: TransformProps,
+    masks: Vec<LayerMask>,
+    content: LayerContent,
+}
+
+enum LayerContent {
+    Empty,
+    Shapes(Vec<Shape>),
+    Solid {
+        color: Color,
+        width: f32,
+        height: f32,
+    },
+    Precomp {
+        layers: Rc<Vec<Layer>>,
+        width: f32,
+        height: f32,
+    },
+}
+
+#[derive(Copy, Clone)]
+enum Matte {
+    Alpha,
+    AlphaInverted,
+    Luma,
+}
+
+struct LayerMask {
+    mode: MaskOp,
+    inverted: bool,
+    shape: Animated<Bezier>,
+    opacity: Animated<Vec<f32>>,
+}
+
+#[derive(Copy, Clone)]
+enum MaskOp {
+    Add,
+    Subtract,
+    Intersect,
+}
+
+impl Layer {
+    fn is_visible(&self, frame: f32) -> bool {
+        !self.hidden && frame >= self.in_point && frame < self.out_point
+    }
+
+    // Converts a frame of the containing composition to the layer's own time
+    fn local_frame(&self, frame: f32) -> f32 {
+        (frame - self.start_time) / self.stretch
+    }
+}
+
+// Precompositions are parsed when first referenced and shared between layers
+struct Assets<'a> {
+    json: HashMap<&'a str, &'a Json>,
+    parsed: HashMap<String, Rc<Vec<Layer>>>,
+}
+
+impl<'a> Assets<'a> {
+    fn new(json: Option<&'a Json>) -> Self {
+        let json = json
+            .and_then(Json::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|asset| Some((asset.get("id")?.as_str()?, asset)))
+            .collect();
+
+        Self {
+            json,
+            parsed: HashMap::new(),
+        }
+    }
+
+    fn layers(&mut self, id: &str, depth: usize) -> Option<Rc<Vec<Layer>>> {
+        if let Some(layers) = self.parsed.get(id) {
+            return Some(layers.clone());
+        }
+
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        let json = *self.json.get(id)?;
+        let layers = Rc::new(parse_layers(json.get("layers"), self, depth + 1));
+        self.parsed.insert(id.to_string(), layers.clone());
+
+        Some(layers)
+    }
+}
+
+fn parse_layers<'a>(json: Option<&'a Json>, assets: &mut Assets<'a>, depth: usize) -> Vec<Layer> {
+    json.and_then(Json::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|layer| parse_layer(layer, assets, depth))
+        .collect()
+}
+
+fn parse_layer<'a>(json: &'a Json, assets: &mut Assets<'a>, depth: usize) -> Option<Layer> {
+    let number = |key, default| json.get(key).and_then(Json::as_f32).unwrap_or(default);
+
+    let content = match json.get("ty")?.as_f32()? as i32 {
+        0 => LayerContent::Precomp {
+            layers: assets.layers(json.get("refId")?.as_str()?, depth)?,
+            width: number("w", 0.0),
+            height: number("h", 0.0),
+        },
+        1 => LayerContent::Solid {
+            color: Color::hex(json.get("sc")?.as_str()?),
+            width: number("sw", 0.0),
+            height: number("sh", 0.0),
+        },
+        4 => LayerContent::Shapes(parse_shapes(json.get("shapes"))),
+        // Null layers only parent other layers. Image and text layers are not supported
+        // but may still be parents.
+        _ => LayerContent::Empty,
+    };
+
+    let masks = json
+        .get("masksProperties")
+        .and_then(Json::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(parse_mask)
+        .collect();
+
+    let matte = match number("tt", 0.0) as i32 {
+        1 => Some(Matte::Alpha),
+        2 => Some(Matte::AlphaInverted),
+        3 => Some(Matte::Luma),
+        _ => None,
+    };
+
+    let stretch = number("sr", 1.0);
+
+    Some(Layer {
+        index: json.get("ind").and_then(Json::as_f32).map(|index| index as i64),
+        parent: json.get("parent").and_then(Json::as_f32).map(|index| index as i64),
+        in_point: number("ip", 0.0),
+        out_point: number("op", f32::MAX),
+        start_time: number("st", 0.0),
+        stretch: if stretch == 0.0 { 1.0 } else { stretch },
+        hidden: flag(json, "hd"),
+        matte_source: flag(json, "td"),
+        matte,
+        transform: TransformProps::from_json(json.get("ks")),
+        masks,
+        content,
+    })
+}
+
+fn parse_mask(json: &Json) -> Option<LayerMask> {
+    let mode = match json.get("mode").and_then(Json::as_str).unwrap_or("a") {
+        "n" => return None,
+        "s" => MaskOp::Subtract,
+        "i" => MaskOp::Intersect,
+        // Lighten, darken and difference are approximated with add
+        _ => MaskOp::Add,
+    };
+
+    Some(LayerMask {
+        mode,
+        inverted: flag(json, "inv"),
+        shape: Animated::from_json(json.get("pt")?)?,
+        opacity: animated(json.get("o"), &[100.0]),
+    })
+}
+
+// Transforms
+
+struct TransformProps {
+    anchor: Animated<Vec<f32>>,
+    position: Position,
+    scale: Animated<Vec<f32>>,
+    rotation: Animated<Vec<f32>>,
+    opacity: Animated<Vec<f32>>,
+}
+
+enum Position {
+    Combined(Animated<Vec<f32>>),
+    Split(Animated<Vec<f32>>, Animated<Vec<f32>>),
+}
+
+impl TransformProps {
+    fn from_json(json: Option<&Json>) -> Self {
+        let get = |key| json.and_then(|json| json.get(key));
+
+        let position = match get("p") {
+            Some(position) if flag(position, "s") => {
+                Position::Split(animated(position.get("x"), &[0.0]), animated(position.get("y"), &[0.0]))
+            }
+            position => Position::Combined(animated(position, &[0.0, 0.0])),
+        };
+
+        Self {
+            anchor: animated(get("a"), &[0.0, 0.0]),
+            position,
+            scale: animated(get("s"), &[100.0, 100.0]),
+            rotation: animated(get("r").or_else(|| get("rz")), &[0.0]),
+            opacity: animated(get("o"), &[100.0]),
+        }
+    }
+
+    fn matrix(&self, frame: f32) -> Transform2D {
+        let [ax, ay] = self.anchor.vec2(frame);
+        let [sx, sy] = self.scale.vec2(frame);
+
+        let [px, py] = match &self.position {
+            Position::Combined(position) => position.vec2(frame),
+            Position::Split(x, y) => [x.scalar(frame), y.scalar(frame)],
+        };
+
+        let mut transform = Transform2D::new_translation(-ax, -ay);
+
+        let mut scale = Transform2D::identity();
+        scale.scale(sx / 100.0, sy / 100.0);
+        transform.multiply(&scale);
+
+        let mut rotation = Transform2D::identity();
+        rotation.rotate(self.rotation.scalar(frame).to_radians());
+        transform.multiply(&rotation);
+
+        transform.multiply(&Transform2D::new_translation(px, py));
+
+        transform
+    }
+
+    fn opacity(&self, frame: f32) -> f32 {
+        self.opacity.scalar(frame) / 100.0
+    }
+}
+
+// The layer transform followed by the transforms of its parents
+fn layer_transform(layers: &[Layer], layer: &Layer, frame: f32) -> Transform2D {
+    let mut transform = layer.transform.matrix(layer.local_frame(frame));
+    let mut parent = layer.parent;
+
+    for _ in 0..MAX_DEPTH {
+        let layer = match parent.and_then(|index| layers.iter().find(|layer| layer.index == Some(index))) {
+            Some(layer) => layer,
+            None => break,
+        };
+
+        transform.multiply(&layer.transform.matrix(layer.local_frame(frame)));
+        parent = layer.parent;
+    }
+
+    transform
+}
+
+// Shapes
+
+enum Shape {
+    Group {
+        items: Vec<Shape>,
+        transform: Option<TransformProps>,
+    },
+    Path(Animated<Bezier>),
+    Rect {
+        position: Animated<Vec<f32>>,
+        size: Animated<Vec<f32>>,
+        roundness: Animated<Vec<f32>>,
+    },
+    Ellipse {
+        position: Animated<Vec<f32>>,
+        size: Animated<Vec<f32>>,
+    },
+    Fill {
+        style: FillStyle,
+        opacity: Animated<Vec<f32>>,
+        rule: FillRule,
+    },
+    Stroke {
+        style: FillStyle,
+        opacity: Animated<Vec<f32>>,
+        width: Animated<Vec<f32>>,
+        cap: LineCap,
+        join: LineJoin,
+        miter_limit: f32,
+    },
+}
+
+enum FillStyle {
+    Color(Animated<Vec<f32>>),
+    Gradient {
+        radial: bool,
+        start: Animated<Vec<f32>>,
+        end: Animated<Vec<f32>>,
+        stop_count: usize,
+        stops: Animated<Vec<f32>>,
+    },
+}
+
+fn parse_shapes(json: Option<&Json>) -> Vec<Shape> {
+    json.and_then(Json::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(parse_shape)
+        .collect()
+}
+
+fn parse_shape(json: &Json) -> Option<Shape> {
+    if flag(json, "hd") {
+        return None;
+    }
+
+    let shape = match json.get("ty")?.as_str()? {
+        "gr" => {
+            let items = json.get("it").and_then(Json::as_slice).unwrap_or_default();
+
+            // The group transform is the "tr" item, which parse_shape skips
+            let transform = items
+                .iter()
+                .find(|item| item.get("ty").and_then(Json::as_str) == Some("tr"))
+                .map(|item| TransformProps::from_json(Some(item)));
+
+            Shape::Group {
+                items: items.iter().filter_map(parse_shape).collect(),
+                transform,
+            }
+        }
+        "sh" => Shape::Path(Animated::from_json(json.get("ks")?)?),
+        "rc" => Shape::Rect {
+            position: animated(json.get("p"), &[0.0, 0.0]),
+            size: animated(json.get("s"), &[0.0, 0.0]),
+            roundness: animated(json.get("r"), &[0.0]),
+        },
+        "el" => Shape::Ellipse {
+            position: animated(json.get("p"), &[0.0, 0.0]),
+            size: animated(json.get("s"), &[0.0, 0.0]),
+        },
+        "fl" | "gf" => Shape::Fill {
+            style: parse_fill_style(json)?,
+            opacity: animated(json.get("o"), &[100.0]),
+            rule: match json.get("r").and_then(Json::as_f32) {
+                Some(rule) if rule as i32 == 2 => FillRule::EvenOdd,
+                _ => FillRule::NonZero,
+            },
+        },
+        "st" | "gs" => Shape::Stroke {
+            style: parse_fill_style(json)?,
+            opacity: animated(json.get("o"), &[100.0]),
+            width: animated(json.get("w"), &[1.0]),
+            cap: match json.get("lc").and_then(Json::as_f32).map(|cap| cap as i32) {
+                Some(2) => LineCap::Round,
+                Some(3) => LineCap::Square,
+                _ => LineCap::Butt,
+            },
+            join: match json.get("lj").and_then(Json::as_f32).map(|join| join as i32) {
+                Some(2) => LineJoin::Round,
+                Some(3) => LineJoin::Bevel,
+                _ => LineJoin::Miter,
+            },
+            miter_limit: json.get("ml").and_then(Json::as_f32).unwrap_or(4.0),
+        },
+        // Trim paths, repeaters, merge paths and the like are not supported
+        _ => return None,
+    };
+
+    Some(shape)
+}
+
+fn parse_fill_style(json: &Json) -> Option<FillStyle> {
+    let gradient = match json.get("g") {
+        Some(gradient) => gradient,
+        None => return Some(FillStyle::Color(animated(json.get("c"), &[0.0, 0.0, 0.0, 1.0]))),
+    };
+
+    Some(FillStyle::Gradient {
+        radial: json.get("t").and_then(Json::as_f32).map(|kind| kind as i32) == Some(2),
+        start: animated(json.get("s"), &[0.0, 0.0]),
+        end: animated(json.get("e"), &[0.0, 0.0]),
+        stop_count: gradient.get("p").and_then(Json::as_f32).unwrap_or(0.0) as usize,
+        stops: Animated::from_json(gradient.get("k")?)?,
+    })
+}
+
+impl FillStyle {
+    fn paint(&self, frame: f32, opacity: f32) -> Paint {
+        match self {
+            Self::Color(color) => Paint::color(convert_color(&color.value(frame), opacity)),
+            Self::Gradient {
+                radial,
+                start,
+                end,
+                stop_count,
+                stops,
+            } => {
+                let [sx, sy] = start.vec2(frame);
+                let [ex, ey] = end.vec2(frame);
+
+                // Color stops are (offset, r, g, b) followed by optional (offset, alpha) pairs
+                let values = stops.value(frame);
+                let count = (*stop_count).min(values.len() / 4);
+                let alphas = &values[count * 4..];
+
+                let stops: Vec<_> = values[..count * 4]
+                    .chunks_exact(4)
+                    .map(|stop| {
+                        let alpha = gradient_alpha(alphas, stop[0]) * opacity;
+                        (stop[0], convert_color(&[stop[1], stop[2], stop[3], alpha], 1.0))
+                    })
+                    .collect();
+
+                if *radial {
+                    let radius = ((ex - sx).powi(2) + (ey - sy).powi(2)).sqrt();
+                    Paint::radial_gradient_stops(sx, sy, 0.0, radius, &stops)
+                } else {
+                    Paint::linear_gradient_stops(sx, sy, ex, ey, &stops)
+                }
+            }
+        }
+    }
+}
+
+fn gradient_alpha(alphas: &[f32], offset: f32) -> f32 {
+    let stops: Vec<_> = alphas.chunks_exact(2).collect();
+
+    let (first, last) = match (stops.first(), stops.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return 1.0,
+    };
+
+    if offset <= first[0] {
+        return first[1];
+    }
+
+    for pair in stops.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+
+        if offset <= b[0] {
+            let t = if b[0] > a[0] {
+                (offset - a[0]) / (b[0] - a[0])
+            } else {
+                1.0
+            };
+            return a[1] + (b[1] - a[1]) * t;
+        }
+    }
+
+    last[1]
+}
+
+fn rect_shape([cx, cy]: [f32; 2], [width, height]: [f32; 2], roundness: f32) -> Bezier {
+    let (hw, hh) = (width * 0.5, height * 0.5);
+    let (left, top, right, bottom) = (cx - hw, cy - hh, cx + hw, cy + hh);
+    let r = roundness.min(hw).min(hh).max(0.0);
+    let k = r * KAPPA;
+
+    let (vertices, in_tangents, out_tangents) = if r > 0.0 {
+        (
+            vec![
+                [right - r, top],
+                [right, top + r],
+                [right, bottom - r],
+                [right - r, bottom],
+                [left + r, bottom],
+                [left, bottom - r],
+                [left, top + r],
+                [left + r, top],
+            ],
+            vec![
+                [0.0, 0.0],
+                [0.0, -k],
+                [0.0, 0.0],
+                [k, 0.0],
+                [0.0, 0.0],
+                [0.0, k],
+                [0.0, 0.0],
+                [-k, 0.0],
+            ],
+            vec![
+                [k, 0.0],
+                [0.0, 0.0],
+                [0.0, k],
+                [0.0, 0.0],
+                [-k, 0.0],
+                [0.0, 0.0],
+                [0.0, -k],
+                [0.0, 0.0],
+            ],
+        )
+    } else {
+        (
+            vec![[right, top], [right, bottom], [left, bottom], [left, top]],
+            vec![[0.0, 0.0]; 4],
+            vec![[0.0, 0.0]; 4],
+        )
+    };
+
+    Bezier {
+        closed: true,
+        vertices,
+        in_tangents,
+        out_tangents,
+    }
+}
+
+fn ellipse_shape([cx, cy]: [f32; 2], [width, height]: [f32; 2]) -> Bezier {
+    let (rx, ry) = (width * 0.5, height * 0.5);
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+
+    Bezier {
+        closed: true,
+        vertices: vec![[cx, cy - ry], [cx + rx, cy], [cx, cy + ry], [cx - rx, cy]],
+        in_tangents: vec![[-kx, 0.0], [0.0, -ky], [kx, 0.0], [0.0, ky]],
+        out_tangents: vec![[kx, 0.0], [0.0, ky], [-kx, 0.0], [0.0, -ky]],
+    }
+}
+
+// Collects the geometry of the shapes, including nested groups, into a single path
+fn add_geometry(shapes: &[Shape], frame: f32, transform: &Transform2D, path: &mut Path) {
+    for shape in shapes {
+        match shape {
+            Shape::Path(bezier) => bezier.value(frame).add_to(path, transform),
+            Shape::Rect {
+                position,
+                size,
+                roundness,
+            } => rect_shape(position.vec2(frame), size.vec2(frame), roundness.scalar(frame)).add_to(path, transform),
+            Shape::Ellipse { position, size } => {
+                ellipse_shape(position.vec2(frame), size.vec2(frame)).add_to(path, transform)
+            }
+            Shape::Group {
+                items,
+                transform: group_transform,
+            } => {
+                let transform = match group_transform {
+                    Some(group_transform) => {
+                        let mut matrix = group_transform.matrix(frame);
+                        matrix.multiply(transform);
+                        matrix
+                    }
+                    None => *transform,
+                };
+
+                add_geometry(items, frame, &transform, path);
+            }
+            Shape::Fill { .. } | Shape::Stroke { .. } => (),
+        }
+    }
+}
+
+// Drawing
+
+// Coverage of a mask or matte, rendered into an image the size of the render target
+#[derive(Copy, Clone)]
+struct Coverage {
+    image: ImageId,
+    mode: MaskMode,
+}
+
+fn clipped<T: Renderer>(canvas: &Canvas<T>, mut paint: Paint, clip: Option<Coverage>) -> Paint {
+    if let Some(clip) = clip {
+        paint.set_mask(Some(canvas.device_mask(clip.image, clip.mode)));
+    }
+
+    paint
+}
+
+// The first layer is the topmost one
+fn draw_layers<T: Renderer>(canvas: &mut Canvas<T>, layers: &[Layer], frame: f32, clip: Option<Coverage>) {
+    for (index, layer) in layers.iter().enumerate().rev() {
+        if layer.matte_source {
+            continue;
+        }
+
+        // A track matte uses the layer right above as the matte source
+        let clip = match (layer.matte, index.checked_sub(1).and_then(|index| layers.get(index))) {
+            (Some(matte), Some(source)) if layer.is_visible(frame) => {
+                render_matte(canvas, layers, source, matte, frame, clip)
+            }
+            _ => clip,
+        };
+
+        draw_layer(canvas, layers, layer, frame, clip);
+    }
+}
+
+fn draw_layer<T: Renderer>(
+    canvas: &mut Canvas<T>,
+    layers: &[Layer],
+    layer: &Layer,
+    frame: f32,
+    clip: Option<Coverage>,
+) {
+    if !layer.is_visible(frame) {
+        return;
+    }
+
+    let local_frame = layer.local_frame(frame);
+
+    canvas.save();
+    canvas.premultiply_transform(layer_transform(layers, layer, frame));
+    canvas.state_mut().alpha *= layer.transform.opacity(local_frame);
+
+    let clip = if layer.masks.is_empty() {
+        clip
+    } else {
+        render_masks(canvas, &layer.masks, local_frame, clip)
+    };
+
+    match &layer.content {
+        LayerContent::Shapes(shapes) => draw_shapes(canvas, shapes, local_frame, clip),
+        LayerContent::Solid { color, width, height } => {
+            let mut path = Path::new();
+            path.rect(0.0, 0.0, *width, *height);
+            let paint = clipped(canvas, Paint::color(*color), clip);
+            canvas.fill_path(&mut path, paint);
+        }
+        LayerContent::Precomp { layers, width, height } => {
+            canvas.intersect_scissor(0.0, 0.0, *width, *height);
+            draw_layers(canvas, layers, local_frame, clip);
+        }
+        LayerContent::Empty => (),
+    }
+
+    canvas.restore();
+}
+
+// Paints apply to all geometry listed before them in the same group, including nested groups.
+// Items listed first are drawn on top.
+fn draw_shapes<T: Renderer>(canvas: &mut Canvas<T>, shapes: &[Shape], frame: f32, clip: Option<Coverage>) {
+    for (index, shape) in shapes.iter().enumerate().rev() {
+        match shape {
+            Shape::Group { items, transform } => {
+                canvas.save();
+
+                if let Some(transform) = transform {
+                    canvas.premultiply_transform(transform.matrix(frame));
+                    canvas.state_mut().alpha *= transform.opacity(frame);
+                }
+
+                draw_shapes(canvas, items, frame, clip);
+                canvas.restore();
+            }
+            Shape::Fill { style, opacity, rule } => {
+                let mut path = Path::new();
+                add_geometry(&shapes[..index], frame, &Transform2D::identity(), &mut path);
+
+                let mut paint = style.paint(frame, opacity.scalar(frame) / 100.0);
+                paint.set_fill_rule(*rule);
+
+                let paint = clipped(canvas, paint, clip);
+                canvas.fill_path(&mut path, paint);
+            }
+            Shape::Stroke {
+                style,
+                opacity,
+                width,
+                cap,
+                join,
+                miter_limit,
+            } => {
+                let mut path = Path::new();
+                add_geometry(&shapes[..index], frame, &Transform2D::identity(), &mut path);
+
+                let mut paint = style.paint(frame, opacity.scalar(frame) / 100.0);
+                paint.set_line_width(width.scalar(frame));
+                paint.set_line_cap(*cap);
+                paint.set_line_join(*join);
+                paint.set_miter_limit(*miter_limit);
+
+                let paint = clipped(canvas, paint, clip);
+                canvas.stroke_path(&mut path, paint);
+            }
+            _ => (),
+        }
+    }
+}
+
+// Renders the layer masks, combined with the outer clip. Returns the outer clip if no
+// coverage image can be created, drawing unmasked beats not drawing at all.
+fn render_masks<T: Renderer>(
+    canvas: &mut Canvas<T>,
+    masks: &[LayerMask],
+    frame: f32,
+    clip: Option<Coverage>,
+) -> Option<Coverage> {
+    let image = match canvas.create_scratch_image(ImageFlags::empty()) {
+        Ok(image) => image,
+        Err(_) => return clip,
+    };
+
+    let target = canvas.current_render_target;
+
+    canvas.save();
+    canvas.state_mut().alpha = 1.0;
+    canvas.set_render_target(RenderTarget::Image(image));
+
+    // Subtracting or intersecting first starts out with everything visible
+    let initial = match masks[0].mode {
+        MaskOp::Add => Color::rgbaf(0.0, 0.0, 0.0, 0.0),
+        MaskOp::Subtract | MaskOp::Intersect => Color::white(),
+    };
+
+    let (width, height) = (canvas.width() as u32, canvas.height() as u32);
+    canvas.clear_rect(0, 0, width, height, initial);
+
+    for mask in masks {
+        let mut path = Path::new();
+        mask.shape.value(frame).add_to(&mut path, &Transform2D::identity());
+
+        // Intersecting removes everything outside of the mask
+        let (operation, inverted) = match mask.mode {
+            MaskOp::Add => (CompositeOperation::SourceOver, mask.inverted),
+            MaskOp::Subtract => (CompositeOperation::DestinationOut, mask.inverted),
+            MaskOp::Intersect => (CompositeOperation::DestinationOut, !mask.inverted),
+        };
+
+        if inverted {
+            add_target_rect(canvas, &mut path);
+        }
+
+        let mut paint = Paint::color(Color::rgbaf(1.0, 1.0, 1.0, mask.opacity.scalar(frame) / 100.0));
+        paint.set_fill_rule(if inverted { FillRule::EvenOdd } else { FillRule::NonZero });
+
+        canvas.global_composite_operation(operation);
+        canvas.fill_path(&mut path, paint);
+    }
+
+    intersect_coverage(canvas, clip);
+
+    canvas.set_render_target(target);
+    canvas.restore();
+
+    Some(Coverage {
+        image,
+        mode: MaskMode::Alpha,
+    })
+}
+
+fn render_matte<T: Renderer>(
+    canvas: &mut Canvas<T>,
+    layers: &[Layer],
+    source: &Layer,
+    matte: Matte,
+    frame: f32,
+    clip: Option<Coverage>,
+) -> Option<Coverage> {
+    let image = match canvas.create_scratch_image(ImageFlags::empty()) {
+        Ok(image) => image,
+        Err(_) => return clip,
+    };
+
+    let target = canvas.current_render_target;
+
+    canvas.save();
+    canvas.state_mut().alpha = 1.0;
+    canvas.set_render_target(RenderTarget::Image(image));
+
+    let (width, height) = (canvas.width() as u32, canvas.height() as u32);
+
+    // An inverted matte punches the source out of an opaque image
+    if let Matte::AlphaInverted = matte {
+        canvas.clear_rect(0, 0, width, height, Color::white());
+        canvas.global_composite_operation(CompositeOperation::DestinationOut);
+    } else {
+        canvas.clear_rect(0, 0, width, height, Color::rgbaf(0.0, 0.0, 0.0, 0.0));
+    }
+
+    draw_layer(canvas, layers, source, frame, None);
+
+    intersect_coverage(canvas, clip);
+
+    canvas.set_render_target(target);
+    canvas.restore();
+
+    let mode = match matte {
+        Matte::Luma => MaskMode::Luminance,
+        Matte::Alpha | Matte::AlphaInverted => MaskMode::Alpha,
+    };
+
+    Some(Coverage { image, mode })
+}
+
+// Multiplies the current render target with the coverage
+fn intersect_coverage<T: Renderer>(canvas: &mut Canvas<T>, clip: Option<Coverage>) {
+    let clip = match clip {
+        Some(clip) => clip,
+        None => return,
+    };
+
+    canvas.save();
+    canvas.reset_transform();
+    canvas.reset_scissor();
+    canvas.state_mut().alpha = 1.0;
+    canvas.global_composite_operation(CompositeOperation::DestinationIn);
+
+    let mut path = Path::new();
+    path.rect(0.0, 0.0, canvas.width(), canvas.height());
+
+    let paint = clipped(canvas, Paint::color(Color::white()), Some(clip));
+    canvas.fill_path(&mut path, paint);
+
+    canvas.restore();
+}
+
+// Adds a rectangle covering the whole render target in the current coordinate system
+fn add_target_rect<T: Renderer>(canvas: &Canvas<T>, path: &mut Path) {
+    let inverse = canvas.transform().inversed();
+    let (width, height) = (canvas.width(), canvas.height());
+
+    let (x, y) = inverse.transform_point(0.0, 0.0);
+    path.move_to(x, y);
+
+    for &(x, y) in &[(width, 0.0), (width, height), (0.0, height)] {
+        let (x, y) = inverse.transform_point(x, y);
+        path.line_to(x, y);
+    }
+
+    path.close();
+}
+
+// Helpers
+
+fn flag(json: &Json, key: &str) -> bool {
+    json.get(key).and_then(Json::as_flag).unwrap_or(false)
+}
+
+fn animated(json: Option<&Json>, default: &[f32]) -> Animated<Vec<f32>> {
+    json.and_then(Animated::from_json)
+        .unwrap_or_else(|| Animated::constant(default.to_vec()))
+}
+
+fn convert_color(values: &[f32], opacity: f32) -> Color {
+    let component = |index| values.get(index).copied().unwrap_or(0.0);
+    let (r, g, b) = (component(0), component(1), component(2));
+    let alpha = values.get(3).copied().unwrap_or(1.0) * opacity;
+
+    // Some older exporters write colors in the 0-255 range
+    if r > 1.0 || g > 1.0 || b > 1.0 {
+        Color::rgbaf(r / 255.0, g / 255.0, b / 255.0, alpha)
+    } else {
+        Color::rgbaf(r, g, b, alpha)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::renderer::Recorder;
+
+    #[test]
+    fn lottie_position_follows_tangents() {
+        // A 10x10 solid moving from (0, 0) to (100, 0) on a curve bulging down to y = 75
+        let animation: LottieAnimation = r##"{
+            "fr": 25, "ip": 0, "op": 50, "w": 200, "h": 200,
+            "layers": [{
+                "ty": 1, "ip": 0, "op": 50, "st": 0, "sc": "#ffffff", "sw": 10, "sh": 10,
+                "ks": {
+                    "p": { "a": 1, "k": [
+                        { "t": 0, "s": [0, 0], "to": [0, 100], "ti": [0, 100] },
+                        { "t": 50, "s": [100, 0] }
+                    ] }
+                }
+            }]
+        }"##
+        .parse()
+        .unwrap();
+
+        let recorder = Recorder::default();
+        let mut canvas = Canvas::new(recorder.clone()).unwrap();
+        canvas.set_size(200, 200, 1.0);
+
+        canvas.draw_lottie(&animation, 1.0);
+        canvas.flush(None);
+
+        let verts = recorder.0.borrow().verts.clone();
+        let min_x = verts.iter().map(|vert| vert.x).fold(f32::MAX, f32::min);
+        let min_y = verts.iter().map(|vert| vert.y).fold(f32::MAX, f32::min);
+
+        // Halfway along the curve, not halfway along the straight line at y = 0
+        assert!((min_x - 50.0).abs() < 1.5, "{}", min_x);
+        assert!((min_y - 75.0).abs() < 1.5, "{}", min_y);
+    }
+}
@@ -0,0 +1,39 @@
+use crate::ErrorKind;
+
+pub(crate) use serde_json::Value as Json;
+
+pub(crate) fn parse(data: &[u8]) -> Result<Json, ErrorKind> {
+    serde_json::from_slice(data).map_err(|error| ErrorKind::LottieParseError(error.to_string()))
+}
+
+/// Accessors for the loosely typed values found in Lottie files.
+pub(crate) trait JsonExt {
+    fn as_f32(&self) -> Option<f32>;
+
+    /// Lottie writes flags both as booleans and as 0/1.
+    fn as_flag(&self) -> Option<bool>;
+
+    fn as_slice(&self) -> Option<&[Json]>;
+}
+
+impl JsonExt for Json {
+    fn as_f32(&self) -> Option<f32> {
+        match self {
+            Self::Number(n) => n.as_f64().map(|n| n as f32),
+            Self::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+            _ => None,
+        }
+    }
+
+    fn as_flag(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            Self::Number(n) => n.as_f64().map(|n| n != 0.0),
+            _ => None,
+        }
+    }
+
+    fn as_slice(&self) -> Option<&[Json]> {
+        self.as_array().map(Vec::as_slice)
+    }
+}
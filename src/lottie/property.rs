@@ -0,0 +1,382 @@
+use crate::{
+    Path,
+    Transform2D,
+};
+
+use super::json::{
+    Json,
+    JsonExt,
+};
+
+/// A value that can be animated with keyframes.
+pub(crate) trait Interpolate: Clone + Sized {
+    fn from_json(json: &Json) -> Option<Self>;
+
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+
+    /// Interpolates along the curve given by the out tangent of `self` and the in tangent of
+    /// `other`, both relative to their value. Only positions move along curves.
+    fn lerp_spatial(&self, other: &Self, _tangents: [[f32; 2]; 2], t: f32) -> Self {
+        self.lerp(other, t)
+    }
+}
+
+impl Interpolate for Vec<f32> {
+    fn from_json(json: &Json) -> Option<Self> {
+        match json {
+            Json::Array(items) => items.iter().map(Json::as_f32).collect(),
+            _ => json.as_f32().map(|value| vec![value]),
+        }
+    }
+
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self.iter().zip(other.iter()).map(|(a, b)| a + (b - a) * t).collect()
+    }
+
+    fn lerp_spatial(&self, other: &Self, [out_tangent, in_tangent]: [[f32; 2]; 2], t: f32) -> Self {
+        let (p0, p3) = match (self.as_slice(), other.as_slice()) {
+            ([x0, y0, ..], [x1, y1, ..]) => ([*x0, *y0], [*x1, *y1]),
+            _ => return self.lerp(other, t),
+        };
+
+        let p1 = [p0[0] + out_tangent[0], p0[1] + out_tangent[1]];
+        let p2 = [p3[0] + in_tangent[0], p3[1] + in_tangent[1]];
+
+        let point = |s: f32| {
+            let inv = 1.0 - s;
+            let weights = [inv * inv * inv, 3.0 * inv * inv * s, 3.0 * inv * s * s, s * s * s];
+
+            let mut point = [0.0, 0.0];
+
+            for (weight, p) in weights.iter().zip(&[p0, p1, p2, p3]) {
+                point[0] += weight * p[0];
+                point[1] += weight * p[1];
+            }
+
+            point
+        };
+
+        // The progress is the distance travelled along the curve, not the curve parameter
+        const SAMPLES: usize = 32;
+
+        let mut lengths = [0.0; SAMPLES + 1];
+        let mut prev = p0;
+
+        for i in 1..=SAMPLES {
+            let p = point(i as f32 / SAMPLES as f32);
+            lengths[i] = lengths[i - 1] + (p[0] - prev[0]).hypot(p[1] - prev[1]);
+            prev = p;
+        }
+
+        let length = lengths[SAMPLES];
+
+        // Easings that overshoot extrapolate the curve
+        let s = if length > 0.0 && (0.0..=1.0).contains(&t) {
+            let distance = length * t;
+            let i = lengths[1..].iter().position(|l| *l >= distance).unwrap_or(SAMPLES - 1);
+            let segment = lengths[i + 1] - lengths[i];
+            let f = if segment > 0.0 {
+                (distance - lengths[i]) / segment
+            } else {
+                0.0
+            };
+
+            (i as f32 + f) / SAMPLES as f32
+        } else {
+            t
+        };
+
+        let mut value = self.lerp(other, t);
+        let [x, y] = point(s);
+        value[0] = x;
+        value[1] = y;
+        value
+    }
+}
+
+/// A bezier shape, vertices with tangents relative to them.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Bezier {
+    pub closed: bool,
+    pub vertices: Vec<[f32; 2]>,
+    pub in_tangents: Vec<[f32; 2]>,
+    pub out_tangents: Vec<[f32; 2]>,
+}
+
+impl Bezier {
+    /// Appends the shape to the path, transforming every point.
+    pub fn add_to(&self, path: &mut Path, transform: &Transform2D) {
+        let count = self
+            .vertices
+            .len()
+            .min(self.in_tangents.len())
+            .min(self.out_tangents.len());
+
+        if count == 0 {
+            return;
+        }
+
+        let point = |p: [f32; 2], offset: [f32; 2]| transform.transform_point(p[0] + offset[0], p[1] + offset[1]);
+
+        let (x, y) = point(self.vertices[0], [0.0, 0.0]);
+        path.move_to(x, y);
+
+        let segments = if self.closed { count } else { count - 1 };
+
+        for i in 0..segments {
+            let next = (i + 1) % count;
+
+            let (c1x, c1y) = point(self.vertices[i], self.out_tangents[i]);
+            let (c2x, c2y) = point(self.vertices[next], self.in_tangents[next]);
+            let (x, y) = point(self.vertices[next], [0.0, 0.0]);
+
+            path.bezier_to(c1x, c1y, c2x, c2y, x, y);
+        }
+
+        if self.closed {
+            path.close();
+        }
+    }
+}
+
+fn points(json: Option<&Json>) -> Option<Vec<[f32; 2]>> {
+    json?
+        .as_slice()?
+        .iter()
+        .map(|point| match Vec::<f32>::from_json(point)?.as_slice() {
+            [x, y, ..] => Some([*x, *y]),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Interpolate for Bezier {
+    fn from_json(json: &Json) -> Option<Self> {
+        // Keyframe values wrap the shape in an array
+        let json = match json {
+            Json::Array(items) => items.first()?,
+            json => json,
+        };
+
+        Some(Self {
+            closed: json.get("c").and_then(Json::as_flag).unwrap_or(false),
+            vertices: points(json.get("v"))?,
+            in_tangents: points(json.get("i"))?,
+            out_tangents: points(json.get("o"))?,
+        })
+    }
+
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let lerp_points = |a: &[[f32; 2]], b: &[[f32; 2]]| {
+            a.iter()
+                .zip(b.iter())
+                .map(|(a, b)| [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t])
+                .collect()
+        };
+
+        Self {
+            closed: self.closed,
+            vertices: lerp_points(&self.vertices, &other.vertices),
+            in_tangents: lerp_points(&self.in_tangents, &other.in_tangents),
+            out_tangents: lerp_points(&self.out_tangents, &other.out_tangents),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Keyframe<T> {
+    time: f32,
+    start: T,
+    end: T,
+    hold: bool,
+    // Cubic bezier control points (x1, y1, x2, y2) mapping time to progress
+    easing: Option<[f32; 4]>,
+    // Out and in tangents of the path position keyframes move along
+    tangents: Option<[[f32; 2]; 2]>,
+}
+
+/// A property that is either constant or keyframed.
+#[derive(Clone, Debug)]
+pub(crate) enum Animated<T> {
+    Static(T),
+    Keyframes(Vec<Keyframe<T>>),
+}
+
+impl<T: Interpolate> Animated<T> {
+    pub fn constant(value: T) -> Self {
+        Self::Static(value)
+    }
+
+    /// Parses a `{"a": 0|1, "k": ...}` property object.
+    pub fn from_json(json: &Json) -> Option<Self> {
+        let value = json.get("k")?;
+
+        let keyframes = match value {
+            Json::Array(items) if matches!(items.first(), Some(item) if item.get("t").is_some()) => items,
+            _ => return T::from_json(value).map(Self::Static),
+        };
+
+        // Newer files leave out the end value, it's the start value of the next keyframe.
+        // The last keyframe often only marks the time where the previous one ends.
+        let mut parsed: Vec<Keyframe<Option<T>>> = keyframes
+            .iter()
+            .map(|keyframe| Keyframe {
+                time: keyframe.get("t").and_then(Json::as_f32).unwrap_or(0.0),
+                start: keyframe.get("s").and_then(T::from_json),
+                end: keyframe.get("e").and_then(T::from_json),
+                hold: keyframe.get("h").and_then(Json::as_flag).unwrap_or(false),
+                easing: easing(keyframe),
+                tangents: tangents(keyframe),
+            })
+            .collect();
+
+        for i in 1..parsed.len() {
+            if parsed[i].start.is_none() {
+                parsed[i].start = parsed[i - 1].end.clone().or_else(|| parsed[i - 1].start.clone());
+            }
+        }
+
+        for i in 0..parsed.len() {
+            if parsed[i].end.is_none() {
+                parsed[i].end = match parsed.get(i + 1) {
+                    Some(next) => next.start.clone(),
+                    None => parsed[i].start.clone(),
+                };
+            }
+        }
+
+        let keyframes: Vec<_> = parsed
+            .into_iter()
+            .filter_map(|keyframe| {
+                Some(Keyframe {
+                    time: keyframe.time,
+                    start: keyframe.start?,
+                    end: keyframe.end?,
+                    hold: keyframe.hold,
+                    easing: keyframe.easing,
+                    tangents: keyframe.tangents,
+                })
+            })
+            .collect();
+
+        if keyframes.is_empty() {
+            return None;
+        }
+
+        Some(Self::Keyframes(keyframes))
+    }
+
+    pub fn value(&self, frame: f32) -> T {
+        let keyframes = match self {
+            Self::Static(value) => return value.clone(),
+            Self::Keyframes(keyframes) => keyframes,
+        };
+
+        let index = keyframes.iter().rposition(|keyframe| keyframe.time <= frame);
+
+        let index = match index {
+            Some(index) => index,
+            None => return keyframes[0].start.clone(),
+        };
+
+        let keyframe = &keyframes[index];
+
+        let next_time = match keyframes.get(index + 1) {
+            Some(next) => next.time,
+            None => return keyframe.start.clone(),
+        };
+
+        if keyframe.hold || next_time <= keyframe.time {
+            return keyframe.start.clone();
+        }
+
+        let mut t = (frame - keyframe.time) / (next_time - keyframe.time);
+
+        if let Some(easing) = keyframe.easing {
+            t = ease(easing, t);
+        }
+
+        match keyframe.tangents {
+            Some(tangents) => keyframe.start.lerp_spatial(&keyframe.end, tangents, t),
+            None => keyframe.start.lerp(&keyframe.end, t),
+        }
+    }
+}
+
+impl Animated<Vec<f32>> {
+    pub fn scalar(&self, frame: f32) -> f32 {
+        self.value(frame).first().copied().unwrap_or(0.0)
+    }
+
+    pub fn vec2(&self, frame: f32) -> [f32; 2] {
+        match self.value(frame).as_slice() {
+            [x, y, ..] => [*x, *y],
+            [x] => [*x, *x],
+            [] => [0.0, 0.0],
+        }
+    }
+}
+
+// Only the first dimension's easing is used for multi dimensional values
+fn easing(keyframe: &Json) -> Option<[f32; 4]> {
+    let component = |json: Option<&Json>, axis| {
+        let value = json?.get(axis)?;
+        value.as_f32().or_else(|| value.as_slice()?.first()?.as_f32())
+    };
+
+    let out_tangent = keyframe.get("o");
+    let in_tangent = keyframe.get("i");
+
+    Some([
+        component(out_tangent, "x")?,
+        component(out_tangent, "y")?,
+        component(in_tangent, "x")?,
+        component(in_tangent, "y")?,
+    ])
+}
+
+// The "to" and "ti" tangents of position keyframes, zero tangents are a straight line
+fn tangents(keyframe: &Json) -> Option<[[f32; 2]; 2]> {
+    let point = |key| match Vec::<f32>::from_json(keyframe.get(key)?)?.as_slice() {
+        [x, y, ..] => Some([*x, *y]),
+        _ => None,
+    };
+
+    let tangents = [point("to")?, point("ti")?];
+
+    if tangents.iter().flatten().all(|value| *value == 0.0) {
+        return None;
+    }
+
+    Some(tangents)
+}
+
+// Solves the cubic bezier (0,0) (x1,y1) (x2,y2) (1,1) for x = t and returns y
+fn ease([x1, y1, x2, y2]: [f32; 4], t: f32) -> f32 {
+    let bezier = |a: f32, b: f32, s: f32| {
+        let inv = 1.0 - s;
+        3.0 * inv * inv * s * a + 3.0 * inv * s * s * b + s * s * s
+    };
+
+    let mut low = 0.0;
+    let mut high = 1.0;
+    let mut s = t;
+
+    for _ in 0..32 {
+        let x = bezier(x1, x2, s);
+
+        if (x - t).abs() < 1e-5 {
+            break;
+        }
+
+        if x < t {
+            low = s;
+        } else {
+            high = s;
+        }
+
+        s = (low + high) * 0.5;
+    }
+
+    bezier(y1, y2, s)
+}
@@ -0,0 +1,248 @@
+use crate::{
+    geometry::Rect,
+    ImageFlags,
+    ImageInfo,
+    Transform2D,
+    Vertex,
+};
+
+/// Determines how the edges and the center of a nine-slice image fill the destination:
+/// `Stretch` (default), `Tile`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum NineSliceMode {
+    /// Edges and center are scaled to fill the available space.
+    Stretch,
+    /// Edges and center are repeated at their original size. The last tile is cut off.
+    Tile,
+}
+
+impl Default for NineSliceMode {
+    fn default() -> Self {
+        Self::Stretch
+    }
+}
+
+/// Inset margins used to split an image into nine regions for nine-slice (nine-patch) drawing.
+///
+/// Margins are specified in image pixels. The four corners are drawn unscaled, the edges
+/// are resized along one axis and the center is resized along both axes.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct NineSlice {
+    left: f32,
+    top: f32,
+    right: f32,
+    bottom: f32,
+    mode: NineSliceMode,
+}
+
+impl NineSlice {
+    /// Creates a new nine-slice description from the provided inset margins.
+    pub fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            left: left.max(0.0),
+            top: top.max(0.0),
+            right: right.max(0.0),
+            bottom: bottom.max(0.0),
+            mode: Default::default(),
+        }
+    }
+
+    /// Creates a new nine-slice description with the same inset on all sides.
+    pub fn uniform(inset: f32) -> Self {
+        Self::new(inset, inset, inset, inset)
+    }
+
+    /// Returns the current fill mode for edges and center.
+    pub fn mode(&self) -> NineSliceMode {
+        self.mode
+    }
+
+    /// Sets how the edges and the center fill the destination rectangle.
+    ///
+    /// By default it's set to NineSliceMode::Stretch
+    pub fn set_mode(&mut self, mode: NineSliceMode) {
+        self.mode = mode;
+    }
+
+    /// Generates the triangle list for drawing an image with the provided info into `dest`.
+    pub(crate) fn vertices(&self, info: &ImageInfo, dest: Rect, transform: &Transform2D) -> Vec<Vertex> {
+        let image_width = info.width() as f32;
+        let image_height = info.height() as f32;
+
+        let mut verts = Vec::new();
+
+        if image_width <= 0.0 || image_height <= 0.0 || dest.w <= 0.0 || dest.h <= 0.0 {
+            return verts;
+        }
+
+        let columns = spans(dest.x, dest.w, image_width, self.left, self.right, self.mode);
+        let rows = spans(dest.y, dest.h, image_height, self.top, self.bottom, self.mode);
+
+        let flip_y = info.flags().contains(ImageFlags::FLIP_Y);
+
+        verts.reserve(columns.len() * rows.len() * 6);
+
+        for row in &rows {
+            let (t0, t1) = if flip_y {
+                (1.0 - row.src_start / image_height, 1.0 - row.src_end / image_height)
+            } else {
+                (row.src_start / image_height, row.src_end / image_height)
+            };
+
+            for column in &columns {
+                let s0 = column.src_start / image_width;
+                let s1 = column.src_end / image_width;
+
+                let (p0, p1) = transform.transform_point(column.dst_start, row.dst_start);
+                let (p2, p3) = transform.transform_point(column.dst_end, row.dst_start);
+                let (p4, p5) = transform.transform_point(column.dst_end, row.dst_end);
+                let (p6, p7) = transform.transform_point(column.dst_start, row.dst_end);
+
+                verts.push(Vertex::new(p0, p1, s0, t0));
+                verts.push(Vertex::new(p4, p5, s1, t1));
+                verts.push(Vertex::new(p2, p3, s1, t0));
+                verts.push(Vertex::new(p0, p1, s0, t0));
+                verts.push(Vertex::new(p6, p7, s0, t1));
+                verts.push(Vertex::new(p4, p5, s1, t1));
+            }
+        }
+
+        verts
+    }
+}
+
+// Upper bound of the tiles along one axis in NineSliceMode::Tile
+const MAX_TILES: f32 = 256.0;
+
+// Destination and source interval along one axis
+#[derive(Copy, Clone, Debug)]
+struct Span {
+    dst_start: f32,
+    dst_end: f32,
+    src_start: f32,
+    src_end: f32,
+}
+
+// Splits one axis of the destination rectangle into the spans needed to draw it.
+fn spans(dst_pos: f32, dst_len: f32, src_len: f32, inset_start: f32, inset_end: f32, mode: NineSliceMode) -> Vec<Span> {
+    let inset_start = inset_start.min(src_len);
+    let inset_end = inset_end.min(src_len - inset_start);
+
+    // Shrink the corners proportionally if they don't fit in the destination.
+    let corner_scale = if inset_start + inset_end > dst_len {
+        dst_len / (inset_start + inset_end)
+    } else {
+        1.0
+    };
+
+    let dst_inset_start = inset_start * corner_scale;
+    let dst_inset_end = inset_end * corner_scale;
+
+    let dst_mid_start = dst_pos + dst_inset_start;
+    let dst_mid_end = dst_pos + dst_len - dst_inset_end;
+    let src_mid_start = inset_start;
+    let src_mid_end = src_len - inset_end;
+
+    let mut spans = Vec::new();
+
+    spans.push(Span {
+        dst_start: dst_pos,
+        dst_end: dst_mid_start,
+        src_start: 0.0,
+        src_end: src_mid_start,
+    });
+
+    let src_mid_len = src_mid_end - src_mid_start;
+
+    // Tiles smaller than a pixel would generate an unreasonable amount of geometry
+    if mode == NineSliceMode::Tile && src_mid_len >= 1.0 {
+        // Tiles are stretched when there would be too many of them
+        let tile_len = src_mid_len.max((dst_mid_end - dst_mid_start) / MAX_TILES);
+        let src_scale = src_mid_len / tile_len;
+
+        let mut x = dst_mid_start;
+
+        while x < dst_mid_end {
+            let len = tile_len.min(dst_mid_end - x);
+
+            spans.push(Span {
+                dst_start: x,
+                dst_end: x + len,
+                src_start: src_mid_start,
+                src_end: src_mid_start + len * src_scale,
+            });
+
+            x += len;
+        }
+    } else {
+        spans.push(Span {
+            dst_start: dst_mid_start,
+            dst_end: dst_mid_end,
+            src_start: src_mid_start,
+            src_end: src_mid_end,
+        });
+    }
+
+    spans.push(Span {
+        dst_start: dst_mid_end,
+        dst_end: dst_pos + dst_len,
+        src_start: src_mid_end,
+        src_end: src_len,
+    });
+
+    spans.retain(|span| span.dst_end - span.dst_start > 0.0);
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::PixelFormat;
+
+    fn bounds(verts: &[Vertex]) -> [f32; 4] {
+        verts.iter().fold([f32::MAX, f32::MAX, f32::MIN, f32::MIN], |b, v| {
+            [b[0].min(v.x), b[1].min(v.y), b[2].max(v.x), b[3].max(v.y)]
+        })
+    }
+
+    #[test]
+    fn insets_shrink_to_fit() {
+        let info = ImageInfo::new(ImageFlags::empty(), 32, 32, PixelFormat::Rgba8);
+        let transform = Transform2D::identity();
+
+        // The 12 pixel insets are scaled down to 2 pixels to fit the 4 pixels, the center disappears
+        for &mode in &[NineSliceMode::Stretch, NineSliceMode::Tile] {
+            let mut slice = NineSlice::uniform(12.0);
+            slice.set_mode(mode);
+
+            let verts = slice.vertices(&info, Rect::new(10.0, 10.0, 4.0, 4.0), &transform);
+
+            assert_eq!(verts.len(), 4 * 6);
+            assert_eq!(bounds(&verts), [10.0, 10.0, 14.0, 14.0]);
+            assert_eq!(bounds(&verts[..6]), [10.0, 10.0, 12.0, 12.0]);
+            assert_eq!((verts[1].u, verts[1].v), (12.0 / 32.0, 12.0 / 32.0));
+
+            // Empty destinations produce nothing
+            assert!(slice
+                .vertices(&info, Rect::new(10.0, 10.0, 0.0, 50.0), &transform)
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn tile_count_is_bounded() {
+        // A one pixel center repeated over a million pixels
+        let info = ImageInfo::new(ImageFlags::empty(), 3, 3, PixelFormat::Rgba8);
+
+        let mut slice = NineSlice::uniform(1.0);
+        slice.set_mode(NineSliceMode::Tile);
+
+        let verts = slice.vertices(&info, Rect::new(0.0, 0.0, 1_000_000.0, 3.0), &Transform2D::identity());
+
+        // 256 tiles and two corners in each of the three rows
+        assert_eq!(verts.len(), (MAX_TILES as usize + 2) * 3 * 6);
+        assert_eq!(bounds(&verts)[2], 1_000_000.0);
+    }
+}
@@ -6,11 +6,18 @@ use crate::{
     Align,
     Baseline,
     Color,
+    ColorMatrix,
     FillRule,
+    FontFeature,
     FontId,
+    FontVariation,
+    GlyphRendering,
     ImageId,
     LineCap,
     LineJoin,
+    TextLanguage,
+    YuvMatrix,
+    YuvRange,
 };
 
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Default)]
@@ -110,6 +117,10 @@ pub(crate) enum PaintFlavor {
         height: f32,
         angle: f32,
         alpha: f32,
+        tint: Color,
+        color_matrix: Option<ColorMatrix>,
+        yuv_matrix: YuvMatrix,
+        yuv_range: YuvRange,
     },
     LinearGradient {
         start_x: f32,
@@ -148,6 +159,98 @@ impl PaintFlavor {
     }
 }
 
+/// Determines which channel of a mask image modulates the coverage: `Alpha` (default), `Luminance`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MaskMode {
+    /// The alpha channel of the mask image is used.
+    Alpha,
+    /// The luminance of the (premultiplied) mask image is used, like SVG `<mask>` elements.
+    Luminance,
+}
+
+impl Default for MaskMode {
+    fn default() -> Self {
+        Self::Alpha
+    }
+}
+
+/// An image that modulates the coverage of fills and strokes drawn with a [Paint](struct.Paint.html).
+///
+/// The mask image is stretched over the rectangle specified by `x`, `y`, `width` and `height`, which
+/// is positioned with the mask transform followed by the current canvas transform. Everything outside
+/// of this rectangle is masked out.
+///
+/// # Example
+/// ```
+/// use femtovg::{Canvas, Color, ImageFlags, Mask, Paint, Path, PixelFormat, renderer::Void};
+///
+/// let mut canvas = Canvas::new(Void).expect("Cannot create canvas");
+/// let gradient = canvas.create_image_empty(64, 64, PixelFormat::Gray8, ImageFlags::empty()).unwrap();
+///
+/// let mut paint = Paint::color(Color::rgb(200, 40, 40));
+/// paint.set_mask(Some(Mask::luminance(gradient, 10.0, 10.0, 100.0, 100.0)));
+///
+/// let mut path = Path::new();
+/// path.rect(10.0, 10.0, 100.0, 100.0);
+/// canvas.fill_path(&mut path, paint);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mask {
+    pub(crate) image: ImageId,
+    pub(crate) mode: MaskMode,
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+    pub(crate) transform: Transform2D,
+}
+
+impl Mask {
+    /// Creates a mask using the alpha channel of the image.
+    pub fn alpha(image: ImageId, x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self::new(image, MaskMode::Alpha, x, y, width, height)
+    }
+
+    /// Creates a mask using the luminance of the image.
+    pub fn luminance(image: ImageId, x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self::new(image, MaskMode::Luminance, x, y, width, height)
+    }
+
+    /// Creates a mask with the provided mode.
+    pub fn new(image: ImageId, mode: MaskMode, x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            image,
+            mode,
+            x,
+            y,
+            width,
+            height,
+            transform: Transform2D::identity(),
+        }
+    }
+
+    /// Returns the mask image.
+    pub fn image(&self) -> ImageId {
+        self.image
+    }
+
+    /// Returns the channel used for masking.
+    pub fn mode(&self) -> MaskMode {
+        self.mode
+    }
+
+    /// Returns the mask transform.
+    pub fn transform(&self) -> Transform2D {
+        self.transform
+    }
+
+    /// Sets a transform applied to the mask rectangle before the canvas transform.
+    /// This allows moving, rotating or scaling the mask independently of the masked shape.
+    pub fn set_transform(&mut self, transform: Transform2D) {
+        self.transform = transform;
+    }
+}
+
 /// Struct controlling how graphical shapes are rendered.
 ///
 /// The Paint struct is a relatively lightweight object which contains all the information needed to
@@ -180,6 +283,8 @@ pub struct Paint {
     pub(crate) transform: Transform2D,
     #[cfg_attr(feature = "serialization", serde(skip))]
     pub(crate) alpha_mask: Option<ImageId>,
+    #[cfg_attr(feature = "serialization", serde(skip))]
+    pub(crate) mask: Option<Mask>,
     pub(crate) shape_anti_alias: bool,
     pub(crate) stencil_strokes: bool,
     pub(crate) miter_limit: f32,
@@ -189,10 +294,14 @@ pub struct Paint {
     pub(crate) line_join: LineJoin,
     #[cfg_attr(feature = "serialization", serde(skip))]
     pub(crate) font_ids: [Option<FontId>; 8],
+    pub(crate) font_features: [Option<FontFeature>; 8],
+    pub(crate) font_variations: [Option<FontVariation>; 8],
+    pub(crate) language: Option<TextLanguage>,
     pub(crate) font_size: f32,
     pub(crate) letter_spacing: f32,
     pub(crate) text_baseline: Baseline,
     pub(crate) text_align: Align,
+    pub(crate) glyph_rendering: GlyphRendering,
     pub(crate) fill_rule: FillRule,
 }
 
@@ -202,6 +311,7 @@ impl Default for Paint {
             flavor: PaintFlavor::Color(Color::white()),
             transform: Default::default(),
             alpha_mask: Default::default(),
+            mask: Default::default(),
             shape_anti_alias: true,
             stencil_strokes: true,
             miter_limit: 10.0,
@@ -210,10 +320,14 @@ impl Default for Paint {
             line_cap_end: Default::default(),
             line_join: Default::default(),
             font_ids: Default::default(),
+            font_features: Default::default(),
+            font_variations: Default::default(),
+            language: None,
             font_size: 16.0,
             letter_spacing: 0.0,
             text_baseline: Default::default(),
             text_align: Default::default(),
+            glyph_rendering: Default::default(),
             fill_rule: Default::default(),
         }
     }
@@ -258,10 +372,41 @@ impl Paint {
             height,
             angle,
             alpha,
+            tint: Color::white(),
+            color_matrix: None,
+            yuv_matrix: Default::default(),
+            yuv_range: Default::default(),
         };
         new
     }
 
+    /// Creates a new image pattern paint with every pixel multiplied by the tint color.
+    ///
+    /// * `id` - is handle to the image to render
+    /// * `cx` `cy` - Specify the top-left location of the image pattern
+    /// * `width` `height` - The size of one image
+    /// * `angle` - Rotation around the top-left corner
+    /// * `tint` - Color the image is multiplied with. Its alpha is used as the image transparency
+    ///
+    /// # Example
+    /// ```
+    /// use femtovg::{Paint, Path, Color, Canvas, ImageFlags, PixelFormat, renderer::Void};
+    ///
+    /// let mut canvas = Canvas::new(Void).expect("Cannot create canvas");
+    ///
+    /// let image_id = canvas.create_image_empty(85, 85, PixelFormat::Rgba8, ImageFlags::empty()).unwrap();
+    /// let fill_paint = Paint::image_tint(image_id, 10.0, 10.0, 85.0, 85.0, 0.0, Color::rgbaf(1.0, 0.2, 0.2, 0.8));
+    ///
+    /// let mut path = Path::new();
+    /// path.rect(10.0, 10.0, 85.0, 85.0);
+    /// canvas.fill_path(&mut path, fill_paint);
+    /// ```
+    pub fn image_tint(id: ImageId, cx: f32, cy: f32, width: f32, height: f32, angle: f32, tint: Color) -> Self {
+        let mut new = Self::image(id, cx, cy, width, height, angle, 1.0);
+        new.set_tint(tint);
+        new
+    }
+
     /// Creates and returns a linear gradient paint.
     ///
     /// The gradient is transformed by the current transform when it is passed to fill_path() or stroke_path().
@@ -487,10 +632,74 @@ impl Paint {
         self.flavor = PaintFlavor::Color(color);
     }
 
+    /// Returns the tint color of an image paint or None for other paint types.
+    pub fn tint(&self) -> Option<Color> {
+        match self.flavor {
+            PaintFlavor::Image { tint, .. } => Some(tint),
+            _ => None,
+        }
+    }
+
+    /// Sets the color every pixel of the image is multiplied with.
+    ///
+    /// Only has effect on image paints.
+    pub fn set_tint(&mut self, color: Color) {
+        if let PaintFlavor::Image { tint, .. } = &mut self.flavor {
+            *tint = color;
+        }
+    }
+
+    /// Returns the color matrix of an image paint, if one is set.
+    pub fn color_matrix(&self) -> Option<ColorMatrix> {
+        match self.flavor {
+            PaintFlavor::Image { color_matrix, .. } => color_matrix,
+            _ => None,
+        }
+    }
+
+    /// Sets a color matrix that transforms the image colors before the tint is applied.
+    /// Passing None removes the matrix.
+    ///
+    /// Only has effect on image paints.
+    ///
+    /// # Example
+    /// ```
+    /// use femtovg::{Paint, ColorMatrix, ImageId};
+    ///
+    /// # fn sepia(image_id: ImageId) -> Paint {
+    /// let mut paint = Paint::image(image_id, 0.0, 0.0, 100.0, 100.0, 0.0, 1.0);
+    /// paint.set_color_matrix(Some(ColorMatrix::sepia(1.0)));
+    /// # paint
+    /// # }
+    /// ```
+    pub fn set_color_matrix(&mut self, matrix: Option<ColorMatrix>) {
+        if let PaintFlavor::Image { color_matrix, .. } = &mut self.flavor {
+            *color_matrix = matrix;
+        }
+    }
+
+    /// Sets how images with a YUV pixel format (`Nv12`, `I420`) are converted to RGB.
+    ///
+    /// Only has effect on image paints. By default BT.709 with limited range is used.
+    pub fn set_yuv_conversion(&mut self, matrix: YuvMatrix, range: YuvRange) {
+        if let PaintFlavor::Image {
+            yuv_matrix, yuv_range, ..
+        } = &mut self.flavor
+        {
+            *yuv_matrix = matrix;
+            *yuv_range = range;
+        }
+    }
+
     pub(crate) fn alpha_mask(&self) -> Option<ImageId> {
         self.alpha_mask
     }
 
+    // The texture bound as mask for commands drawn with this paint. Glyph masks take precedence.
+    pub(crate) fn mask_image(&self) -> Option<ImageId> {
+        self.alpha_mask.or_else(|| self.mask.map(|mask| mask.image))
+    }
+
     /// Set an alpha mask; this is only used by draw_triangles which is used for text.
     // This is scoped to crate visibility because fill_path and stroke_path don't propagate
     // the alpha mask (so nothing draws), and the texture coordinates are used for antialiasing
@@ -499,6 +708,19 @@ impl Paint {
         self.alpha_mask = image_id;
     }
 
+    /// Returns the mask applied to shapes drawn with this paint.
+    pub fn mask(&self) -> Option<Mask> {
+        self.mask
+    }
+
+    /// Sets an image mask modulating the coverage of fills and strokes drawn with this paint.
+    /// Passing None removes the mask.
+    ///
+    /// Text rendered through the glyph atlas is not affected by the mask.
+    pub fn set_mask(&mut self, mask: Option<Mask>) {
+        self.mask = mask;
+    }
+
     /// Returns boolean if the shapes drawn with this paint will be antialiased.
     pub fn anti_alias(&self) -> bool {
         self.shape_anti_alias
@@ -593,6 +815,52 @@ impl Paint {
         }
     }
 
+    /// Returns the OpenType features the text is shaped with
+    pub fn font_features(&self) -> impl Iterator<Item = FontFeature> + '_ {
+        self.font_features.iter().flatten().copied()
+    }
+
+    /// Sets up to 8 OpenType features the text is shaped with, like tabular numbers or
+    /// disabled ligatures. Features not listed keep the font's defaults.
+    ///
+    /// Only has effect on canvas text operations
+    pub fn set_font_features(&mut self, features: &[FontFeature]) {
+        self.font_features = Default::default();
+
+        for (i, feature) in features.iter().take(8).enumerate() {
+            self.font_features[i] = Some(*feature);
+        }
+    }
+
+    /// Returns the variable font axis values the text is shaped and drawn with
+    pub fn font_variations(&self) -> impl Iterator<Item = FontVariation> + '_ {
+        self.font_variations.iter().flatten().copied()
+    }
+
+    /// Sets up to 8 variable font axis values, like the weight or optical size. They apply on
+    /// top of the values of font instances, axes not listed keep the instance's values.
+    ///
+    /// Only has effect on canvas text operations
+    pub fn set_font_variations(&mut self, variations: &[FontVariation]) {
+        self.font_variations = Default::default();
+
+        for (i, variation) in variations.iter().take(8).enumerate() {
+            self.font_variations[i] = Some(*variation);
+        }
+    }
+
+    /// Returns the language the text is shaped for
+    pub fn language(&self) -> Option<TextLanguage> {
+        self.language
+    }
+
+    /// Sets the language the text is shaped for, which selects language specific glyphs.
+    ///
+    /// Only has effect on canvas text operations
+    pub fn set_language(&mut self, language: Option<TextLanguage>) {
+        self.language = language;
+    }
+
     /// Returns the current font size
     ///
     /// Only has effect on canvas text operations
@@ -643,6 +911,19 @@ impl Paint {
         self.text_align = align;
     }
 
+    /// Returns how glyphs are rendered into the glyph atlases
+    pub fn glyph_rendering(&self) -> GlyphRendering {
+        self.glyph_rendering
+    }
+
+    /// Sets how glyphs are rendered into the glyph atlases, distance fields let text be scaled
+    /// and rotated without rasterizing the glyphs again
+    ///
+    /// Only has effect on canvas text operations
+    pub fn set_glyph_rendering(&mut self, rendering: GlyphRendering) {
+        self.glyph_rendering = rendering;
+    }
+
     /// Retrieves the current fill rule setting for this paint
     pub fn fill_rule(&self) -> FillRule {
         self.fill_rule
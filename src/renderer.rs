@@ -10,6 +10,7 @@ use crate::{
     FillRule,
     ImageId,
     ImageInfo,
+    ImageSampler,
     ImageSource,
     ImageStore,
 };
@@ -33,6 +34,11 @@ pub use webgpu::{
 mod void;
 pub use void::Void;
 
+#[cfg(test)]
+mod recorder;
+#[cfg(test)]
+pub(crate) use recorder::Recorder;
+
 mod params;
 pub(crate) use params::Params;
 
@@ -120,27 +126,88 @@ pub trait Renderer {
     fn update_image(&mut self, image: &mut Self::Image, data: ImageSource, x: usize, y: usize)
         -> Result<(), ErrorKind>;
     fn delete_image(&mut self, image: Self::Image);
+    fn set_image_sampler(&mut self, image: &mut Self::Image, sampler: ImageSampler);
+
+    /// Regenerates the mip chain of an image from its base level. Does nothing for images
+    /// without `ImageFlags::GENERATE_MIPMAPS`.
+    fn generate_mipmaps(&mut self, image: &Self::Image);
 
     fn screenshot(&mut self) -> Result<ImgVec<RGBA8>, ErrorKind>;
+
+    /// Returns true if images rendered into through `RenderTarget::Image` end up upside down
+    /// when drawn, in which case they need `ImageFlags::FLIP_Y`.
+    fn flip_y(&self) -> bool {
+        false
+    }
+
+    /// Reads back a region of an image. The region is specified top down, the way the image is drawn,
+    /// so rows of `ImageFlags::FLIP_Y` images are read bottom up. It has already been validated against
+    /// the image bounds.
+    fn read_image(
+        &mut self,
+        images: &ImageStore<Self::Image>,
+        id: ImageId,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<ImgVec<RGBA8>, ErrorKind>;
+
+    /// Copies a region of the src image into the dst image. Both images have the same format
+    /// and the region has already been validated against their bounds.
+    #[allow(clippy::too_many_arguments)]
+    fn copy_image(
+        &mut self,
+        images: &ImageStore<Self::Image>,
+        src: ImageId,
+        dst: ImageId,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        dst_x: usize,
+        dst_y: usize,
+    ) -> Result<(), ErrorKind>;
 }
 
 /// Vertex struct for specifying triangle geometry
-#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Default)]
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
 #[repr(C)]
 pub struct Vertex {
     pub x: f32,
     pub y: f32,
     pub u: f32,
     pub v: f32,
+    // Premultiplied color the textured triangles are multiplied with
+    pub(crate) tint: RGBA8,
 }
 
 impl Vertex {
     pub fn new(x: f32, y: f32, u: f32, v: f32) -> Self {
-        Self { x, y, u, v }
+        Self::with_tint(x, y, u, v, RGBA8::new(255, 255, 255, 255))
+    }
+
+    /// Creates a vertex of textured triangles whose color is multiplied with the premultiplied `tint`.
+    pub fn with_tint(x: f32, y: f32, u: f32, v: f32, tint: RGBA8) -> Self {
+        Self { x, y, u, v, tint }
     }
 
     pub fn set(&mut self, x: f32, y: f32, u: f32, v: f32) {
-        *self = Self { x, y, u, v };
+        self.x = x;
+        self.y = y;
+        self.u = u;
+        self.v = v;
+    }
+
+    /// Returns the premultiplied color the textured triangles are multiplied with.
+    pub fn tint(&self) -> RGBA8 {
+        self.tint
+    }
+}
+
+impl Default for Vertex {
+    fn default() -> Self {
+        Self::new(0.0, 0.0, 0.0, 0.0)
     }
 }
 
@@ -150,6 +217,7 @@ pub enum ShaderType {
     FillImage,
     Stencil,
     FillImageGradient,
+    TexturedTris,
 }
 
 impl Default for ShaderType {
@@ -165,6 +233,7 @@ impl ShaderType {
             Self::FillImage => 1.0,
             Self::Stencil => 2.0,
             Self::FillImageGradient => 3.0,
+            Self::TexturedTris => 4.0,
         }
     }
 }
@@ -18,7 +18,9 @@ use crate::{
     CompositeOperationState,
     ErrorKind,
     FillRule,
+    ImageFlags,
     ImageInfo,
+    ImageSampler,
     ImageSource,
     ImageStore,
 };
@@ -37,7 +39,10 @@ mod program;
 use program::MainProgram;
 
 mod gl_texture;
-use gl_texture::GlTexture;
+use gl_texture::{
+    GlTexture,
+    TextureCaps,
+};
 
 mod framebuffer;
 use framebuffer::Framebuffer;
@@ -49,6 +54,7 @@ pub struct OpenGl {
     debug: bool,
     antialias: bool,
     is_opengles_2_0: bool,
+    texture_caps: TextureCaps,
     view: [f32; 2],
     screen_view: [f32; 2],
     main_program: MainProgram,
@@ -57,6 +63,7 @@ pub struct OpenGl {
     framebuffers: FnvHashMap<ImageId, Result<Framebuffer, ErrorKind>>,
     context: Rc<glow::Context>,
     screen_target: Option<Framebuffer>,
+    current_target: RenderTarget,
 }
 
 impl OpenGl {
@@ -98,6 +105,7 @@ impl OpenGl {
             debug: debug,
             antialias: antialias,
             is_opengles_2_0: false,
+            texture_caps: TextureCaps::query(&context, is_opengles_2_0),
             view: [0.0, 0.0],
             screen_view: [0.0, 0.0],
             main_program: main_program,
@@ -106,6 +114,7 @@ impl OpenGl {
             framebuffers: Default::default(),
             context: context.clone(),
             screen_target: None,
+            current_target: RenderTarget::Screen,
         };
 
         unsafe {
@@ -369,22 +378,39 @@ impl OpenGl {
         self.main_program.set_config(arr.as_slice());
         self.check_error("set_uniforms uniforms");
 
-        let tex = image_tex
-            .and_then(|id| images.get(id))
-            .map_or(None, |tex| Some(tex.id()));
+        let tex = image_tex.and_then(|id| images.get(id));
 
         unsafe {
             self.context.active_texture(glow::TEXTURE0);
-            self.context.bind_texture(glow::TEXTURE_2D, tex);
+            self.context.bind_texture(glow::TEXTURE_2D, tex.map(GlTexture::id));
         }
 
-        let masktex = alpha_tex
-            .and_then(|id| images.get(id))
-            .map_or(None, |tex| Some(tex.id()));
+        let masktex = alpha_tex.and_then(|id| images.get(id));
 
         unsafe {
             self.context.active_texture(glow::TEXTURE0 + 1);
-            self.context.bind_texture(glow::TEXTURE_2D, masktex);
+            self.context.bind_texture(glow::TEXTURE_2D, masktex.map(GlTexture::id));
+        }
+
+        // External textures are sampled through sampler objects, leaving their own parameters alone
+        if !self.is_opengles_2_0 {
+            unsafe {
+                self.context.bind_sampler(0, tex.and_then(GlTexture::sampler));
+                self.context.bind_sampler(1, masktex.and_then(GlTexture::sampler));
+            }
+        }
+
+        // Chroma planes of YUV images
+        let planes = image_tex
+            .and_then(|id| images.get(id))
+            .map_or(&[][..], |tex| tex.planes());
+
+        unsafe {
+            self.context.active_texture(glow::TEXTURE0 + 2);
+            self.context.bind_texture(glow::TEXTURE_2D, planes.get(0).copied());
+            self.context.active_texture(glow::TEXTURE0 + 3);
+            self.context.bind_texture(glow::TEXTURE_2D, planes.get(1).copied());
+            self.context.active_texture(glow::TEXTURE0);
         }
 
         self.check_error("set_uniforms texture");
@@ -405,7 +431,18 @@ impl OpenGl {
         }
     }
 
+    // Rendering only writes the base level, so an image target's mip chain is stale afterwards
+    fn generate_target_mipmaps(&self, images: &ImageStore<GlTexture>) {
+        if let RenderTarget::Image(id) = self.current_target {
+            if let Some(texture) = images.get(id) {
+                texture.generate_mipmaps();
+            }
+        }
+    }
+
     fn set_target(&mut self, images: &ImageStore<GlTexture>, target: RenderTarget) {
+        self.current_target = target;
+
         match (target, &self.screen_target) {
             (RenderTarget::Screen, None) => unsafe {
                 Framebuffer::unbind(&self.context);
@@ -442,6 +479,30 @@ impl OpenGl {
         }
     }
 
+    pub(crate) fn import_texture(
+        &self,
+        texture: <glow::Context as glow::HasContext>::Texture,
+        info: ImageInfo,
+    ) -> Result<GlTexture, ErrorKind> {
+        GlTexture::from_external(&self.context, texture, info, self.texture_caps)
+    }
+
+    // Returns the framebuffer used to render into the image, creating it when needed
+    fn image_framebuffer(&mut self, id: ImageId, texture: &GlTexture) -> Result<&Framebuffer, ErrorKind> {
+        let context = self.context.clone();
+
+        match self
+            .framebuffers
+            .entry(id)
+            .or_insert_with(|| Framebuffer::new(&context, texture))
+        {
+            Ok(fb) => Ok(fb),
+            Err(_) => Err(ErrorKind::RenderTargetError(
+                "Failed to create a framebuffer for the image".to_owned(),
+            )),
+        }
+    }
+
     /// Make the "Screen" RenderTarget actually render to a framebuffer object. This is useful when
     /// embedding femtovg into another program where final composition is handled by an external task.
     /// The given `framebuffer_object` must refer to a Framebuffer Object created on the current OpenGL
@@ -508,6 +569,7 @@ impl Renderer for OpenGl {
 
             self.context.enable_vertex_attrib_array(0);
             self.context.enable_vertex_attrib_array(1);
+            self.context.enable_vertex_attrib_array(2);
 
             self.context
                 .vertex_attrib_pointer_f32(0, 2, glow::FLOAT, false, vertex_size as i32, 0);
@@ -519,11 +581,21 @@ impl Renderer for OpenGl {
                 vertex_size as i32,
                 2 * mem::size_of::<f32>() as i32,
             );
+            self.context.vertex_attrib_pointer_f32(
+                2,
+                4,
+                glow::UNSIGNED_BYTE,
+                true,
+                vertex_size as i32,
+                4 * mem::size_of::<f32>() as i32,
+            );
         }
 
-        // Bind the two uniform samplers to texture units
+        // Bind the uniform samplers to texture units
         self.main_program.set_tex(0);
         self.main_program.set_masktex(1);
+        self.main_program.set_planetex1(2);
+        self.main_program.set_planetex2(3);
 
         self.check_error("render prepare");
 
@@ -549,20 +621,32 @@ impl Renderer for OpenGl {
                     self.clear_rect(*x, *y, *width, *height, *color);
                 }
                 CommandType::SetRenderTarget(target) => {
+                    if self.current_target != *target {
+                        self.generate_target_mipmaps(images);
+                    }
+
                     self.set_target(images, *target);
                     self.main_program.set_view(self.view);
                 }
             }
         }
 
+        self.generate_target_mipmaps(images);
+
         unsafe {
             self.context.disable_vertex_attrib_array(0);
             self.context.disable_vertex_attrib_array(1);
+            self.context.disable_vertex_attrib_array(2);
             self.context.bind_vertex_array(None);
 
             self.context.disable(glow::CULL_FACE);
             self.context.bind_buffer(glow::ARRAY_BUFFER, None);
             self.context.bind_texture(glow::TEXTURE_2D, None);
+
+            if !self.is_opengles_2_0 {
+                self.context.bind_sampler(0, None);
+                self.context.bind_sampler(1, None);
+            }
         }
 
         self.main_program.unbind();
@@ -571,7 +655,7 @@ impl Renderer for OpenGl {
     }
 
     fn alloc_image(&mut self, info: ImageInfo) -> Result<Self::Image, ErrorKind> {
-        Self::Image::new(&self.context, info, self.is_opengles_2_0)
+        Self::Image::new(&self.context, info, self.texture_caps)
     }
 
     fn update_image(
@@ -581,13 +665,26 @@ impl Renderer for OpenGl {
         x: usize,
         y: usize,
     ) -> Result<(), ErrorKind> {
-        image.update(data, x, y, self.is_opengles_2_0)
+        image.update(data, x, y, self.texture_caps)
     }
 
     fn delete_image(&mut self, image: Self::Image) {
         image.delete();
     }
 
+    fn generate_mipmaps(&mut self, image: &Self::Image) {
+        image.generate_mipmaps();
+    }
+
+    fn set_image_sampler(&mut self, image: &mut Self::Image, sampler: ImageSampler) {
+        image.set_sampler(sampler, self.texture_caps);
+    }
+
+    // The framebuffer origin is at the bottom left
+    fn flip_y(&self) -> bool {
+        true
+    }
+
     fn screenshot(&mut self) -> Result<ImgVec<RGBA8>, ErrorKind> {
         //let mut image = image::RgbaImage::new(self.view[0] as u32, self.view[1] as u32);
         let w = self.view[0] as usize;
@@ -627,6 +724,101 @@ impl Renderer for OpenGl {
 
         Ok(ImgVec::new(flipped, w, h))
     }
+
+    fn read_image(
+        &mut self,
+        images: &ImageStore<Self::Image>,
+        id: ImageId,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<ImgVec<RGBA8>, ErrorKind> {
+        let texture = images.get(id).ok_or(ErrorKind::ImageIdNotFound)?;
+
+        self.image_framebuffer(id, texture)?.bind();
+
+        let flip_y = texture.info().flags().contains(ImageFlags::FLIP_Y);
+        let texture_y = texture.info().texture_y(y, height);
+
+        let mut image = ImgVec::new(vec![RGBA8::default(); width * height], width, height);
+
+        unsafe {
+            self.context.read_pixels(
+                x as i32,
+                texture_y as i32,
+                width as i32,
+                height as i32,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelPackData::Slice(image.buf_mut().align_to_mut().1),
+            );
+        }
+
+        // Restore the framebuffer the canvas is currently rendering to
+        self.set_target(images, self.current_target);
+
+        if !flip_y {
+            return Ok(image);
+        }
+
+        let mut flipped = Vec::with_capacity(width * height);
+
+        for row in image.rows().rev() {
+            flipped.extend_from_slice(row);
+        }
+
+        Ok(ImgVec::new(flipped, width, height))
+    }
+
+    fn copy_image(
+        &mut self,
+        images: &ImageStore<Self::Image>,
+        src: ImageId,
+        dst: ImageId,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        dst_x: usize,
+        dst_y: usize,
+    ) -> Result<(), ErrorKind> {
+        if self.is_opengles_2_0 {
+            return Err(ErrorKind::RenderTargetError(
+                "Copying images requires framebuffer blits, which are not supported by WebGL 1".to_owned(),
+            ));
+        }
+
+        let src_texture = images.get(src).ok_or(ErrorKind::ImageIdNotFound)?;
+        let dst_texture = images.get(dst).ok_or(ErrorKind::ImageIdNotFound)?;
+
+        self.image_framebuffer(src, src_texture)?;
+        self.image_framebuffer(dst, dst_texture)?;
+
+        // Rows of FLIP_Y images are stored bottom up, so the copy is flipped if only one of them has the flag
+        let src_y = src_texture.info().texture_y(y, height);
+        let dst_y = dst_texture.info().texture_y(dst_y, height);
+        let flip_y = src_texture.info().flags().contains(ImageFlags::FLIP_Y)
+            != dst_texture.info().flags().contains(ImageFlags::FLIP_Y);
+
+        if let (Some(Ok(src_fb)), Some(Ok(dst_fb))) = (self.framebuffers.get(&src), self.framebuffers.get(&dst)) {
+            src_fb.blit_to(
+                dst_fb,
+                x as i32,
+                src_y as i32,
+                dst_x as i32,
+                dst_y as i32,
+                width as i32,
+                height as i32,
+                flip_y,
+            );
+        }
+
+        // Restore the framebuffer the canvas is currently rendering to
+        self.set_target(images, self.current_target);
+
+        Ok(())
+    }
 }
 
 impl Drop for OpenGl {
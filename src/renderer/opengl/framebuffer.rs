@@ -122,30 +122,47 @@ impl Framebuffer {
         }
     }
 
-    // pub fn blit_to_texture(&self, texture: &GlTexture) {
-    //     let dest_fbo = Self::new(texture);
-
-    //     unsafe {
-    //         glow::BindFramebuffer(glow::READ_FRAMEBUFFER, self.fbo);
-    //         glow::BindFramebuffer(glow::DRAW_FRAMEBUFFER, dest_fbo.fbo);
-
-    //         glow::BlitFramebuffer(
-    //             0,
-    //             0,
-    //             self.width as i32,
-    //             self.height as i32,
-    //             0,
-    //             0,
-    //             dest_fbo.width as i32,
-    //             dest_fbo.height as i32,
-    //             glow::COLOR_BUFFER_BIT,
-    //             glow::NEAREST
-    //         );
-
-    //         glow::BindFramebuffer(glow::READ_FRAMEBUFFER, 0);
-    //         glow::BindFramebuffer(glow::DRAW_FRAMEBUFFER, 0);
-    //     }
-    // }
+    // Copies a region of this framebuffer's color attachment into the destination framebuffer
+    #[allow(clippy::too_many_arguments)]
+    pub fn blit_to(
+        &self,
+        dest: &Framebuffer,
+        src_x: i32,
+        src_y: i32,
+        dst_x: i32,
+        dst_y: i32,
+        width: i32,
+        height: i32,
+        flip_y: bool,
+    ) {
+        // Swapping the destination rows mirrors the copied region
+        let (dst_y0, dst_y1) = if flip_y {
+            (dst_y + height, dst_y)
+        } else {
+            (dst_y, dst_y + height)
+        };
+
+        unsafe {
+            self.context.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(self.fbo));
+            self.context.bind_framebuffer(glow::DRAW_FRAMEBUFFER, Some(dest.fbo));
+
+            self.context.blit_framebuffer(
+                src_x,
+                src_y,
+                src_x + width,
+                src_y + height,
+                dst_x,
+                dst_y0,
+                dst_x + width,
+                dst_y1,
+                glow::COLOR_BUFFER_BIT,
+                glow::NEAREST,
+            );
+
+            self.context.bind_framebuffer(glow::READ_FRAMEBUFFER, None);
+            self.context.bind_framebuffer(glow::DRAW_FRAMEBUFFER, None);
+        }
+    }
 }
 
 impl Drop for Framebuffer {
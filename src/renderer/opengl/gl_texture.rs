@@ -1,28 +1,133 @@
-use std::rc::Rc;
+use std::{
+    borrow::Cow,
+    rc::Rc,
+};
 
 use crate::{
+    image::{
+        chroma_size,
+        validate_chroma_planes,
+    },
     ErrorKind,
+    ImageFilter,
     ImageFlags,
     ImageInfo,
+    ImageSampler,
     ImageSource,
+    MipmapMode,
     PixelFormat,
+    WrapMode,
 };
 
 use glow::HasContext;
+use imgref::ImgRef;
+use rgb::{
+    alt::GRAYA8,
+    RGBA8,
+};
+
+// Texture features that depend on the GL version and extensions
+#[derive(Copy, Clone, Debug, Default)]
+pub struct TextureCaps {
+    pub opengles_2_0: bool,
+    pub gles: bool,
+    // GL 3.3 or GLES 3.0, gray alpha textures are expanded to RGBA before upload otherwise
+    pub swizzle: bool,
+    // Desktop GL or EXT_texture_format_BGRA8888, BGRA data is converted to RGBA before upload otherwise
+    pub bgra: bool,
+}
+
+impl TextureCaps {
+    pub fn query(context: &glow::Context, opengles_2_0: bool) -> Self {
+        if opengles_2_0 {
+            return Self {
+                opengles_2_0,
+                gles: true,
+                ..Default::default()
+            };
+        }
+
+        let version = unsafe { context.get_parameter_string(glow::VERSION) };
+        let (gles, major, minor) = parse_version(&version).unwrap_or((false, 2, 0));
+
+        let has_extension = |name: &str| unsafe {
+            if major >= 3 {
+                let count = context.get_parameter_i32(glow::NUM_EXTENSIONS);
+                (0..count).any(|i| context.get_parameter_indexed_string(glow::EXTENSIONS, i as u32) == name)
+            } else {
+                context
+                    .get_parameter_string(glow::EXTENSIONS)
+                    .split_whitespace()
+                    .any(|extension| extension == name)
+            }
+        };
+
+        let swizzle = if gles {
+            major >= 3
+        } else {
+            (major, minor) >= (3, 3) || has_extension("GL_ARB_texture_swizzle")
+        };
+
+        let bgra = !gles || has_extension("GL_EXT_texture_format_BGRA8888");
+
+        Self {
+            opengles_2_0,
+            gles,
+            swizzle,
+            bgra,
+        }
+    }
+}
+
+// Parses GL_VERSION strings like "4.6.0 NVIDIA 510.47" or "OpenGL ES 3.2 Mesa 21.2" into (is GLES, major, minor)
+fn parse_version(version: &str) -> Option<(bool, u32, u32)> {
+    let (gles, version) = match version.strip_prefix("OpenGL ES") {
+        Some(rest) => (
+            true,
+            rest.trim_start().trim_start_matches("-CM ").trim_start_matches("-CL "),
+        ),
+        None => (false, version),
+    };
+
+    let number = version.split_whitespace().next()?;
+    let mut parts = number.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts
+        .next()?
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<String>()
+        .parse()
+        .ok()?;
+
+    Some((gles, major, minor))
+}
 
 pub struct GlTexture {
     context: Rc<glow::Context>,
     id: <glow::Context as glow::HasContext>::Texture,
+    // Chroma planes of YUV images
+    planes: Vec<<glow::Context as glow::HasContext>::Texture>,
+    // Sampling options of external textures, whose own parameters belong to the application
+    sampler: Option<<glow::Context as glow::HasContext>::Sampler>,
     info: ImageInfo,
 }
 
 impl GlTexture {
-    pub fn new(context: &Rc<glow::Context>, info: ImageInfo, opengles_2_0: bool) -> Result<Self, ErrorKind> {
+    pub fn new(context: &Rc<glow::Context>, info: ImageInfo, caps: TextureCaps) -> Result<Self, ErrorKind> {
         //let size = src.dimensions();
+        let opengles_2_0 = caps.opengles_2_0;
+
+        // Float textures are not part of WebGL 1
+        if opengles_2_0 && info.format() == PixelFormat::Rgba16F {
+            return Err(ErrorKind::UnsuportedImageFromat);
+        }
 
         let mut texture = Self {
             context: context.clone(),
             id: Default::default(),
+            planes: Vec::new(),
+            sampler: None,
             info: info,
         };
 
@@ -38,111 +143,107 @@ impl GlTexture {
         }
 
         match info.format() {
-            PixelFormat::Gray8 => unsafe {
-                let internal_format = if opengles_2_0 { glow::LUMINANCE } else { glow::R8 };
-                let format = if opengles_2_0 { internal_format } else { glow::RED };
-
+            PixelFormat::Gray8 | PixelFormat::Nv12 | PixelFormat::I420 => unsafe {
+                alloc_gray(context, texture.info.width(), texture.info.height(), opengles_2_0);
+            },
+            PixelFormat::Rgb8 => unsafe {
                 context.tex_image_2d(
                     glow::TEXTURE_2D,
                     0,
-                    internal_format as i32,
+                    glow::RGB as i32,
                     texture.info.width() as i32,
                     texture.info.height() as i32,
                     0,
-                    format,
+                    glow::RGB,
                     glow::UNSIGNED_BYTE,
-                    None, //data.buf().as_ptr() as *const GLvoid
+                    None,
+                    //data.buf().as_ptr() as *const GLvoid
                 );
             },
-            PixelFormat::Rgb8 => unsafe {
+            PixelFormat::Rgba8 => unsafe {
                 context.tex_image_2d(
                     glow::TEXTURE_2D,
                     0,
-                    glow::RGB as i32,
+                    glow::RGBA as i32,
                     texture.info.width() as i32,
                     texture.info.height() as i32,
                     0,
-                    glow::RGB,
+                    glow::RGBA,
                     glow::UNSIGNED_BYTE,
                     None,
                     //data.buf().as_ptr() as *const GLvoid
                 );
             },
-            PixelFormat::Rgba8 => unsafe {
+            PixelFormat::GrayAlpha8 => unsafe {
+                alloc_gray_alpha(context, texture.info.width(), texture.info.height(), caps);
+            },
+            PixelFormat::Bgra8 => unsafe {
+                // Without BGRA support update converts the data to RGBA instead
+                let format = if caps.bgra { glow::BGRA } else { glow::RGBA };
+                // GLES wants matching internal and external formats
+                let internal_format = if caps.bgra && !caps.gles { glow::RGBA } else { format };
+
                 context.tex_image_2d(
                     glow::TEXTURE_2D,
                     0,
-                    glow::RGBA as i32,
+                    internal_format as i32,
                     texture.info.width() as i32,
                     texture.info.height() as i32,
                     0,
-                    glow::RGBA,
+                    format,
                     glow::UNSIGNED_BYTE,
                     None,
-                    //data.buf().as_ptr() as *const GLvoid
+                );
+            },
+            PixelFormat::Rgba16F => unsafe {
+                context.tex_image_2d(
+                    glow::TEXTURE_2D,
+                    0,
+                    glow::RGBA16F as i32,
+                    texture.info.width() as i32,
+                    texture.info.height() as i32,
+                    0,
+                    glow::RGBA,
+                    glow::HALF_FLOAT,
+                    None,
                 );
             },
         }
 
         let flags = texture.info.flags();
 
-        if flags.contains(ImageFlags::GENERATE_MIPMAPS) {
-            if flags.contains(ImageFlags::NEAREST) {
-                unsafe {
-                    context.tex_parameter_i32(
-                        glow::TEXTURE_2D,
-                        glow::TEXTURE_MIN_FILTER,
-                        glow::NEAREST_MIPMAP_NEAREST as i32,
-                    );
-                }
-            } else {
-                unsafe {
-                    context.tex_parameter_i32(
-                        glow::TEXTURE_2D,
-                        glow::TEXTURE_MIN_FILTER,
-                        glow::LINEAR_MIPMAP_LINEAR as i32,
-                    );
-                }
-            }
-        } else {
-            if flags.contains(ImageFlags::NEAREST) {
-                unsafe {
-                    context.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, glow::NEAREST as i32);
-                }
-            } else {
-                unsafe {
-                    context.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, glow::LINEAR as i32);
-                }
-            }
+        unsafe {
+            set_sampler_params(context, texture.info, opengles_2_0);
         }
 
-        if flags.contains(ImageFlags::NEAREST) {
-            unsafe {
-                context.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, glow::NEAREST as i32);
-            }
-        } else {
-            unsafe {
-                context.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, glow::LINEAR as i32);
-            }
-        }
+        if info.format().is_yuv() {
+            let (width, height) = chroma_size(info.width(), info.height());
 
-        if flags.contains(ImageFlags::REPEAT_X) {
-            unsafe {
-                context.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, glow::REPEAT as i32);
-            }
-        } else {
-            unsafe {
-                context.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as i32);
-            }
-        }
+            let plane_count = if info.format() == PixelFormat::Nv12 { 1 } else { 2 };
 
-        if flags.contains(ImageFlags::REPEAT_Y) {
-            unsafe {
-                context.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_T, glow::REPEAT as i32);
+            for _ in 0..plane_count {
+                unsafe {
+                    let plane = context.create_texture().unwrap();
+                    context.bind_texture(glow::TEXTURE_2D, Some(plane));
+
+                    if info.format() == PixelFormat::Nv12 {
+                        alloc_gray_alpha(context, width, height, caps);
+                    } else {
+                        alloc_gray(context, width, height, opengles_2_0);
+                    }
+
+                    set_sampler_params(context, texture.info, opengles_2_0);
+
+                    if flags.contains(ImageFlags::GENERATE_MIPMAPS) {
+                        context.generate_mipmap(glow::TEXTURE_2D);
+                    }
+
+                    texture.planes.push(plane);
+                }
             }
-        } else {
+
             unsafe {
-                context.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as i32);
+                context.bind_texture(glow::TEXTURE_2D, Some(texture.id));
             }
         }
 
@@ -169,12 +270,48 @@ impl GlTexture {
         Ok(texture)
     }
 
+    // Wraps a texture created outside of femtovg
+    pub fn from_external(
+        context: &Rc<glow::Context>,
+        id: <glow::Context as glow::HasContext>::Texture,
+        info: ImageInfo,
+        caps: TextureCaps,
+    ) -> Result<Self, ErrorKind> {
+        if info.format().is_yuv() {
+            return Err(ErrorKind::UnsuportedImageFromat);
+        }
+
+        // WebGL 1 has no sampler objects, the texture is sampled with its own parameters there
+        let sampler = if caps.opengles_2_0 {
+            None
+        } else {
+            unsafe {
+                let sampler = context.create_sampler().map_err(ErrorKind::GeneralError)?;
+                set_sampler_object_params(context, sampler, info);
+                Some(sampler)
+            }
+        };
+
+        Ok(Self {
+            context: context.clone(),
+            id,
+            planes: Vec::new(),
+            sampler,
+            info,
+        })
+    }
+
     pub fn id(&self) -> <glow::Context as glow::HasContext>::Texture {
         self.id
     }
 
-    pub fn update(&mut self, src: ImageSource, x: usize, y: usize, opengles_2_0: bool) -> Result<(), ErrorKind> {
+    pub fn sampler(&self) -> Option<<glow::Context as glow::HasContext>::Sampler> {
+        self.sampler
+    }
+
+    pub fn update(&mut self, src: ImageSource, x: usize, y: usize, caps: TextureCaps) -> Result<(), ErrorKind> {
         let size = src.dimensions();
+        let opengles_2_0 = caps.opengles_2_0;
 
         if x + size.0 > self.info.width() {
             return Err(ErrorKind::ImageUpdateOutOfBounds);
@@ -188,6 +325,8 @@ impl GlTexture {
             return Err(ErrorKind::ImageUpdateWithDifferentFormat);
         }
 
+        validate_chroma_planes(&src, &self.info, x, y)?;
+
         unsafe {
             self.context.bind_texture(glow::TEXTURE_2D, Some(self.id));
             self.context.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
@@ -238,6 +377,122 @@ impl GlTexture {
                     glow::PixelUnpackData::Slice(data.buf().align_to().1),
                 );
             },
+            ImageSource::GrayAlpha(data) => unsafe {
+                let (format, data) = gray_alpha_data(data, caps);
+
+                self.context.tex_sub_image_2d(
+                    glow::TEXTURE_2D,
+                    0,
+                    x as i32,
+                    y as i32,
+                    size.0 as i32,
+                    size.1 as i32,
+                    format,
+                    glow::UNSIGNED_BYTE,
+                    glow::PixelUnpackData::Slice(&data),
+                );
+            },
+            ImageSource::Bgra(data) => unsafe {
+                if !caps.bgra {
+                    let rgba: Vec<RGBA8> = data.pixels().map(|px| RGBA8::new(px.r, px.g, px.b, px.a)).collect();
+
+                    self.context.tex_sub_image_2d(
+                        glow::TEXTURE_2D,
+                        0,
+                        x as i32,
+                        y as i32,
+                        size.0 as i32,
+                        size.1 as i32,
+                        glow::RGBA,
+                        glow::UNSIGNED_BYTE,
+                        glow::PixelUnpackData::Slice(rgba.align_to().1),
+                    );
+                } else {
+                    self.context.tex_sub_image_2d(
+                        glow::TEXTURE_2D,
+                        0,
+                        x as i32,
+                        y as i32,
+                        size.0 as i32,
+                        size.1 as i32,
+                        glow::BGRA,
+                        glow::UNSIGNED_BYTE,
+                        glow::PixelUnpackData::Slice(data.buf().align_to().1),
+                    );
+                }
+            },
+            ImageSource::Rgba16F(data) => unsafe {
+                self.context.tex_sub_image_2d(
+                    glow::TEXTURE_2D,
+                    0,
+                    x as i32,
+                    y as i32,
+                    size.0 as i32,
+                    size.1 as i32,
+                    glow::RGBA,
+                    glow::HALF_FLOAT,
+                    glow::PixelUnpackData::Slice(data.buf().align_to().1),
+                );
+            },
+            ImageSource::Nv12 { y: luma, uv } => unsafe {
+                let gray = if opengles_2_0 { glow::LUMINANCE } else { glow::RED };
+                let (gray_alpha, uv_data) = gray_alpha_data(uv, caps);
+
+                self.update_plane(
+                    self.id,
+                    gray,
+                    x,
+                    y,
+                    luma.width(),
+                    luma.height(),
+                    luma.buf().align_to().1,
+                    opengles_2_0,
+                );
+                self.update_plane(
+                    self.planes[0],
+                    gray_alpha,
+                    x / 2,
+                    y / 2,
+                    uv.width(),
+                    uv.height(),
+                    &uv_data,
+                    opengles_2_0,
+                );
+            },
+            ImageSource::I420 { y: luma, u, v } => unsafe {
+                let gray = if opengles_2_0 { glow::LUMINANCE } else { glow::RED };
+
+                self.update_plane(
+                    self.id,
+                    gray,
+                    x,
+                    y,
+                    luma.width(),
+                    luma.height(),
+                    luma.buf().align_to().1,
+                    opengles_2_0,
+                );
+                self.update_plane(
+                    self.planes[0],
+                    gray,
+                    x / 2,
+                    y / 2,
+                    u.width(),
+                    u.height(),
+                    u.buf().align_to().1,
+                    opengles_2_0,
+                );
+                self.update_plane(
+                    self.planes[1],
+                    gray,
+                    x / 2,
+                    y / 2,
+                    v.width(),
+                    v.height(),
+                    v.buf().align_to().1,
+                    opengles_2_0,
+                );
+            },
             #[cfg(target_arch = "wasm32")]
             ImageSource::HtmlImageElement(image_element) => unsafe {
                 self.context.tex_sub_image_2d_with_html_image(
@@ -252,12 +507,7 @@ impl GlTexture {
             },
         }
 
-        if self.info.flags().contains(ImageFlags::GENERATE_MIPMAPS) {
-            unsafe {
-                self.context.generate_mipmap(glow::TEXTURE_2D);
-                //glow::TexParameteri(glow::TEXTURE_2D, glow::GENERATE_MIPMAP, glow::TRUE);
-            }
-        }
+        self.generate_mipmaps();
 
         unsafe {
             self.context.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
@@ -272,13 +522,269 @@ impl GlTexture {
         Ok(())
     }
 
+    /// Regenerates the mip chain from the base level, if the image has mipmaps.
+    pub fn generate_mipmaps(&self) {
+        if !self.info.flags().contains(ImageFlags::GENERATE_MIPMAPS) {
+            return;
+        }
+
+        unsafe {
+            self.context.bind_texture(glow::TEXTURE_2D, Some(self.id));
+            self.context.generate_mipmap(glow::TEXTURE_2D);
+
+            for plane in &self.planes {
+                self.context.bind_texture(glow::TEXTURE_2D, Some(*plane));
+                self.context.generate_mipmap(glow::TEXTURE_2D);
+            }
+
+            self.context.bind_texture(glow::TEXTURE_2D, None);
+        }
+    }
+
     pub fn delete(self) {
+        if let Some(sampler) = self.sampler {
+            unsafe {
+                self.context.delete_sampler(sampler);
+            }
+        }
+
+        if self.info.flags().contains(ImageFlags::EXTERNAL) {
+            return;
+        }
+
         unsafe {
             self.context.delete_texture(self.id);
+
+            for plane in self.planes {
+                self.context.delete_texture(plane);
+            }
+        }
+    }
+
+    pub fn planes(&self) -> &[<glow::Context as glow::HasContext>::Texture] {
+        &self.planes
+    }
+
+    // Uploads one plane of a YUV image
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn update_plane(
+        &self,
+        plane: <glow::Context as glow::HasContext>::Texture,
+        format: u32,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        data: &[u8],
+        opengles_2_0: bool,
+    ) {
+        self.context.bind_texture(glow::TEXTURE_2D, Some(plane));
+
+        if !opengles_2_0 {
+            self.context.pixel_store_i32(glow::UNPACK_ROW_LENGTH, width as i32);
         }
+
+        self.context.tex_sub_image_2d(
+            glow::TEXTURE_2D,
+            0,
+            x as i32,
+            y as i32,
+            width as i32,
+            height as i32,
+            format,
+            glow::UNSIGNED_BYTE,
+            glow::PixelUnpackData::Slice(data),
+        );
     }
 
     pub fn info(&self) -> ImageInfo {
         self.info
     }
+
+    pub fn set_sampler(&mut self, sampler: ImageSampler, caps: TextureCaps) {
+        self.info.set_sampler(sampler);
+
+        if let Some(sampler) = self.sampler {
+            unsafe {
+                set_sampler_object_params(&self.context, sampler, self.info);
+            }
+
+            return;
+        }
+
+        if self.info.flags().contains(ImageFlags::EXTERNAL) {
+            return;
+        }
+
+        unsafe {
+            for texture in std::iter::once(self.id).chain(self.planes.iter().copied()) {
+                self.context.bind_texture(glow::TEXTURE_2D, Some(texture));
+                set_sampler_params(&self.context, self.info, caps.opengles_2_0);
+            }
+
+            self.context.bind_texture(glow::TEXTURE_2D, None);
+        }
+    }
+}
+
+// Applies the sampling options to the currently bound texture
+unsafe fn set_sampler_params(context: &glow::Context, info: ImageInfo, opengles_2_0: bool) {
+    for (name, value) in sampler_params(info, opengles_2_0) {
+        context.tex_parameter_i32(glow::TEXTURE_2D, name, value);
+    }
+
+    if uses_border_color(info, opengles_2_0) {
+        context.tex_parameter_f32_slice(glow::TEXTURE_2D, glow::TEXTURE_BORDER_COLOR, &[0.0, 0.0, 0.0, 0.0]);
+    }
+}
+
+// Applies the sampling options to a sampler object, sampler objects don't exist on WebGL 1
+unsafe fn set_sampler_object_params(
+    context: &glow::Context,
+    sampler: <glow::Context as glow::HasContext>::Sampler,
+    info: ImageInfo,
+) {
+    for (name, value) in sampler_params(info, false) {
+        context.sampler_parameter_i32(sampler, name, value);
+    }
+
+    if uses_border_color(info, false) {
+        context.sampler_parameter_f32_slice(sampler, glow::TEXTURE_BORDER_COLOR, &mut [0.0, 0.0, 0.0, 0.0]);
+    }
+}
+
+// Filter and wrap parameters for the sampling options
+fn sampler_params(info: ImageInfo, opengles_2_0: bool) -> [(u32, i32); 4] {
+    let sampler = info.sampler();
+
+    // The bicubic kernel is made of linear samples
+    let (min_filter, mag_filter) = if sampler.bicubic() {
+        (ImageFilter::Linear, ImageFilter::Linear)
+    } else {
+        (sampler.min_filter(), sampler.mag_filter())
+    };
+
+    let mipmap_mode = if info.flags().contains(ImageFlags::GENERATE_MIPMAPS) {
+        sampler.mipmap_mode()
+    } else {
+        MipmapMode::None
+    };
+
+    let min_filter = match (min_filter, mipmap_mode) {
+        (ImageFilter::Nearest, MipmapMode::None) => glow::NEAREST,
+        (ImageFilter::Nearest, MipmapMode::Nearest) => glow::NEAREST_MIPMAP_NEAREST,
+        (ImageFilter::Nearest, MipmapMode::Linear) => glow::NEAREST_MIPMAP_LINEAR,
+        (ImageFilter::Linear, MipmapMode::None) => glow::LINEAR,
+        (ImageFilter::Linear, MipmapMode::Nearest) => glow::LINEAR_MIPMAP_NEAREST,
+        (ImageFilter::Linear, MipmapMode::Linear) => glow::LINEAR_MIPMAP_LINEAR,
+    };
+
+    let mag_filter = match mag_filter {
+        ImageFilter::Nearest => glow::NEAREST,
+        ImageFilter::Linear => glow::LINEAR,
+    };
+
+    let wrap = |mode| match mode {
+        WrapMode::ClampToEdge => glow::CLAMP_TO_EDGE,
+        WrapMode::Repeat => glow::REPEAT,
+        WrapMode::MirroredRepeat => glow::MIRRORED_REPEAT,
+        // Border clamping is not part of WebGL 1
+        WrapMode::ClampToBorder if opengles_2_0 => glow::CLAMP_TO_EDGE,
+        WrapMode::ClampToBorder => glow::CLAMP_TO_BORDER,
+    };
+
+    [
+        (glow::TEXTURE_MIN_FILTER, min_filter as i32),
+        (glow::TEXTURE_MAG_FILTER, mag_filter as i32),
+        (glow::TEXTURE_WRAP_S, wrap(sampler.wrap_x()) as i32),
+        (glow::TEXTURE_WRAP_T, wrap(sampler.wrap_y()) as i32),
+    ]
+}
+
+fn uses_border_color(info: ImageInfo, opengles_2_0: bool) -> bool {
+    let sampler = info.sampler();
+
+    !opengles_2_0 && (sampler.wrap_x() == WrapMode::ClampToBorder || sampler.wrap_y() == WrapMode::ClampToBorder)
+}
+
+// Allocates single channel storage for the currently bound texture
+unsafe fn alloc_gray(context: &glow::Context, width: usize, height: usize, opengles_2_0: bool) {
+    let internal_format = if opengles_2_0 { glow::LUMINANCE } else { glow::R8 };
+    let format = if opengles_2_0 { internal_format } else { glow::RED };
+
+    context.tex_image_2d(
+        glow::TEXTURE_2D,
+        0,
+        internal_format as i32,
+        width as i32,
+        height as i32,
+        0,
+        format,
+        glow::UNSIGNED_BYTE,
+        None,
+    );
+}
+
+// Allocates two channel storage for the currently bound texture
+unsafe fn alloc_gray_alpha(context: &glow::Context, width: usize, height: usize, caps: TextureCaps) {
+    let (internal_format, format) = if caps.opengles_2_0 {
+        (glow::LUMINANCE_ALPHA, glow::LUMINANCE_ALPHA)
+    } else if caps.swizzle {
+        (glow::RG8, glow::RG)
+    } else {
+        (glow::RGBA8, glow::RGBA)
+    };
+
+    context.tex_image_2d(
+        glow::TEXTURE_2D,
+        0,
+        internal_format as i32,
+        width as i32,
+        height as i32,
+        0,
+        format,
+        glow::UNSIGNED_BYTE,
+        None,
+    );
+
+    // Sample as (L, L, L, A) like LUMINANCE_ALPHA does on GLES
+    if !caps.opengles_2_0 && caps.swizzle {
+        context.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_SWIZZLE_R, glow::RED as i32);
+        context.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_SWIZZLE_G, glow::RED as i32);
+        context.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_SWIZZLE_B, glow::RED as i32);
+        context.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_SWIZZLE_A, glow::GREEN as i32);
+    }
+}
+
+// Returns the upload format and bytes of gray alpha data, expanded to (L, L, L, A) when textures can't be swizzled
+fn gray_alpha_data(data: ImgRef<GRAYA8>, caps: TextureCaps) -> (u32, Cow<[u8]>) {
+    if caps.opengles_2_0 {
+        (glow::LUMINANCE_ALPHA, Cow::Borrowed(unsafe { data.buf().align_to().1 }))
+    } else if caps.swizzle {
+        (glow::RG, Cow::Borrowed(unsafe { data.buf().align_to().1 }))
+    } else {
+        let mut bytes = Vec::with_capacity(data.width() * data.height() * 4);
+
+        for px in data.pixels() {
+            bytes.extend_from_slice(&[px.0, px.0, px.0, px.1]);
+        }
+
+        (glow::RGBA, Cow::Owned(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_version;
+
+    #[test]
+    fn gl_version_strings() {
+        assert_eq!(parse_version("4.6.0 NVIDIA 510.47.03"), Some((false, 4, 6)));
+        assert_eq!(parse_version("3.3 (Core Profile) Mesa 21.2.6"), Some((false, 3, 3)));
+        assert_eq!(parse_version("2.1 Metal - 76.3"), Some((false, 2, 1)));
+        assert_eq!(parse_version("OpenGL ES 3.2 Mesa 21.2.6"), Some((true, 3, 2)));
+        assert_eq!(parse_version("OpenGL ES 2.0 (ANGLE 2.1.0)"), Some((true, 2, 0)));
+        assert_eq!(parse_version("OpenGL ES-CM 1.1"), Some((true, 1, 1)));
+        assert_eq!(parse_version(""), None);
+    }
 }
@@ -1,7 +1,7 @@
 
 precision highp float;
 
-#define UNIFORMARRAY_SIZE 12
+#define UNIFORMARRAY_SIZE 21
 
 uniform vec4 frag[UNIFORMARRAY_SIZE];
 
@@ -16,16 +16,28 @@ uniform vec4 frag[UNIFORMARRAY_SIZE];
 #define feather frag[9].w
 #define strokeMult frag[10].x
 #define strokeThr frag[10].y
+// Gray alpha textures (3 and 4) and NV12 chroma planes are sampled as (L, L, L, A) in both GL and GLES
 #define texType int(frag[10].z)
 #define shaderType int(frag[10].w)
 #define hasMask int(frag[11].x)
+#define hasColorMatrix int(frag[11].y)
+#define maskTexType int(frag[11].z)
+#define bicubic int(frag[11].w)
+#define colorMat mat4(frag[12], frag[13], frag[14], frag[15])
+#define colorOffset frag[16]
+#define maskMat mat3(frag[17].xyz, frag[18].xyz, frag[19].xyz)
+#define texSize frag[20].xy
+#define distanceRange frag[20].z
 
 uniform sampler2D tex;
 uniform sampler2D masktex;
+uniform sampler2D planetex1;
+uniform sampler2D planetex2;
 uniform vec2 viewSize;
 
 varying vec2 ftcoord;
 varying vec2 fpos;
+varying vec4 ftint;
 
 float sdroundrect(vec2 pt, vec2 ext, float rad) {
     vec2 ext2 = ext - vec2(rad,rad);
@@ -40,6 +52,68 @@ float scissorMask(vec2 p) {
     return clamp(sc.x,0.0,1.0) * clamp(sc.y,0.0,1.0);
 }
 
+// Cubic B-spline filtering made of four bilinear samples
+vec4 sampleBicubic(vec2 pt) {
+    vec2 texel = pt * texSize - 0.5;
+    vec2 f = fract(texel);
+    texel -= f;
+
+    vec2 f2 = f * f;
+    vec2 f3 = f2 * f;
+
+    vec2 w0 = (1.0 - 3.0 * f + 3.0 * f2 - f3) / 6.0;
+    vec2 w1 = (4.0 - 6.0 * f2 + 3.0 * f3) / 6.0;
+    vec2 w2 = (1.0 + 3.0 * f + 3.0 * f2 - 3.0 * f3) / 6.0;
+    vec2 w3 = f3 / 6.0;
+
+    vec2 s0 = w0 + w1;
+    vec2 s1 = w2 + w3;
+
+    vec2 c0 = (texel - 0.5 + w1 / s0) / texSize;
+    vec2 c1 = (texel + 1.5 + w3 / s1) / texSize;
+
+    vec4 row0 = mix(texture2D(tex, vec2(c1.x, c0.y)), texture2D(tex, c0), s0.x);
+    vec4 row1 = mix(texture2D(tex, c1), texture2D(tex, vec2(c0.x, c1.y)), s0.x);
+
+    return mix(row1, row0, s0.y);
+}
+
+// Samples the image, YUV images (5 - NV12, 6 - I420) are returned as (Y, U, V, 1)
+vec4 sampleImage(vec2 pt) {
+    if (texType == 5) {
+        vec4 uv = texture2D(planetex1, pt);
+        return vec4(texture2D(tex, pt).x, uv.x, uv.w, 1.0);
+    } else if (texType == 6) {
+        return vec4(texture2D(tex, pt).x, texture2D(planetex1, pt).x, texture2D(planetex2, pt).x, 1.0);
+    } else if (bicubic == 1) {
+        return sampleBicubic(pt);
+    }
+
+    return texture2D(tex, pt);
+}
+
+// Color matrix - operates on unpremultiplied colors
+vec4 applyColorMatrix(vec4 color) {
+    if (color.w > 0.0) color.xyz /= color.w;
+    color = clamp(colorMat * color + colorOffset, 0.0, 1.0);
+    return vec4(color.xyz * color.w, color.w);
+}
+
+// Image mask - alpha or luminance of the mask texture, zero outside of the mask rectangle
+float imageMask(vec2 p) {
+    vec2 pt = (maskMat * vec3(p, 1.0)).xy;
+    vec4 color = texture2D(masktex, pt);
+
+    if (maskTexType == 1 || maskTexType == 3) color = vec4(color.xyz * color.w, color.w);
+    if (maskTexType == 2) color = vec4(color.x);
+
+    if (pt.x < 0.0 || pt.x > 1.0 || pt.y < 0.0 || pt.y > 1.0) return 0.0;
+
+    if (hasMask == 3) return dot(color.xyz, vec3(0.2125, 0.7154, 0.0721));
+
+    return color.w;
+}
+
 #ifdef EDGE_AA
 // Stroke - from [0..1] to clipped pyramid, where the slope is 1px.
 float strokeMask() {
@@ -89,11 +163,13 @@ void main(void) {
         // Calculate color from texture
         vec2 pt = (paintMat * vec3(fpos, 1.0)).xy / extent;
 
-        vec4 color = texture2D(tex, pt);
+        vec4 color = sampleImage(pt);
 
-        if (texType == 1) color = vec4(color.xyz * color.w, color.w);
+        if (texType == 1 || texType == 3) color = vec4(color.xyz * color.w, color.w);
         if (texType == 2) color = vec4(color.x);
 
+        if (hasColorMatrix == 1) color = applyColorMatrix(color);
+
         // Apply color tint and alpha.
         color *= innerCol;
 
@@ -101,9 +177,45 @@ void main(void) {
     } else if (shaderType == 2) {
         // Stencil fill
         result = vec4(1,1,1,1);
+    } else if (shaderType == 4) {
+        // Textured tris
+
+        // Calculate color from texture using the vertex texture coordinates
+        vec4 color = sampleImage(ftcoord);
+
+        if (texType == 1 || texType == 3) color = vec4(color.xyz * color.w, color.w);
+        if (texType == 2) color = vec4(color.x);
+
+        if (hasColorMatrix == 1) color = applyColorMatrix(color);
+
+        // Apply color tint and alpha, the vertex tint colors individual sprites.
+        color *= innerCol * ftint;
+
+        result = color;
+    }
+
+    if (hasMask == 2 || hasMask == 3) {
+        result *= imageMask(fpos);
     }
 
-    if (hasMask == 1) {
+    if (hasMask == 4) {
+        // Distance field glyphs, the median of the channels is the distance to the outline
+        vec4 field = texture2D(masktex, ftcoord);
+        float dist = max(min(field.x, field.y), min(max(field.x, field.y), field.z));
+
+        result *= clamp((dist - 0.5) * distanceRange + 0.5, 0.0, 1.0) * scissor;
+    } else if (hasMask == 5 || hasMask == 6) {
+        // Subpixel glyphs, blended in two passes: the coverage of each channel darkens the
+        // destination (5), then the color is added (6)
+        vec3 coverage = texture2D(masktex, ftcoord).xyz * scissor;
+        float alpha = result.w * (coverage.x + coverage.y + coverage.z) / 3.0;
+
+        if (hasMask == 5) {
+            result = vec4(coverage * result.w, alpha);
+        } else {
+            result = vec4(result.xyz * coverage, alpha);
+        }
+    } else if (hasMask == 1) {
         // Textured tris
         vec4 mask = texture2D(masktex, ftcoord);
         mask = vec4(mask.x);
@@ -113,6 +225,9 @@ void main(void) {
 
         mask *= scissor;
         result *= mask;
+    } else if (shaderType == 4) {
+        // Texture coordinates are used for sampling, there is no stroke coverage
+        result *= scissor;
     } else if (shaderType != 2) { // Not stencil fill
         // Combine alpha
         result *= strokeAlpha * scissor;
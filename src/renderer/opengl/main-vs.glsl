@@ -3,13 +3,16 @@ uniform vec2 viewSize;
 
 attribute vec2 vertex;
 attribute vec2 tcoord;
+attribute vec4 tint;
 
 varying vec2 ftcoord;
 varying vec2 fpos;
+varying vec4 ftint;
 
 void main(void) {
     ftcoord = tcoord;
     fpos = vertex;
+    ftint = tint;
 
     gl_Position = vec4(2.0 * vertex.x / viewSize.x - 1.0, 1.0 - 2.0 * vertex.y / viewSize.y, 0, 1);
 }
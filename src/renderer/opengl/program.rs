@@ -135,6 +135,8 @@ pub struct MainProgram {
     loc_viewsize: <glow::Context as glow::HasContext>::UniformLocation,
     loc_tex: <glow::Context as glow::HasContext>::UniformLocation,
     loc_masktex: <glow::Context as glow::HasContext>::UniformLocation,
+    loc_planetex1: <glow::Context as glow::HasContext>::UniformLocation,
+    loc_planetex2: <glow::Context as glow::HasContext>::UniformLocation,
     loc_frag: <glow::Context as glow::HasContext>::UniformLocation,
 }
 
@@ -147,11 +149,13 @@ impl MainProgram {
         let vert_shader = Shader::new(context, &vert_shader_src, glow::VERTEX_SHADER)?;
         let frag_shader = Shader::new(context, &frag_shader_src, glow::FRAGMENT_SHADER)?;
 
-        let program = Program::new(context, &[vert_shader, frag_shader], &["vertex", "tcoord"])?;
+        let program = Program::new(context, &[vert_shader, frag_shader], &["vertex", "tcoord", "tint"])?;
 
         let loc_viewsize = program.uniform_location("viewSize")?;
         let loc_tex = program.uniform_location("tex")?;
         let loc_masktex = program.uniform_location("masktex")?;
+        let loc_planetex1 = program.uniform_location("planetex1")?;
+        let loc_planetex2 = program.uniform_location("planetex2")?;
         let loc_frag = program.uniform_location("frag")?;
 
         Ok(Self {
@@ -160,6 +164,8 @@ impl MainProgram {
             loc_viewsize,
             loc_tex,
             loc_masktex,
+            loc_planetex1,
+            loc_planetex2,
             loc_frag,
         })
     }
@@ -176,6 +182,18 @@ impl MainProgram {
         }
     }
 
+    pub(crate) fn set_planetex1(&self, tex: i32) {
+        unsafe {
+            self.context.uniform_1_i32(Some(&self.loc_planetex1), tex);
+        }
+    }
+
+    pub(crate) fn set_planetex2(&self, tex: i32) {
+        unsafe {
+            self.context.uniform_1_i32(Some(&self.loc_planetex2), tex);
+        }
+    }
+
     pub(crate) fn set_view(&self, view: [f32; 2]) {
         unsafe {
             self.context.uniform_2_f32_slice(Some(&self.loc_viewsize), &view);
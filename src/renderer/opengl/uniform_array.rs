@@ -1,16 +1,12 @@
 use super::Params;
 
-const UNIFORMARRAY_SIZE: usize = 12;
+const UNIFORMARRAY_SIZE: usize = 21;
 
 pub struct UniformArray([f32; UNIFORMARRAY_SIZE * 4]);
 
 impl Default for UniformArray {
     fn default() -> Self {
-        Self([
-            0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
-            0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
-            0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
-        ])
+        Self([0.0; UNIFORMARRAY_SIZE * 4])
     }
 }
 
@@ -74,6 +70,30 @@ impl UniformArray {
     pub fn set_has_mask(&mut self, has_mask: f32) {
         self.0[44] = has_mask;
     }
+
+    pub fn set_has_color_matrix(&mut self, has_color_matrix: f32) {
+        self.0[45] = has_color_matrix;
+    }
+
+    pub fn set_mask_tex_type(&mut self, mask_tex_type: f32) {
+        self.0[46] = mask_tex_type;
+    }
+
+    pub fn set_color_mat(&mut self, mat: [f32; 16]) {
+        self.0[48..64].copy_from_slice(&mat);
+    }
+
+    pub fn set_color_offset(&mut self, offset: [f32; 4]) {
+        self.0[64..68].copy_from_slice(&offset);
+    }
+
+    pub fn set_mask_mat(&mut self, mat: [f32; 12]) {
+        self.0[68..80].copy_from_slice(&mat);
+    }
+
+    pub fn set_distance_range(&mut self, distance_range: f32) {
+        self.0[82] = distance_range;
+    }
 }
 
 impl From<&Params> for UniformArray {
@@ -94,6 +114,12 @@ impl From<&Params> for UniformArray {
         arr.set_shader_type(params.shader_type);
         arr.set_tex_type(params.tex_type);
         arr.set_has_mask(params.has_mask);
+        arr.set_has_color_matrix(params.has_color_matrix);
+        arr.set_color_mat(params.color_mat);
+        arr.set_color_offset(params.color_offset);
+        arr.set_mask_tex_type(params.mask_tex_type);
+        arr.set_mask_mat(params.mask_mat);
+        arr.set_distance_range(params.distance_range);
 
         arr
     }
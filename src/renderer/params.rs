@@ -1,8 +1,11 @@
 use crate::{
     paint::GradientColors,
     Color,
+    ColorMatrix,
     ImageFlags,
+    ImageInfo,
     ImageStore,
+    MaskMode,
     Paint,
     PaintFlavor,
     PixelFormat,
@@ -28,7 +31,17 @@ pub struct Params {
     pub(crate) tex_type: f32,
     pub(crate) shader_type: f32,
     pub(crate) has_mask: f32,
-    pub(crate) padding: [f32; 19],
+    pub(crate) has_color_matrix: f32,
+    pub(crate) mask_tex_type: f32,
+    pub(crate) bicubic: f32,
+    pub(crate) color_mat: [f32; 16],
+    pub(crate) color_offset: [f32; 4],
+    pub(crate) mask_mat: [f32; 12],
+    pub(crate) tex_size: [f32; 2],
+    pub(crate) distance_range: f32,
+    pub(crate) pad0: f32,
+    // Pads the struct to 512 bytes, wgpu requires dynamic uniform offsets to be aligned to 256 bytes
+    pub(crate) padding: [[f32; 4]; 11],
 }
 
 impl Params {
@@ -68,7 +81,36 @@ impl Params {
         params.stroke_mult = (stroke_width * 0.5 + fringe_width * 0.5) / fringe_width;
         params.stroke_thr = stroke_thr;
 
-        params.has_mask = if paint.alpha_mask().is_some() { 1.0 } else { 0.0 };
+        if paint.alpha_mask().is_some() {
+            params.has_mask = 1.0;
+        } else if let Some(mask) = paint.mask() {
+            if let Some(mask_info) = images.info(mask.image) {
+                params.has_mask = match mask.mode {
+                    MaskMode::Alpha => 2.0,
+                    MaskMode::Luminance => 3.0,
+                };
+
+                // Only the luma plane of YUV images is bound for masking
+                params.mask_tex_type = if mask_info.format().is_yuv() {
+                    2.0
+                } else {
+                    tex_type(mask_info)
+                };
+
+                // Maps the unit square to the mask rectangle in canvas space
+                let mut transform = Transform2D::identity();
+                transform.scale(mask.width, mask.height);
+                transform.multiply(&Transform2D::new_translation(mask.x, mask.y));
+                transform.multiply(&mask.transform);
+                transform.multiply(&paint.transform);
+
+                if mask_info.flags().contains(ImageFlags::FLIP_Y) {
+                    transform.premultiply(&Transform2D([1.0, 0.0, 0.0, -1.0, 0.0, 1.0]));
+                }
+
+                params.mask_mat = transform.inversed().to_mat3x4();
+            }
+        }
 
         let inv_transform;
 
@@ -88,6 +130,10 @@ impl Params {
                 height,
                 angle,
                 alpha,
+                tint,
+                color_matrix,
+                yuv_matrix,
+                yuv_range,
             } => {
                 let image_info = match images.info(id) {
                     Some(info) => info,
@@ -97,7 +143,7 @@ impl Params {
                 params.extent[0] = width;
                 params.extent[1] = height;
 
-                let color = Color::rgbaf(1.0, 1.0, 1.0, alpha);
+                let color = Color::rgbaf(tint.r, tint.g, tint.b, tint.a * alpha);
 
                 params.inner_col = color.premultiplied().to_array();
                 params.outer_col = color.premultiplied().to_array();
@@ -127,17 +173,28 @@ impl Params {
 
                 params.shader_type = ShaderType::FillImage.to_f32();
 
-                params.tex_type = match image_info.format() {
-                    PixelFormat::Rgba8 => {
-                        if image_info.flags().contains(ImageFlags::PREMULTIPLIED) {
-                            0.0
-                        } else {
-                            1.0
-                        }
-                    }
-                    PixelFormat::Gray8 => 2.0,
-                    _ => 0.0,
+                // YUV images are converted to RGB by the color matrix, followed by the user provided one
+                let color_matrix = if image_info.format().is_yuv() {
+                    let yuv = ColorMatrix::yuv_to_rgb(yuv_matrix, yuv_range);
+                    Some(color_matrix.map_or(yuv, |matrix| yuv.then(&matrix)))
+                } else {
+                    color_matrix
                 };
+
+                if let Some(matrix) = color_matrix {
+                    let (mat, offset) = matrix.to_mat4_offset();
+
+                    params.has_color_matrix = 1.0;
+                    params.color_mat = mat;
+                    params.color_offset = offset;
+                }
+
+                params.tex_type = tex_type(image_info);
+
+                if image_info.sampler().bicubic() && !image_info.format().is_yuv() {
+                    params.bicubic = 1.0;
+                    params.tex_size = [image_info.width() as f32, image_info.height() as f32];
+                }
             }
             PaintFlavor::LinearGradient {
                 start_x,
@@ -244,3 +301,29 @@ impl Params {
         params
     }
 }
+
+// Tells the shaders how to interpret the texture data of the image
+fn tex_type(info: ImageInfo) -> f32 {
+    let premultiplied = info.flags().contains(ImageFlags::PREMULTIPLIED);
+
+    match info.format() {
+        PixelFormat::Rgba8 | PixelFormat::Bgra8 | PixelFormat::Rgba16F => {
+            if premultiplied {
+                0.0
+            } else {
+                1.0
+            }
+        }
+        PixelFormat::Gray8 => 2.0,
+        PixelFormat::GrayAlpha8 => {
+            if premultiplied {
+                4.0
+            } else {
+                3.0
+            }
+        }
+        PixelFormat::Nv12 => 5.0,
+        PixelFormat::I420 => 6.0,
+        _ => 0.0,
+    }
+}
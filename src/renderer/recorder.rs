@@ -0,0 +1,122 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use imgref::ImgVec;
+use rgb::RGBA8;
+
+use crate::{
+    ErrorKind,
+    ImageId,
+    ImageInfo,
+    ImageSampler,
+    ImageSource,
+    ImageStore,
+};
+
+use super::{
+    Command,
+    CommandType,
+    Renderer,
+    Vertex,
+};
+
+/// Keeps the vertices, command count and fills of the last flush, the RGBA images uploaded so far
+/// and the number of mipmap regenerations.
+#[derive(Default)]
+pub(crate) struct Recording {
+    pub commands: usize,
+    pub verts: Vec<Vertex>,
+    /// The inner paint color and the mean x of the fill vertices of every fill
+    pub fills: Vec<([f32; 4], f32)>,
+    pub uploads: Vec<Vec<RGBA8>>,
+    pub mipmaps: usize,
+}
+
+/// Renderer used by tests to inspect what the canvas hands to the renderer. Clones share the recording.
+#[derive(Clone, Default)]
+pub(crate) struct Recorder(pub Rc<RefCell<Recording>>);
+
+impl Renderer for Recorder {
+    type Image = ();
+    type Target = ();
+
+    fn set_size(&mut self, _width: u32, _height: u32, _dpi: f32) {}
+
+    fn render(&mut self, _target: Option<&()>, _images: &ImageStore<()>, verts: &[Vertex], commands: &[Command]) {
+        let mut recording = self.0.borrow_mut();
+        recording.commands = commands.len();
+        recording.verts = verts.to_vec();
+
+        recording.fills = commands
+            .iter()
+            .filter_map(|cmd| {
+                let color = match &cmd.cmd_type {
+                    CommandType::ConvexFill { params } => params.inner_col,
+                    CommandType::ConcaveFill { fill_params, .. } => fill_params.inner_col,
+                    _ => return None,
+                };
+
+                let xs: Vec<f32> = cmd
+                    .drawables
+                    .iter()
+                    .filter_map(|drawable| drawable.fill_verts)
+                    .flat_map(|(offset, count)| &verts[offset..offset + count])
+                    .map(|vertex| vertex.x)
+                    .collect();
+
+                Some((color, xs.iter().sum::<f32>() / xs.len().max(1) as f32))
+            })
+            .collect();
+    }
+
+    fn alloc_image(&mut self, _info: ImageInfo) -> Result<(), ErrorKind> {
+        Ok(())
+    }
+
+    fn update_image(&mut self, _image: &mut (), data: ImageSource, _x: usize, _y: usize) -> Result<(), ErrorKind> {
+        if let ImageSource::Rgba(data) = data {
+            self.0.borrow_mut().uploads.push(data.pixels().collect());
+        }
+
+        Ok(())
+    }
+
+    fn delete_image(&mut self, _image: ()) {}
+
+    fn set_image_sampler(&mut self, _image: &mut (), _sampler: ImageSampler) {}
+
+    fn generate_mipmaps(&mut self, _image: &()) {
+        self.0.borrow_mut().mipmaps += 1;
+    }
+
+    fn screenshot(&mut self) -> Result<ImgVec<RGBA8>, ErrorKind> {
+        Ok(ImgVec::new(Vec::new(), 0, 0))
+    }
+
+    fn read_image(
+        &mut self,
+        _images: &ImageStore<()>,
+        _id: ImageId,
+        _x: usize,
+        _y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<ImgVec<RGBA8>, ErrorKind> {
+        Ok(ImgVec::new(vec![RGBA8::default(); width * height], width, height))
+    }
+
+    fn copy_image(
+        &mut self,
+        _images: &ImageStore<()>,
+        _src: ImageId,
+        _dst: ImageId,
+        _x: usize,
+        _y: usize,
+        _width: usize,
+        _height: usize,
+        _dst_x: usize,
+        _dst_y: usize,
+    ) -> Result<(), ErrorKind> {
+        Ok(())
+    }
+}
@@ -4,8 +4,11 @@ use imgref::ImgVec;
 use rgb::RGBA8;
 
 use crate::{
+    image::validate_chroma_planes,
     ErrorKind,
+    ImageId,
     ImageInfo,
+    ImageSampler,
     ImageSource,
     ImageStore,
 };
@@ -55,14 +58,47 @@ impl Renderer for Void {
             return Err(ErrorKind::ImageUpdateOutOfBounds);
         }
 
-        Ok(())
+        validate_chroma_planes(&data, &image.info, x, y)
     }
 
     fn delete_image(&mut self, image: Self::Image) {}
 
+    fn set_image_sampler(&mut self, image: &mut Self::Image, sampler: ImageSampler) {
+        image.info.set_sampler(sampler);
+    }
+
+    fn generate_mipmaps(&mut self, _image: &Self::Image) {}
+
     fn screenshot(&mut self) -> Result<ImgVec<RGBA8>, ErrorKind> {
         Ok(ImgVec::new(Vec::new(), 0, 0))
     }
+
+    fn read_image(
+        &mut self,
+        images: &ImageStore<VoidImage>,
+        id: ImageId,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<ImgVec<RGBA8>, ErrorKind> {
+        Ok(ImgVec::new(vec![RGBA8::default(); width * height], width, height))
+    }
+
+    fn copy_image(
+        &mut self,
+        images: &ImageStore<VoidImage>,
+        src: ImageId,
+        dst: ImageId,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        dst_x: usize,
+        dst_y: usize,
+    ) -> Result<(), ErrorKind> {
+        Ok(())
+    }
 }
 
 pub struct VoidImage {
@@ -29,6 +29,9 @@ pub use wgpu_bind_group_cache::*;
 mod wgpu_var;
 pub use wgpu_var::*;
 
+mod wgpu_mipmap;
+pub use wgpu_mipmap::*;
+
 use crate::{
     renderer::{
         ImageId,
@@ -39,7 +42,9 @@ use crate::{
     CompositeOperationState,
     ErrorKind,
     FillRule,
+    ImageFlags,
     ImageInfo,
+    ImageSampler,
     ImageSource,
     ImageStore,
     Rect,
@@ -57,6 +62,8 @@ use super::{
 use self::VecExt;
 
 // use fnv::FnvHashMap;
+use std::sync::Arc;
+
 use imgref::ImgVec;
 use rgb::RGBA8;
 
@@ -291,6 +298,27 @@ impl WGPU {
                     },
                     count: None,
                 },
+                // chroma planes
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -440,6 +468,15 @@ impl WGPU {
 // }
 
 impl WGPU {
+    pub(crate) fn import_texture(
+        &self,
+        texture: Arc<wgpu::Texture>,
+        info: ImageInfo,
+    ) -> Result<WGPUTexture, ErrorKind> {
+        let label = format!("{:?}", info);
+        WGPUTexture::from_texture(&self.ctx, texture, info, &label)
+    }
+
     // pub fn bind_group_for(
     //     &self,
     //     images: &ImageStore<WGPUTexture>,
@@ -764,7 +801,7 @@ impl Renderer for WGPU {
                     ),
                     RenderTarget::Image(id) => {
                         let tex = images.get(id).unwrap();
-                        (tex.view(), tex.stencil_view(), tex.size(), tex.format())
+                        (tex.attachment_view(), tex.stencil_view(), tex.size(), tex.format())
                     }
                 };
 
@@ -1087,10 +1124,17 @@ impl Renderer for WGPU {
                             counter.set_render_target += 1;
 
                             if render_target != *target {
-                                render_target = *target;
+                                let prev_target = std::mem::replace(&mut render_target, *target);
                                 drop(pass);
                                 self.ctx.queue().submit(Some(encoder.finish()));
 
+                                // the image we just rendered into needs its mip chain rebuilt
+                                if let RenderTarget::Image(id) = prev_target {
+                                    if let Some(texture) = images.get(id) {
+                                        texture.generate_mipmaps();
+                                    }
+                                }
+
                                 should_submit = false;
                                 continue 'new_pass;
                             } else {
@@ -1108,6 +1152,12 @@ impl Renderer for WGPU {
                 self.ctx.queue().submit(Some(encoder.finish()));
             }
 
+            if let RenderTarget::Image(id) = render_target {
+                if let Some(texture) = images.get(id) {
+                    texture.generate_mipmaps();
+                }
+            }
+
             self.frame += 1;
 
             // if self.frame == 4 {
@@ -1129,9 +1179,107 @@ impl Renderer for WGPU {
         // we don't have to do anything since the textures will be freed by wgpu automatically
     }
 
+    fn generate_mipmaps(&mut self, image: &Self::Image) {
+        image.generate_mipmaps();
+    }
+
+    fn set_image_sampler(&mut self, image: &mut Self::Image, sampler: ImageSampler) {
+        image.set_sampler(sampler);
+        // bind groups reference the old sampler
+        self.bind_group_cache.clear();
+    }
+
+    fn flip_y(&self) -> bool {
+        false
+    }
+
     fn screenshot(&mut self) -> Result<ImgVec<RGBA8>, ErrorKind> {
         todo!()
     }
+
+    fn read_image(
+        &mut self,
+        images: &ImageStore<Self::Image>,
+        id: ImageId,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<ImgVec<RGBA8>, ErrorKind> {
+        let texture = images.get(id).ok_or(ErrorKind::ImageIdNotFound)?;
+
+        let image = texture.read(x, texture.info().texture_y(y, height), width, height)?;
+
+        // Rows of FLIP_Y images are stored bottom up, the same as in the OpenGL renderer
+        if !texture.info().flags().contains(ImageFlags::FLIP_Y) {
+            return Ok(image);
+        }
+
+        let mut flipped = Vec::with_capacity(width * height);
+
+        for row in image.rows().rev() {
+            flipped.extend_from_slice(row);
+        }
+
+        Ok(ImgVec::new(flipped, width, height))
+    }
+
+    fn copy_image(
+        &mut self,
+        images: &ImageStore<Self::Image>,
+        src: ImageId,
+        dst: ImageId,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        dst_x: usize,
+        dst_y: usize,
+    ) -> Result<(), ErrorKind> {
+        let src = images.get(src).ok_or(ErrorKind::ImageIdNotFound)?;
+        let dst = images.get(dst).ok_or(ErrorKind::ImageIdNotFound)?;
+
+        let src_y = src.info().texture_y(y, height);
+        let dst_y = dst.info().texture_y(dst_y, height);
+
+        // Rows of FLIP_Y images are stored bottom up, so the copy is flipped if only one of them has the flag
+        let flip_y = src.info().flags().contains(ImageFlags::FLIP_Y) != dst.info().flags().contains(ImageFlags::FLIP_Y);
+
+        // Texture copies can't mirror, a flipped copy goes row by row
+        let (rows, row_height) = if flip_y { (height, 1) } else { (1, height) };
+
+        let mut encoder = self.ctx.create_command_encoder(Some("image copy"));
+
+        for row in 0..rows {
+            let dst_row = if flip_y { height - 1 - row } else { row };
+
+            encoder.copy_texture_to_texture(
+                wgpu::ImageCopyTexture {
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: x as _,
+                        y: (src_y + row) as _,
+                        z: 0,
+                    },
+                    texture: src.tex(),
+                },
+                wgpu::ImageCopyTexture {
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: dst_x as _,
+                        y: (dst_y + dst_row) as _,
+                        z: 0,
+                    },
+                    texture: dst.tex(),
+                },
+                Size::new(width as _, row_height as _).into(),
+            );
+        }
+
+        self.ctx.queue().submit(Some(encoder.finish()));
+
+        Ok(())
+    }
 }
 
 impl From<Color> for wgpu::Color {
@@ -0,0 +1,28 @@
+// renders a full screen triangle sampling the previous mip level
+
+struct VertexOutput {
+    [[builtin(position)]] pos: vec4<f32>;
+    [[location(0)]] uv: vec2<f32>;
+};
+
+[[stage(vertex)]]
+fn vertex_shader(
+    [[builtin(vertex_index)]] index: u32,
+) -> VertexOutput {
+    const uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+    var ret: VertexOutput;
+    ret.uv = uv;
+    ret.pos = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    return ret;
+}
+
+[[group(0), binding(0)]]
+var tex: texture_2d<f32>;
+[[group(0), binding(1)]]
+var samplr: sampler;
+
+[[stage(fragment)]]
+fn fragment_shader(vert: VertexOutput) -> [[location(0)]] vec4<f32> {
+    return textureSample(tex, samplr, vert.uv);
+}
@@ -11,10 +11,11 @@ struct RasterizerData {
     [[builtin(position)]] pos: vec4<f32>;
     [[location(0)]] fpos: vec2<f32>;
     [[location(1)]] ftcoord: vec2<f32>;
+    [[location(2)]] ftint: vec4<f32>;
 };
 
 
-// size_of(uniforms) == 256
+// size_of(uniforms) == 512
 [[block]]
 struct Uniforms {
     scissor_mat: mat3x4<f32>;                       // 3 * 4 * 4
@@ -31,9 +32,29 @@ struct Uniforms {
     tex_type: f32;                                  // 4
     shader_type: f32;                               // 4
     has_mask: f32;                                  // 4
-    padding: [[stride(4)]] array<f32, 19>;          // 19 * 4
+    has_color_matrix: f32;                          // 4
+    mask_tex_type: f32;                             // 4
+    bicubic: f32;                                   // 4
+    color_mat: mat4x4<f32>;                         // 4 * 4 * 4
+    color_offset: vec4<f32>;                        // 4 * 4
+    mask_mat: mat3x4<f32>;                          // 3 * 4 * 4
+    tex_size: vec2<f32>;                            // 2 * 4
+    distance_range: f32;                            // 4
+    pad0: f32;                                      // 4
+    padding: [[stride(16)]] array<vec4<f32>, 11>;   // 11 * 4 * 4
 };
 
+// color matrix, operates on unpremultiplied colors
+fn apply_color_matrix(u: Uniforms, color: vec4<f32>) -> vec4<f32> {
+    var c: vec4<f32>;
+    c = color;
+    if (c.w > 0.0) {
+        c = vec4<f32>(c.xyz / c.w, c.w);
+    }
+    c = clamp(u.color_mat * c + u.color_offset, vec4<f32>(0.0, 0.0, 0.0, 0.0), vec4<f32>(1.0, 1.0, 1.0, 1.0));
+    return vec4<f32>(c.xyz * c.w, c.w);
+}
+
 fn scissor_mask(u: Uniforms, p: vec2<f32>) -> f32 {
     var sc: vec2<f32>;
     sc = (abs((u.scissor_mat * vec3<f32>(p, 1.0)).xy)
@@ -74,6 +95,7 @@ var<push_constant> viewSize: ViewSize;
 [[stage(vertex)]]
 fn vertex_shader(
     [[location(0)]] vert: vec4<f32>,
+    [[location(1)]] tint: vec4<f32>,
 ) -> RasterizerData {
     const pos = vert.xy;
     const tcoord = vert.zw;
@@ -81,6 +103,7 @@ fn vertex_shader(
     var ret: RasterizerData;
     ret.ftcoord = tcoord;
     ret.fpos = pos;
+    ret.ftint = tint;
     ret.pos = vec4<f32>(
                     2.0 * pos.x / f32(viewSize.x) - 1.0,
                     1.0 - 2.0 * pos.y / f32(viewSize.y),
@@ -104,6 +127,88 @@ var samplr: sampler;
 var alpha_tex: texture_2d<f32>;
 [[group(0), binding(4)]]
 var alpha_samplr: sampler;
+[[group(0), binding(5)]]
+var plane_tex1: texture_2d<f32>;
+[[group(0), binding(6)]]
+var plane_tex2: texture_2d<f32>;
+
+// cubic b-spline filtering made of four bilinear samples
+fn sample_bicubic(u: Uniforms, pt: vec2<f32>) -> vec4<f32> {
+    var texel: vec2<f32>;
+    texel = pt * u.tex_size - vec2<f32>(0.5, 0.5);
+    const f = fract(texel);
+    texel = texel - f;
+
+    const f2 = f * f;
+    const f3 = f2 * f;
+
+    const w0 = (vec2<f32>(1.0, 1.0) - 3.0 * f + 3.0 * f2 - f3) / 6.0;
+    const w1 = (vec2<f32>(4.0, 4.0) - 6.0 * f2 + 3.0 * f3) / 6.0;
+    const w2 = (vec2<f32>(1.0, 1.0) + 3.0 * f + 3.0 * f2 - 3.0 * f3) / 6.0;
+    const w3 = f3 / 6.0;
+
+    const s0 = w0 + w1;
+    const s1 = w2 + w3;
+
+    const c0 = (texel - vec2<f32>(0.5, 0.5) + w1 / s0) / u.tex_size;
+    const c1 = (texel + vec2<f32>(1.5, 1.5) + w3 / s1) / u.tex_size;
+
+    const row0 = mix(textureSample(tex, samplr, vec2<f32>(c1.x, c0.y)), textureSample(tex, samplr, c0), vec4<f32>(s0.x, s0.x, s0.x, s0.x));
+    const row1 = mix(textureSample(tex, samplr, c1), textureSample(tex, samplr, vec2<f32>(c0.x, c1.y)), vec4<f32>(s0.x, s0.x, s0.x, s0.x));
+
+    return mix(row1, row0, vec4<f32>(s0.y, s0.y, s0.y, s0.y));
+}
+
+// samples the image, yuv images (5 - nv12, 6 - i420) are returned as (y, u, v, 1)
+fn sample_image(u: Uniforms, pt: vec2<f32>) -> vec4<f32> {
+    const color = textureSample(tex, samplr, pt);
+    const plane1 = textureSample(plane_tex1, samplr, pt);
+    const plane2 = textureSample(plane_tex2, samplr, pt);
+
+    if (u.tex_type == 5.0) {
+        return vec4<f32>(color.x, plane1.x, plane1.y, 1.0);
+    }
+    elseif (u.tex_type == 6.0) {
+        return vec4<f32>(color.x, plane1.x, plane2.x, 1.0);
+    }
+    elseif (u.bicubic == 1.0) {
+        return sample_bicubic(u, pt);
+    }
+
+    return color;
+}
+
+// image mask, alpha or luminance of the mask texture, zero outside of the mask rectangle
+fn image_mask(u: Uniforms, p: vec2<f32>) -> f32 {
+    const pt = (u.mask_mat * vec3<f32>(p, 1.0)).xy;
+
+    var color: vec4<f32>;
+    color = textureSample(alpha_tex, alpha_samplr, pt);
+
+    if (u.mask_tex_type == 1.0) {
+        color = vec4<f32>(color.xyz * color.w, color.w);
+    }
+    elseif (u.mask_tex_type == 2.0) {
+        color = vec4<f32>(color.x, color.x, color.x, color.x);
+    }
+    elseif (u.mask_tex_type == 3.0) {
+        // gray alpha textures are stored as rg
+        color = vec4<f32>(color.xxx * color.y, color.y);
+    }
+    elseif (u.mask_tex_type == 4.0) {
+        color = vec4<f32>(color.xxx, color.y);
+    }
+
+    if (pt.x < 0.0 || pt.x > 1.0 || pt.y < 0.0 || pt.y > 1.0) {
+        return 0.0;
+    }
+
+    if (u.has_mask == 3.0) {
+        return dot(color.xyz, vec3<f32>(0.2125, 0.7154, 0.0721));
+    }
+
+    return color.w;
+}
 
 [[stage(fragment)]]
 fn fragment_shader_aa(
@@ -136,7 +241,7 @@ fn fragment_shader_aa(
         const pt = (u.paint_mat * vec3<f32>(in.fpos, 1.0)).xy / u.extent;
 
         var color: vec4<f32>;
-        color = textureSample(tex, samplr, pt);
+        color = sample_image(u, pt);
 
         if (u.tex_type == 1.0) {
             color = vec4<f32>(color.xyz * color.w, color.w);
@@ -144,14 +249,74 @@ fn fragment_shader_aa(
         elseif (u.tex_type == 2.0) {
             color = vec4<f32>(color.x, color.x, color.x, color.x);
         }
+        elseif (u.tex_type == 3.0) {
+            // gray alpha textures are stored as rg
+            color = vec4<f32>(color.xxx * color.y, color.y);
+        }
+        elseif (u.tex_type == 4.0) {
+            color = vec4<f32>(color.xxx, color.y);
+        }
+
+        if (u.has_color_matrix == 1.0) {
+            color = apply_color_matrix(u, color);
+        }
         result = color * u.inner_col;
+    } elseif (u.shader_type == 4.0) {
+        // textured triangles, sampled with the vertex texture coordinates
+        var color: vec4<f32>;
+        color = sample_image(u, in.ftcoord);
+
+        if (u.tex_type == 1.0) {
+            color = vec4<f32>(color.xyz * color.w, color.w);
+        }
+        elseif (u.tex_type == 2.0) {
+            color = vec4<f32>(color.x, color.x, color.x, color.x);
+        }
+        elseif (u.tex_type == 3.0) {
+            // gray alpha textures are stored as rg
+            color = vec4<f32>(color.xxx * color.y, color.y);
+        }
+        elseif (u.tex_type == 4.0) {
+            color = vec4<f32>(color.xxx, color.y);
+        }
+
+        if (u.has_color_matrix == 1.0) {
+            color = apply_color_matrix(u, color);
+        }
+        // the vertex tint colors individual sprites
+        result = color * u.inner_col * in.ftint;
     } else {
     //     // stencil
     //     // MNVG_SHADER_FILLIMG
         result = vec4<f32>(1.0, 1.0, 1.0, 1.0);
     }
 
-    if (u.has_mask == 1.0) {
+    if (u.has_mask == 2.0 || u.has_mask == 3.0) {
+        result = result * image_mask(u, in.fpos);
+    }
+
+    if (u.has_mask == 4.0) {
+        // distance field glyphs, the median of the channels is the distance to the outline
+        const ftcoord = vec2<f32>(in.ftcoord.x, 1.0 - in.ftcoord.y);
+        const field = textureSample(alpha_tex, alpha_samplr, ftcoord);
+        const dist = max(min(field.x, field.y), min(max(field.x, field.y), field.z));
+
+        result = result * clamp((dist - 0.5) * u.distance_range + 0.5, 0.0, 1.0) * scissor;
+    }
+    elseif (u.has_mask == 5.0 || u.has_mask == 6.0) {
+        // subpixel glyphs, blended in two passes: the coverage of each channel darkens the
+        // destination (5), then the color is added (6)
+        const ftcoord = vec2<f32>(in.ftcoord.x, 1.0 - in.ftcoord.y);
+        const coverage = textureSample(alpha_tex, alpha_samplr, ftcoord).xyz * scissor;
+        const alpha = result.w * (coverage.x + coverage.y + coverage.z) / 3.0;
+
+        if (u.has_mask == 5.0) {
+            result = vec4<f32>(coverage * result.w, alpha);
+        } else {
+            result = vec4<f32>(result.xyz * coverage, alpha);
+        }
+    }
+    elseif (u.has_mask == 1.0) {
     //     // revisit ftcoord
         const ftcoord = vec2<f32>(in.ftcoord.x, 1.0 - in.ftcoord.y);
         const r = textureSample(alpha_tex, alpha_samplr, ftcoord).x;
@@ -163,6 +328,9 @@ fn fragment_shader_aa(
         mask = mask * scissor;
         result = result * mask;
     }
+    elseif (u.shader_type == 4.0) {
+        result = result * scissor;
+    }
     elseif (u.shader_type != 2.0) {
         result = result * stroke_alpha * scissor;
     }
@@ -93,6 +93,15 @@ fn create_bind_group(
                 binding: 4,
                 resource: wgpu::BindingResource::Sampler(alpha_tex.sampler()),
             },
+            // chroma planes of yuv images, sampled with the texture sampler
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: wgpu::BindingResource::TextureView(tex.plane_view(0).unwrap_or(pseudo_tex.view())),
+            },
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: wgpu::BindingResource::TextureView(tex.plane_view(1).unwrap_or(pseudo_tex.view())),
+            },
         ],
     })
     // pass.set_tex
@@ -3,6 +3,8 @@ use std::rc::Rc;
 use raw_window_handle::HasRawWindowHandle;
 
 use std::future::Future;
+
+use super::WGPUMipmapGenerator;
 #[derive(Clone)]
 pub struct WGPUInstance {
     pub instance: Rc<wgpu::Instance>,
@@ -71,6 +73,7 @@ pub struct WGPUContext {
     pub instance: WGPUInstance,
     pub device: Rc<wgpu::Device>,
     pub queue: Rc<wgpu::Queue>,
+    mipmap_generator: Rc<WGPUMipmapGenerator>,
 }
 
 impl WGPUContext {
@@ -91,6 +94,7 @@ impl WGPUContext {
         );
         async move {
             f.await.map(|(device, queue)| Self {
+                mipmap_generator: Rc::new(WGPUMipmapGenerator::new(&instance.adapter, &device)),
                 instance: instance.clone(),
                 device: Rc::new(device),
                 queue: Rc::new(queue),
@@ -100,9 +104,10 @@ impl WGPUContext {
 
     pub fn from_device(instance: WGPUInstance, device: Rc<wgpu::Device>, queue: Rc<wgpu::Queue>) -> Self {
         Self {
+            mipmap_generator: Rc::new(WGPUMipmapGenerator::new(&instance.adapter, &device)),
             instance,
             device,
-            queue
+            queue,
         }
     }
 }
@@ -124,6 +129,10 @@ impl WGPUContext {
         self.instance.surface.as_ref().unwrap()
     }
 
+    pub fn mipmap_generator(&self) -> &WGPUMipmapGenerator {
+        &self.mipmap_generator
+    }
+
     #[inline]
     pub fn create_command_encoder(&self, label: Option<&str>) -> wgpu::CommandEncoder {
         self.device
@@ -0,0 +1,124 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Generates mipmap chains by rendering every level downscaled into the next one.
+/// Pipelines are created lazily for each texture format.
+pub struct WGPUMipmapGenerator {
+    shader: wgpu::ShaderModule,
+    sampler: wgpu::Sampler,
+    pipelines: RefCell<HashMap<wgpu::TextureFormat, wgpu::RenderPipeline>>,
+}
+
+impl WGPUMipmapGenerator {
+    pub fn new(adapter: &wgpu::Adapter, device: &wgpu::Device) -> Self {
+        let mut flags = wgpu::ShaderFlags::VALIDATION;
+        match adapter.get_info().backend {
+            wgpu::Backend::Metal | wgpu::Backend::Vulkan => flags |= wgpu::ShaderFlags::EXPERIMENTAL_TRANSLATION,
+            _ => (), //TODO
+        }
+
+        let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("mipmap shader"),
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(include_str!("mipmap.wgsl"))),
+            flags,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("mipmap sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            shader,
+            sampler,
+            pipelines: Default::default(),
+        }
+    }
+
+    pub fn generate(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        format: wgpu::TextureFormat,
+        mip_level_count: u32,
+    ) {
+        let mut pipelines = self.pipelines.borrow_mut();
+
+        let pipeline = pipelines.entry(format).or_insert_with(|| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("mipmap pipeline"),
+                layout: None,
+                vertex: wgpu::VertexState {
+                    module: &self.shader,
+                    entry_point: "vertex_shader",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.shader,
+                    entry_point: "fragment_shader",
+                    targets: &[format.into()],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+            })
+        });
+
+        let layout = pipeline.get_bind_group_layout(0);
+
+        let level_view = |level| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("mipmap level"),
+                base_mip_level: level,
+                mip_level_count: std::num::NonZeroU32::new(1),
+                ..Default::default()
+            })
+        };
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("mipmap encoder"),
+        });
+
+        for level in 1..mip_level_count {
+            let src_view = level_view(level - 1);
+            let dst_view = level_view(level);
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("mipmap bind group"),
+                layout: &layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&src_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("mipmap pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: &dst_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+
+        queue.submit(Some(encoder.finish()));
+    }
+}
@@ -1,10 +1,18 @@
 use crate::{
+    image::{
+        chroma_size,
+        validate_chroma_planes,
+    },
     ErrorKind,
+    ImageFilter,
     ImageFlags,
     ImageInfo,
+    ImageSampler,
     ImageSource,
+    MipmapMode,
     PixelFormat,
     Size,
+    WrapMode,
 };
 
 use super::{
@@ -18,7 +26,13 @@ use super::{
 
 // static INSTANCE: Once<usize> = Once::new();
 
-use rgb::ComponentBytes;
+use std::sync::Arc;
+
+use imgref::ImgVec;
+use rgb::{
+    ComponentBytes,
+    RGBA8,
+};
 
 impl From<PixelFormat> for wgpu::TextureFormat {
     fn from(a: PixelFormat) -> Self {
@@ -26,6 +40,11 @@ impl From<PixelFormat> for wgpu::TextureFormat {
             PixelFormat::Rgba8 => Self::Rgba8Unorm,
             PixelFormat::Rgb8 => unimplemented!("wgpu doesn't support the RGB8 pixel format"),
             PixelFormat::Gray8 => Self::R8Unorm,
+            PixelFormat::GrayAlpha8 => Self::Rg8Unorm,
+            PixelFormat::Bgra8 => Self::Bgra8Unorm,
+            PixelFormat::Rgba16F => Self::Rgba16Float,
+            // Luma plane, the chroma planes are separate textures
+            PixelFormat::Nv12 | PixelFormat::I420 => Self::R8Unorm,
         }
     }
 }
@@ -34,9 +53,14 @@ pub struct WGPUTexture {
     //
     ctx: WGPUContext,
     info: ImageInfo,
-    tex: wgpu::Texture,
+    // Shared with the application for imported textures
+    tex: Arc<wgpu::Texture>,
     view: wgpu::TextureView,
+    // Render attachments may only cover a single mip level
+    attachment_view: wgpu::TextureView,
     sampler: wgpu::Sampler,
+    // Chroma planes of YUV images
+    planes: Vec<(wgpu::Texture, wgpu::TextureView)>,
 
     // stencil: Option<wgpu::Texture>,
     // stencil_view: Option<wgpu::TextureView>,
@@ -55,9 +79,6 @@ impl WGPUTexture {
         let ctx = ctx.clone();
 
         let generate_mipmaps = info.flags().contains(ImageFlags::GENERATE_MIPMAPS);
-        let nearest = info.flags().contains(ImageFlags::NEAREST);
-        let repeatx = info.flags().contains(ImageFlags::REPEAT_X);
-        let repeaty = info.flags().contains(ImageFlags::REPEAT_Y);
 
         let format = info.format().into();
 
@@ -79,44 +100,53 @@ impl WGPUTexture {
             dimension: wgpu::TextureDimension::D2,
             format,
             //todo!
-            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::COPY_DST,
+            usage: wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::RENDER_ATTACHMENT
+                | wgpu::TextureUsage::COPY_DST
+                | wgpu::TextureUsage::COPY_SRC,
         });
 
         let view = tex.create_view(&wgpu::TextureViewDescriptor::default());
         // .create_view(&Default::default());
-
-        let filter = if nearest {
-            wgpu::FilterMode::Nearest
-        } else {
-            wgpu::FilterMode::Linear
-        };
-
-        let mut sampler_desc = wgpu::SamplerDescriptor {
-            label: Some("Texture Sampler"),
-            mag_filter: filter,
-            min_filter: filter,
-            ..Default::default()
-        };
-
-        if generate_mipmaps {
-            tex.generate_mipmaps(ctx.device());
-            sampler_desc.mipmap_filter = filter;
+        let attachment_view = create_attachment_view(&tex);
+
+        let sampler = create_sampler(&ctx, info);
+
+        let mut planes = Vec::new();
+
+        if info.format().is_yuv() {
+            let (width, height) = chroma_size(info.width(), info.height());
+
+            let (plane_count, plane_format) = if info.format() == PixelFormat::Nv12 {
+                (1, wgpu::TextureFormat::Rg8Unorm)
+            } else {
+                (2, wgpu::TextureFormat::R8Unorm)
+            };
+
+            let plane_size: wgpu::Extent3d = Size::new(width as _, height as _).into();
+
+            for _ in 0..plane_count {
+                let plane = ctx.device().create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: plane_size,
+                    mip_level_count: if generate_mipmaps {
+                        plane_size.mip_mipmap_level_count()
+                    } else {
+                        1
+                    },
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: plane_format,
+                    usage: wgpu::TextureUsage::SAMPLED
+                        | wgpu::TextureUsage::COPY_DST
+                        | wgpu::TextureUsage::RENDER_ATTACHMENT,
+                });
+
+                let plane_view = plane.create_view(&wgpu::TextureViewDescriptor::default());
+                planes.push((plane, plane_view));
+            }
         }
 
-        sampler_desc.address_mode_u = if repeatx {
-            wgpu::AddressMode::Repeat
-        } else {
-            wgpu::AddressMode::ClampToEdge
-        };
-
-        sampler_desc.address_mode_v = if repeaty {
-            wgpu::AddressMode::Repeat
-        } else {
-            wgpu::AddressMode::ClampToEdge
-        };
-
-        let sampler = ctx.device().create_sampler(&sampler_desc);
-
         let stencil_label = format!("{:?} stencil", label);
         let stencil_desc = super::new_stencil_descriptor(info.size(), &stencil_label);
 
@@ -131,7 +161,46 @@ impl WGPUTexture {
 
         Ok(Self {
             view,
+            attachment_view,
+            sampler,
+            planes,
+            ctx,
+            info,
+            tex: Arc::new(tex),
+            stencil,
+            stencil_view,
+        })
+    }
+
+    /// Wraps a texture created by the application. The texture must have the `SAMPLED` usage
+    /// and a format matching the image info.
+    pub fn from_texture(
+        ctx: &WGPUContext,
+        tex: Arc<wgpu::Texture>,
+        info: ImageInfo,
+        label: &str,
+    ) -> Result<Self, ErrorKind> {
+        if info.format().is_yuv() || info.format() == PixelFormat::Rgb8 {
+            return Err(ErrorKind::UnsuportedImageFromat);
+        }
+
+        let ctx = ctx.clone();
+
+        let view = tex.create_view(&wgpu::TextureViewDescriptor::default());
+        let attachment_view = create_attachment_view(&tex);
+        let sampler = create_sampler(&ctx, info);
+
+        let stencil_label = format!("{:?} stencil", label);
+        let stencil_desc = super::new_stencil_descriptor(info.size(), &stencil_label);
+
+        let stencil = ctx.device().create_texture(&stencil_desc);
+        let stencil_view = stencil.create_view(&Default::default());
+
+        Ok(Self {
+            view,
+            attachment_view,
             sampler,
+            planes: Vec::new(),
             ctx,
             info,
             tex,
@@ -140,6 +209,11 @@ impl WGPUTexture {
         })
     }
 
+    pub fn set_sampler(&mut self, sampler: ImageSampler) {
+        self.info.set_sampler(sampler);
+        self.sampler = create_sampler(&self.ctx, self.info);
+    }
+
     pub fn stencil_view(&self) -> &wgpu::TextureView {
         &self.stencil_view
     }
@@ -203,21 +277,199 @@ impl WGPUTexture {
                     .queue()
                     .write_texture(copy_view, data.buf().as_bytes(), data_layout, size.into())
             }
+            ImageSource::GrayAlpha(data) => {
+                let data_layout = wgpu::ImageDataLayout {
+                    bytes_per_row: Some(std::num::NonZeroU32::new(2 * width as u32)).unwrap(),
+                    ..Default::default()
+                };
+
+                self.ctx
+                    .queue()
+                    .write_texture(copy_view, data.buf().as_bytes(), data_layout, size.into())
+            }
+            ImageSource::Bgra(data) => {
+                let data_layout = wgpu::ImageDataLayout {
+                    bytes_per_row: Some(std::num::NonZeroU32::new(4 * width as u32)).unwrap(),
+                    ..Default::default()
+                };
+
+                self.ctx
+                    .queue()
+                    .write_texture(copy_view, data.buf().as_bytes(), data_layout, size.into())
+            }
+            ImageSource::Rgba16F(data) => {
+                let data_layout = wgpu::ImageDataLayout {
+                    bytes_per_row: Some(std::num::NonZeroU32::new(8 * width as u32)).unwrap(),
+                    ..Default::default()
+                };
+
+                self.ctx
+                    .queue()
+                    .write_texture(copy_view, data.buf().as_bytes(), data_layout, size.into())
+            }
+            ImageSource::Nv12 { y: luma, uv } => {
+                validate_chroma_planes(&src, &self.info, x, y)?;
+
+                let data_layout = wgpu::ImageDataLayout {
+                    bytes_per_row: Some(std::num::NonZeroU32::new(width as u32)).unwrap(),
+                    ..Default::default()
+                };
+
+                self.ctx
+                    .queue()
+                    .write_texture(copy_view, luma.buf().as_bytes(), data_layout, size.into());
+
+                self.write_plane(0, x / 2, y / 2, uv.width(), uv.height(), 2, uv.buf().as_bytes());
+            }
+            ImageSource::I420 { y: luma, u, v } => {
+                validate_chroma_planes(&src, &self.info, x, y)?;
+
+                let data_layout = wgpu::ImageDataLayout {
+                    bytes_per_row: Some(std::num::NonZeroU32::new(width as u32)).unwrap(),
+                    ..Default::default()
+                };
+
+                self.ctx
+                    .queue()
+                    .write_texture(copy_view, luma.buf().as_bytes(), data_layout, size.into());
+
+                self.write_plane(0, x / 2, y / 2, u.width(), u.height(), 1, u.buf().as_bytes());
+                self.write_plane(1, x / 2, y / 2, v.width(), v.height(), 1, v.buf().as_bytes());
+            }
             ImageSource::Rgb(_) => {
                 unimplemented!(
                     "wgpu doesn't support RGB pixel format. Image should have been converted in load_image_file"
                 )
             }
         };
-        let generate_mipmaps = self.info.flags().contains(ImageFlags::GENERATE_MIPMAPS);
-        if generate_mipmaps {
-            // self.tex.generate_mipmaps(&self.queue);
-            self.tex().generate_mipmaps(self.ctx.device());
-        }
+        self.generate_mipmaps();
 
         Ok(())
     }
 
+    /// Regenerates the mip chain from the base level, if the image has mipmaps.
+    pub fn generate_mipmaps(&self) {
+        if !self.info.flags().contains(ImageFlags::GENERATE_MIPMAPS) {
+            return;
+        }
+
+        let size: wgpu::Extent3d = self.info.size().into();
+        self.tex
+            .generate_mipmaps(&self.ctx, self.format(), size.mip_mipmap_level_count());
+
+        if !self.planes.is_empty() {
+            let (width, height) = chroma_size(self.info.width(), self.info.height());
+            let size: wgpu::Extent3d = Size::new(width as _, height as _).into();
+
+            let format = if self.info.format() == PixelFormat::Nv12 {
+                wgpu::TextureFormat::Rg8Unorm
+            } else {
+                wgpu::TextureFormat::R8Unorm
+            };
+
+            for (plane, _) in &self.planes {
+                plane.generate_mipmaps(&self.ctx, format, size.mip_mipmap_level_count());
+            }
+        }
+    }
+
+    // Uploads one chroma plane of a YUV image
+    #[allow(clippy::too_many_arguments)]
+    fn write_plane(&self, index: usize, x: usize, y: usize, width: usize, height: usize, bpp: usize, data: &[u8]) {
+        let copy_view = wgpu::ImageCopyTexture {
+            mip_level: 0,
+            origin: wgpu::Origin3d {
+                x: x as _,
+                y: y as _,
+                z: 0,
+            },
+            texture: &self.planes[index].0,
+        };
+
+        let data_layout = wgpu::ImageDataLayout {
+            bytes_per_row: Some(std::num::NonZeroU32::new((bpp * width) as u32)).unwrap(),
+            ..Default::default()
+        };
+
+        let size = Size::new(width as _, height as _);
+
+        self.ctx
+            .queue()
+            .write_texture(copy_view, data, data_layout, size.into())
+    }
+
+    // Copies a region of the texture into a staging buffer and converts it to RGBA
+    pub fn read(&self, x: usize, y: usize, width: usize, height: usize) -> Result<ImgVec<RGBA8>, ErrorKind> {
+        let bpp = match self.info.format() {
+            PixelFormat::Rgba8 | PixelFormat::Bgra8 => 4,
+            PixelFormat::GrayAlpha8 => 2,
+            PixelFormat::Gray8 => 1,
+            _ => return Err(ErrorKind::UnsuportedImageFromat),
+        };
+
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+        let padded_row = (bpp * width + align - 1) / align * align;
+
+        let buffer = self.ctx.device().create_buffer(&wgpu::BufferDescriptor {
+            label: Some("image readback"),
+            size: (padded_row * height) as _,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.ctx.create_command_encoder(Some("image readback"));
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: x as _,
+                    y: y as _,
+                    z: 0,
+                },
+                texture: self.tex(),
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(padded_row as u32),
+                    rows_per_image: None,
+                },
+            },
+            Size::new(width as _, height as _).into(),
+        );
+
+        self.ctx.queue().submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+
+        // Waiting on the device completes the mapping, so the returned future doesn't need to be polled
+        let _ = slice.map_async(wgpu::MapMode::Read);
+        self.ctx.device().poll(wgpu::Maintain::Wait);
+
+        let mut pixels = Vec::with_capacity(width * height);
+
+        {
+            let data = slice.get_mapped_range();
+
+            for row in data.chunks(padded_row) {
+                for px in row[..bpp * width].chunks(bpp) {
+                    pixels.push(match self.info.format() {
+                        PixelFormat::Rgba8 => RGBA8::new(px[0], px[1], px[2], px[3]),
+                        PixelFormat::Bgra8 => RGBA8::new(px[2], px[1], px[0], px[3]),
+                        PixelFormat::GrayAlpha8 => RGBA8::new(px[0], px[0], px[0], px[1]),
+                        _ => RGBA8::new(px[0], px[0], px[0], 255),
+                    });
+                }
+            }
+        }
+
+        buffer.unmap();
+
+        Ok(ImgVec::new(pixels, width, height))
+    }
+
     pub fn tex(&self) -> &wgpu::Texture {
         &self.tex
     }
@@ -226,6 +478,15 @@ impl WGPUTexture {
         &self.view
     }
 
+    /// View of the base level, for rendering into the texture
+    pub fn attachment_view(&self) -> &wgpu::TextureView {
+        &self.attachment_view
+    }
+
+    pub fn plane_view(&self, index: usize) -> Option<&wgpu::TextureView> {
+        self.planes.get(index).map(|(_, view)| view)
+    }
+
     pub fn sampler(&self) -> &wgpu::Sampler {
         &self.sampler
     }
@@ -234,6 +495,10 @@ impl WGPUTexture {
         self.info.size()
     }
 
+    pub fn info(&self) -> ImageInfo {
+        self.info
+    }
+
     // pub fn delete(&self) {
     //     // self.tex.destroy();
     //     // self.stencil.destroy();
@@ -244,6 +509,60 @@ impl WGPUTexture {
     }
 }
 
+fn create_attachment_view(tex: &wgpu::Texture) -> wgpu::TextureView {
+    tex.create_view(&wgpu::TextureViewDescriptor {
+        label: Some("render attachment"),
+        base_mip_level: 0,
+        mip_level_count: std::num::NonZeroU32::new(1),
+        ..Default::default()
+    })
+}
+
+fn create_sampler(ctx: &WGPUContext, info: ImageInfo) -> wgpu::Sampler {
+    let sampler = info.sampler();
+
+    let filter = |filter| match filter {
+        // The bicubic kernel is made of linear samples
+        _ if sampler.bicubic() => wgpu::FilterMode::Linear,
+        ImageFilter::Nearest => wgpu::FilterMode::Nearest,
+        ImageFilter::Linear => wgpu::FilterMode::Linear,
+    };
+
+    let address_mode = |mode| match mode {
+        WrapMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        WrapMode::Repeat => wgpu::AddressMode::Repeat,
+        WrapMode::MirroredRepeat => wgpu::AddressMode::MirrorRepeat,
+        WrapMode::ClampToBorder => wgpu::AddressMode::ClampToBorder,
+    };
+
+    let mut sampler_desc = wgpu::SamplerDescriptor {
+        label: Some("Texture Sampler"),
+        mag_filter: filter(sampler.mag_filter()),
+        min_filter: filter(sampler.min_filter()),
+        address_mode_u: address_mode(sampler.wrap_x()),
+        address_mode_v: address_mode(sampler.wrap_y()),
+        ..Default::default()
+    };
+
+    let mipmap_mode = if info.flags().contains(ImageFlags::GENERATE_MIPMAPS) {
+        sampler.mipmap_mode()
+    } else {
+        MipmapMode::None
+    };
+
+    match mipmap_mode {
+        MipmapMode::None => sampler_desc.lod_max_clamp = 0.0,
+        MipmapMode::Nearest => sampler_desc.mipmap_filter = wgpu::FilterMode::Nearest,
+        MipmapMode::Linear => sampler_desc.mipmap_filter = wgpu::FilterMode::Linear,
+    }
+
+    if sampler.wrap_x() == WrapMode::ClampToBorder || sampler.wrap_y() == WrapMode::ClampToBorder {
+        sampler_desc.border_color = Some(wgpu::SamplerBorderColor::TransparentBlack);
+    }
+
+    ctx.device().create_sampler(&sampler_desc)
+}
+
 // impl Drop for WGPUTexture {
 //     fn drop(&mut self) {
 //         self.delete();
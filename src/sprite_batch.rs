@@ -0,0 +1,254 @@
+use rgb::RGBA8;
+
+use crate::{
+    geometry::Rect,
+    Color,
+    ImageFlags,
+    ImageId,
+    ImageInfo,
+    Transform2D,
+    Vertex,
+};
+
+#[derive(Copy, Clone, Debug)]
+struct Sprite {
+    src: Rect,
+    dst: Rect,
+    angle: f32,
+    tint: RGBA8,
+}
+
+/// A list of textured quads taken from a single image (sprite sheet).
+///
+/// All sprites in a batch are submitted to the renderer as a single draw call without
+/// any path tessellation, which makes it suitable for particles, tile maps and similar
+/// content consisting of thousands of quads. Each sprite can have its own tint color.
+///
+/// # Example
+/// ```
+/// use femtovg::{Canvas, Color, ImageFlags, PixelFormat, Rect, SpriteBatch, renderer::Void};
+///
+/// let mut canvas = Canvas::new(Void).expect("Cannot create canvas");
+/// let sheet = canvas.create_image_empty(64, 64, PixelFormat::Rgba8, ImageFlags::empty()).unwrap();
+///
+/// let mut batch = SpriteBatch::new(sheet);
+/// batch.add(Rect::new(0.0, 0.0, 16.0, 16.0), Rect::new(10.0, 10.0, 32.0, 32.0));
+/// batch.add_rotated(Rect::new(16.0, 0.0, 16.0, 16.0), Rect::new(50.0, 10.0, 32.0, 32.0), 0.5);
+/// batch.add_tinted(Rect::new(32.0, 0.0, 16.0, 16.0), Rect::new(90.0, 10.0, 32.0, 32.0), 0.0, Color::rgbf(1.0, 0.5, 0.5));
+///
+/// canvas.draw_sprite_batch(&batch).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct SpriteBatch {
+    image: ImageId,
+    tint: Color,
+    sprites: Vec<Sprite>,
+}
+
+impl SpriteBatch {
+    /// Creates a new empty batch drawing from the provided image.
+    pub fn new(image: ImageId) -> Self {
+        Self::with_capacity(image, 0)
+    }
+
+    /// Creates a new empty batch with space reserved for `capacity` sprites.
+    pub fn with_capacity(image: ImageId, capacity: usize) -> Self {
+        Self {
+            image,
+            tint: Color::white(),
+            sprites: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the image sprites are taken from.
+    pub fn image(&self) -> ImageId {
+        self.image
+    }
+
+    /// Returns the tint color applied to all sprites in the batch.
+    pub fn tint(&self) -> Color {
+        self.tint
+    }
+
+    /// Sets the color all sprites are multiplied with, on top of their own tint. White (no tint)
+    /// by default.
+    pub fn set_tint(&mut self, color: Color) {
+        self.tint = color;
+    }
+
+    /// Adds a sprite drawing the `src` region of the image (in image pixels) into `dst`.
+    pub fn add(&mut self, src: Rect, dst: Rect) {
+        self.add_tinted(src, dst, 0.0, Color::white());
+    }
+
+    /// Adds a sprite rotated around the center of `dst`. Angle is specified in radians.
+    pub fn add_rotated(&mut self, src: Rect, dst: Rect, angle: f32) {
+        self.add_tinted(src, dst, angle, Color::white());
+    }
+
+    /// Adds a rotated sprite whose pixels are multiplied with `tint`.
+    pub fn add_tinted(&mut self, src: Rect, dst: Rect, angle: f32, tint: Color) {
+        let tint = tint.premultiplied();
+        let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        self.sprites.push(Sprite {
+            src,
+            dst,
+            angle,
+            tint: RGBA8::new(to_u8(tint.r), to_u8(tint.g), to_u8(tint.b), to_u8(tint.a)),
+        });
+    }
+
+    /// Removes all sprites, keeping the allocated memory for reuse in the next frame.
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+    }
+
+    /// Returns the number of sprites in the batch.
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    /// Returns true if the batch contains no sprites.
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    /// Generates the triangle list for all sprites in the batch.
+    pub(crate) fn vertices(&self, info: &ImageInfo, transform: &Transform2D) -> Vec<Vertex> {
+        let mut verts = Vec::with_capacity(self.sprites.len() * 6);
+
+        let image_width = info.width() as f32;
+        let image_height = info.height() as f32;
+
+        if image_width <= 0.0 || image_height <= 0.0 {
+            return verts;
+        }
+
+        let itw = 1.0 / image_width;
+        let ith = 1.0 / image_height;
+
+        let flip_y = info.flags().contains(ImageFlags::FLIP_Y);
+
+        for sprite in &self.sprites {
+            let s0 = sprite.src.x * itw;
+            let s1 = (sprite.src.x + sprite.src.w) * itw;
+
+            let (t0, t1) = if flip_y {
+                (1.0 - sprite.src.y * ith, 1.0 - (sprite.src.y + sprite.src.h) * ith)
+            } else {
+                (sprite.src.y * ith, (sprite.src.y + sprite.src.h) * ith)
+            };
+
+            // Sprite local transform: rotation around the center of the destination rectangle
+            let hw = sprite.dst.w * 0.5;
+            let hh = sprite.dst.h * 0.5;
+
+            let mut xform = Transform2D::identity();
+
+            if sprite.angle != 0.0 {
+                xform.rotate(sprite.angle);
+            }
+
+            xform[4] = sprite.dst.x + hw;
+            xform[5] = sprite.dst.y + hh;
+            xform.multiply(transform);
+
+            let (p0, p1) = xform.transform_point(-hw, -hh);
+            let (p2, p3) = xform.transform_point(hw, -hh);
+            let (p4, p5) = xform.transform_point(hw, hh);
+            let (p6, p7) = xform.transform_point(-hw, hh);
+
+            let v0 = Vertex::with_tint(p0, p1, s0, t0, sprite.tint);
+            let v1 = Vertex::with_tint(p2, p3, s1, t0, sprite.tint);
+            let v2 = Vertex::with_tint(p4, p5, s1, t1, sprite.tint);
+            let v3 = Vertex::with_tint(p6, p7, s0, t1, sprite.tint);
+
+            // Mirrored sprites (negative destination size or transform scale) flip the winding
+            // order, which has to be reversed to not get culled.
+            let det = xform[0] * xform[3] - xform[1] * xform[2];
+
+            if (det < 0.0) != (hw * hh < 0.0) {
+                verts.extend_from_slice(&[v0, v1, v2, v0, v2, v3]);
+            } else {
+                verts.extend_from_slice(&[v0, v2, v1, v0, v3, v2]);
+            }
+        }
+
+        verts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rgb::RGBA8;
+
+    use super::*;
+    use crate::{
+        renderer::Recorder,
+        Canvas,
+        ImageFlags,
+        PixelFormat,
+    };
+
+    #[test]
+    fn sprite_batch() {
+        let recorder = Recorder::default();
+        let mut canvas = Canvas::new(recorder.clone()).unwrap();
+        canvas.set_size(200, 200, 1.0);
+
+        let sheet = canvas
+            .create_image_empty(64, 32, PixelFormat::Rgba8, ImageFlags::empty())
+            .unwrap();
+
+        let mut batch = SpriteBatch::with_capacity(sheet, 1000);
+        let faded = Color::rgbaf(1.0, 0.5, 0.0, 0.5);
+
+        for i in 0..1000 {
+            let dst = Rect::new((i % 20) as f32 * 10.0, (i / 20) as f32 * 4.0, 10.0, 10.0);
+
+            if i % 2 == 0 {
+                batch.add(Rect::new(0.0, 0.0, 16.0, 16.0), dst);
+            } else {
+                batch.add_tinted(Rect::new(16.0, 16.0, 16.0, 16.0), dst, i as f32 * 0.1, faded);
+            }
+        }
+
+        assert_eq!(batch.len(), 1000);
+        canvas.draw_sprite_batch(&batch).unwrap();
+        canvas.flush(None);
+
+        // All sprites are drawn by a single command following the render target set by set_size,
+        // two triangles each
+        {
+            let recording = recorder.0.borrow();
+            assert_eq!(recording.commands, 2);
+            assert_eq!(recording.verts.len(), 6000);
+
+            let white = RGBA8::new(255, 255, 255, 255);
+            let premultiplied = RGBA8::new(128, 64, 0, 128);
+
+            for (i, sprite) in recording.verts.chunks(6).enumerate() {
+                let tint = if i % 2 == 0 { white } else { premultiplied };
+                assert!(sprite.iter().all(|vertex| vertex.tint == tint));
+            }
+
+            // Texture coordinates of the source regions
+            let (u, v) = (recording.verts[6].u, recording.verts[6].v);
+            assert!((0.25..=0.5).contains(&u) && (0.5..=1.0).contains(&v));
+        }
+
+        // The batch tint applies to the whole batch on top of the sprite tints
+        batch.set_tint(Color::rgbf(0.5, 0.5, 0.5));
+        canvas.draw_sprite_batch(&batch).unwrap();
+        canvas.flush(None);
+        assert_eq!(recorder.0.borrow().commands, 1);
+
+        batch.clear();
+        assert!(batch.is_empty());
+        canvas.draw_sprite_batch(&batch).unwrap();
+        canvas.flush(None);
+        assert_eq!(recorder.0.borrow().commands, 0);
+    }
+}
@@ -0,0 +1,671 @@
+use std::cell::RefCell;
+use std::path::Path as FilePath;
+use std::str::FromStr;
+
+use crate::{
+    Canvas,
+    Color,
+    CompositeOperation,
+    ErrorKind,
+    FillRule,
+    ImageFlags,
+    ImageId,
+    LineCap,
+    LineJoin,
+    MaskMode,
+    Paint,
+    Path,
+    RenderTarget,
+    Renderer,
+    Transform2D,
+};
+
+/// An SVG document that can be drawn with [Canvas::draw_svg](struct.Canvas.html#method.draw_svg).
+///
+/// The document is parsed and simplified by [usvg](https://docs.rs/usvg) and converted to paths and
+/// paints once, so drawing it again only costs tessellation, which is cached per path as long as the
+/// transform doesn't change.
+///
+/// Groups, transforms, opacity, clip paths, fills and strokes with solid colors, linear and radial
+/// gradients are supported. Text is converted to paths by usvg using the font database of the
+/// `usvg::Options` the document is parsed with: [from_data](#method.from_data) and
+/// [from_file](#method.from_file) load the fonts installed on the system, use
+/// [from_data_with_options](#method.from_data_with_options) to provide fonts yourself. Text set in
+/// fonts that can't be found is left out. Masks, patterns, filters, embedded images, dashed strokes
+/// and gradient spread methods are not supported.
+pub struct SvgDocument {
+    width: f32,
+    height: f32,
+    nodes: Vec<SvgNode>,
+}
+
+enum SvgNode {
+    Path {
+        transform: Transform2D,
+        path: RefCell<Path>,
+        fill: Option<Paint>,
+        stroke: Option<Paint>,
+    },
+    Group {
+        transform: Transform2D,
+        opacity: f32,
+        clip: Option<Box<SvgClip>>,
+        children: Vec<SvgNode>,
+    },
+}
+
+struct SvgClip {
+    transform: Transform2D,
+    shapes: Vec<SvgClipShape>,
+    clip: Option<Box<SvgClip>>,
+}
+
+struct SvgClipShape {
+    transform: Transform2D,
+    path: RefCell<Path>,
+    fill_rule: FillRule,
+}
+
+impl SvgDocument {
+    /// Parses an SVG (or SVGZ) document with the default usvg options and the system fonts.
+    ///
+    /// The system fonts are searched for on every call, which can take a while. When parsing
+    /// several documents create the options once and use
+    /// [from_data_with_options](#method.from_data_with_options) instead.
+    pub fn from_data(data: &[u8]) -> Result<Self, ErrorKind> {
+        let mut options = usvg::Options::default();
+        options.fontdb.load_system_fonts();
+
+        Self::from_data_with_options(data, &options)
+    }
+
+    /// Parses an SVG (or SVGZ) document with the given usvg options. Text is rendered with the
+    /// fonts of `options.fontdb`, which is empty by default.
+    pub fn from_data_with_options(data: &[u8], options: &usvg::Options) -> Result<Self, ErrorKind> {
+        let tree = usvg::Tree::from_data(data, options)?;
+        Ok(Self::from_tree(&tree))
+    }
+
+    /// Reads and parses an SVG (or SVGZ) file with the default usvg options and the system fonts.
+    pub fn from_file<P: AsRef<FilePath>>(path: P) -> Result<Self, ErrorKind> {
+        let data = std::fs::read(path)?;
+        Self::from_data(&data)
+    }
+
+    /// Converts an already parsed usvg tree. Use this to control parsing, e.g. to provide fonts for text.
+    pub fn from_tree(tree: &usvg::Tree) -> Self {
+        let svg = tree.svg_node();
+        let view_box = usvg::utils::view_box_to_transform(svg.view_box.rect, svg.view_box.aspect, svg.size);
+
+        let root = SvgNode::Group {
+            transform: convert_transform(view_box),
+            opacity: 1.0,
+            clip: None,
+            children: convert_children(tree, &tree.root()),
+        };
+
+        Self {
+            width: svg.size.width() as f32,
+            height: svg.size.height() as f32,
+            nodes: vec![root],
+        }
+    }
+
+    /// Returns the width of the document.
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    /// Returns the height of the document.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    pub(crate) fn draw<T: Renderer>(&self, canvas: &mut Canvas<T>, transform: Transform2D) {
+        canvas.save();
+        apply_transform(canvas, &transform);
+
+        for node in &self.nodes {
+            node.draw(canvas, None);
+        }
+
+        canvas.restore();
+    }
+
+    pub(crate) fn stroke_paths_at<T: Renderer>(
+        &self,
+        canvas: &mut Canvas<T>,
+        transform: Transform2D,
+        x: f32,
+        y: f32,
+        paint: Paint,
+    ) -> bool {
+        canvas.save();
+        apply_transform(canvas, &transform);
+
+        let mut hit = false;
+
+        for node in &self.nodes {
+            hit |= node.stroke_at(canvas, x, y, paint);
+        }
+
+        canvas.restore();
+
+        hit
+    }
+}
+
+impl FromStr for SvgDocument {
+    type Err = ErrorKind;
+
+    fn from_str(text: &str) -> Result<Self, ErrorKind> {
+        Self::from_data(text.as_bytes())
+    }
+}
+
+impl SvgNode {
+    fn draw<T: Renderer>(&self, canvas: &mut Canvas<T>, clip: Option<ImageId>) {
+        match self {
+            Self::Path {
+                transform,
+                path,
+                fill,
+                stroke,
+            } => {
+                canvas.save();
+                apply_transform(canvas, transform);
+
+                let path = &mut *path.borrow_mut();
+
+                if let Some(fill) = fill {
+                    let paint = clipped(canvas, *fill, clip);
+                    canvas.fill_path(path, paint);
+                }
+
+                if let Some(stroke) = stroke {
+                    let paint = clipped(canvas, *stroke, clip);
+                    canvas.stroke_path(path, paint);
+                }
+
+                canvas.restore();
+            }
+            Self::Group {
+                transform,
+                opacity,
+                clip: group_clip,
+                children,
+            } => {
+                canvas.save();
+                apply_transform(canvas, transform);
+
+                let clip = match group_clip {
+                    Some(group_clip) => group_clip.render(canvas, clip),
+                    None => clip,
+                };
+
+                // Opacity applies to the group as a whole, so overlapping children are composited
+                // into a layer first. Drawing them translucent one by one beats not drawing at all.
+                let layer = if *opacity < 1.0 { begin_layer(canvas) } else { None };
+
+                if layer.is_none() {
+                    canvas.state_mut().alpha *= opacity;
+                }
+
+                for child in children {
+                    child.draw(canvas, clip);
+                }
+
+                if let Some((layer, target)) = layer {
+                    end_layer(canvas, layer, target, *opacity);
+                }
+
+                canvas.restore();
+            }
+        }
+    }
+
+    // Strokes the paths below this node whose fill contains the point. Clip paths are ignored.
+    fn stroke_at<T: Renderer>(&self, canvas: &mut Canvas<T>, x: f32, y: f32, paint: Paint) -> bool {
+        match self {
+            Self::Path {
+                transform, path, fill, ..
+            } => {
+                canvas.save();
+                apply_transform(canvas, transform);
+
+                let path = &mut *path.borrow_mut();
+                let fill_rule = fill.map_or(FillRule::NonZero, |fill| fill.fill_rule());
+
+                let hit = canvas.contains_point(path, x, y, fill_rule);
+
+                if hit {
+                    canvas.stroke_path(path, paint);
+                }
+
+                canvas.restore();
+
+                hit
+            }
+            Self::Group {
+                transform, children, ..
+            } => {
+                canvas.save();
+                apply_transform(canvas, transform);
+
+                let mut hit = false;
+
+                for child in children {
+                    hit |= child.stroke_at(canvas, x, y, paint);
+                }
+
+                canvas.restore();
+
+                hit
+            }
+        }
+    }
+}
+
+impl SvgClip {
+    // Renders the clip path into a coverage image the size of the current render target.
+    // Returns the outer clip if the image can't be created, drawing unclipped beats not drawing at all.
+    fn render<T: Renderer>(&self, canvas: &mut Canvas<T>, outer: Option<ImageId>) -> Option<ImageId> {
+        // A clip path on the clipPath element itself intersects with this one
+        let outer = match &self.clip {
+            Some(clip) => clip.render(canvas, outer),
+            None => outer,
+        };
+
+        let image = match canvas.create_scratch_image(ImageFlags::empty()) {
+            Ok(image) => image,
+            Err(_) => return outer,
+        };
+
+        let target = canvas.current_render_target;
+
+        canvas.save();
+        canvas.state_mut().alpha = 1.0;
+        canvas.global_composite_operation(CompositeOperation::SourceOver);
+
+        canvas.set_render_target(RenderTarget::Image(image));
+        let (width, height) = (canvas.width() as u32, canvas.height() as u32);
+        canvas.clear_rect(0, 0, width, height, Color::rgbaf(0.0, 0.0, 0.0, 0.0));
+
+        apply_transform(canvas, &self.transform);
+
+        for shape in &self.shapes {
+            canvas.save();
+            apply_transform(canvas, &shape.transform);
+
+            let mut paint = Paint::color(Color::white());
+            paint.set_fill_rule(shape.fill_rule);
+            let paint = clipped(canvas, paint, outer);
+
+            canvas.fill_path(&mut *shape.path.borrow_mut(), paint);
+            canvas.restore();
+        }
+
+        canvas.set_render_target(target);
+        canvas.restore();
+
+        Some(image)
+    }
+}
+
+// Redirects drawing into a transparent image the size of the current render target. Returns the
+// image and the render target to go back to.
+fn begin_layer<T: Renderer>(canvas: &mut Canvas<T>) -> Option<(ImageId, RenderTarget)> {
+    // Rendered content has premultiplied alpha
+    let image = canvas.create_scratch_image(ImageFlags::PREMULTIPLIED).ok()?;
+    let target = canvas.current_render_target;
+
+    canvas.set_render_target(RenderTarget::Image(image));
+    let (width, height) = (canvas.width() as u32, canvas.height() as u32);
+    canvas.clear_rect(0, 0, width, height, Color::rgbaf(0.0, 0.0, 0.0, 0.0));
+
+    canvas.save();
+    canvas.state_mut().alpha = 1.0;
+    canvas.global_composite_operation(CompositeOperation::SourceOver);
+
+    Some((image, target))
+}
+
+// Goes back to the render target and draws the layer over all of it
+fn end_layer<T: Renderer>(canvas: &mut Canvas<T>, image: ImageId, target: RenderTarget, opacity: f32) {
+    canvas.restore();
+    canvas.set_render_target(target);
+
+    let (width, height) = match canvas.image_info(image) {
+        Ok(info) => (info.width() as f32, info.height() as f32),
+        Err(_) => return,
+    };
+
+    canvas.save();
+    canvas.reset_transform();
+
+    let mut path = Path::new();
+    path.rect(0.0, 0.0, width, height);
+    canvas.fill_path(&mut path, Paint::image(image, 0.0, 0.0, width, height, 0.0, opacity));
+
+    canvas.restore();
+}
+
+fn clipped<T: Renderer>(canvas: &Canvas<T>, mut paint: Paint, clip: Option<ImageId>) -> Paint {
+    if let Some(image) = clip {
+        paint.set_mask(Some(canvas.device_mask(image, MaskMode::Alpha)));
+    }
+
+    paint
+}
+
+fn apply_transform<T: Renderer>(canvas: &mut Canvas<T>, transform: &Transform2D) {
+    canvas.premultiply_transform(*transform);
+}
+
+fn convert_children(tree: &usvg::Tree, parent: &usvg::Node) -> Vec<SvgNode> {
+    parent.children().filter_map(|node| convert_node(tree, &node)).collect()
+}
+
+fn convert_node(tree: &usvg::Tree, node: &usvg::Node) -> Option<SvgNode> {
+    match *node.borrow() {
+        usvg::NodeKind::Path(ref path) => convert_path(tree, path),
+        usvg::NodeKind::Group(ref group) => Some(SvgNode::Group {
+            transform: convert_transform(group.transform),
+            opacity: group.opacity.value() as f32,
+            clip: group.clip_path.as_ref().and_then(|id| convert_clip(tree, node, id)),
+            children: convert_children(tree, node),
+        }),
+        // Gradients and clip paths are only used through references, everything else is unsupported
+        _ => None,
+    }
+}
+
+fn convert_path(tree: &usvg::Tree, path: &usvg::Path) -> Option<SvgNode> {
+    if !matches!(path.visibility, usvg::Visibility::Visible) {
+        return None;
+    }
+
+    let bbox = path.data.bbox();
+
+    let fill = path.fill.as_ref().and_then(|fill| {
+        let mut paint = convert_paint(tree, &fill.paint, fill.opacity.value(), bbox)?;
+        paint.set_fill_rule(convert_fill_rule(fill.rule));
+        Some(paint)
+    });
+
+    let stroke = path.stroke.as_ref().and_then(|stroke| {
+        let mut paint = convert_paint(tree, &stroke.paint, stroke.opacity.value(), bbox)?;
+        paint.set_line_width(stroke.width.value() as f32);
+        paint.set_miter_limit(stroke.miterlimit.value() as f32);
+        paint.set_line_cap(match stroke.linecap {
+            usvg::LineCap::Butt => LineCap::Butt,
+            usvg::LineCap::Round => LineCap::Round,
+            usvg::LineCap::Square => LineCap::Square,
+        });
+        paint.set_line_join(match stroke.linejoin {
+            usvg::LineJoin::Miter => LineJoin::Miter,
+            usvg::LineJoin::Round => LineJoin::Round,
+            usvg::LineJoin::Bevel => LineJoin::Bevel,
+        });
+        Some(paint)
+    });
+
+    if fill.is_none() && stroke.is_none() {
+        return None;
+    }
+
+    Some(SvgNode::Path {
+        transform: convert_transform(path.transform),
+        path: RefCell::new(convert_path_data(&path.data)),
+        fill,
+        stroke,
+    })
+}
+
+fn convert_path_data(data: &usvg::PathData) -> Path {
+    let mut path = Path::new();
+
+    for segment in data.iter() {
+        match *segment {
+            usvg::PathSegment::MoveTo { x, y } => path.move_to(x as f32, y as f32),
+            usvg::PathSegment::LineTo { x, y } => path.line_to(x as f32, y as f32),
+            usvg::PathSegment::CurveTo { x1, y1, x2, y2, x, y } => {
+                path.bezier_to(x1 as f32, y1 as f32, x2 as f32, y2 as f32, x as f32, y as f32)
+            }
+            usvg::PathSegment::ClosePath => path.close(),
+        }
+    }
+
+    path
+}
+
+fn convert_paint(tree: &usvg::Tree, paint: &usvg::Paint, opacity: f64, bbox: Option<usvg::Rect>) -> Option<Paint> {
+    let id = match paint {
+        usvg::Paint::Color(color) => return Some(Paint::color(convert_color(*color, opacity))),
+        usvg::Paint::Link(id) => id,
+    };
+
+    let node = tree.defs_by_id(id)?;
+
+    let paint = match *node.borrow() {
+        usvg::NodeKind::LinearGradient(ref gradient) => {
+            let transform = gradient_transform(&gradient.base, bbox)?;
+            let stops = convert_stops(&gradient.base.stops, opacity);
+
+            // Gradient transforms can skew, so instead of transforming both end points find the
+            // canvas space line along which the gradient parameter grows from 0 to 1
+            let (x1, y1) = (gradient.x1 as f32, gradient.y1 as f32);
+            let (dx, dy) = (gradient.x2 as f32 - x1, gradient.y2 as f32 - y1);
+            let len2 = dx * dx + dy * dy;
+
+            let inv = transform.inversed();
+            let gx = (inv[0] * dx + inv[1] * dy) / len2;
+            let gy = (inv[2] * dx + inv[3] * dy) / len2;
+            let glen2 = gx * gx + gy * gy;
+
+            if !glen2.is_normal() {
+                // Zero length gradients are painted with the last stop color
+                return stops.last().map(|stop| Paint::color(stop.1));
+            }
+
+            let (sx, sy) = transform.transform_point(x1, y1);
+            Paint::linear_gradient_stops(sx, sy, sx + gx / glen2, sy + gy / glen2, &stops)
+        }
+        usvg::NodeKind::RadialGradient(ref gradient) => {
+            let transform = gradient_transform(&gradient.base, bbox)?;
+            let stops = convert_stops(&gradient.base.stops, opacity);
+
+            // Non-uniform scales and skews turn the circle into an ellipse, which is approximated
+            let (cx, cy) = transform.transform_point(gradient.cx as f32, gradient.cy as f32);
+            let scale = (transform[0] * transform[3] - transform[1] * transform[2]).abs().sqrt();
+            let radius = gradient.r.value() as f32 * scale;
+
+            Paint::radial_gradient_stops(cx, cy, 0.0, radius, &stops)
+        }
+        _ => return None,
+    };
+
+    Some(paint)
+}
+
+// Maps gradient coordinates to the user space of the painted path
+fn gradient_transform(gradient: &usvg::BaseGradient, bbox: Option<usvg::Rect>) -> Option<Transform2D> {
+    let mut transform = convert_transform(gradient.transform);
+
+    if matches!(gradient.units, usvg::Units::ObjectBoundingBox) {
+        let bbox = bbox?;
+        transform.multiply(&Transform2D([
+            bbox.width() as f32,
+            0.0,
+            0.0,
+            bbox.height() as f32,
+            bbox.x() as f32,
+            bbox.y() as f32,
+        ]));
+    }
+
+    Some(transform)
+}
+
+fn convert_stops(stops: &[usvg::Stop], opacity: f64) -> Vec<(f32, Color)> {
+    stops
+        .iter()
+        .map(|stop| {
+            (
+                stop.offset.value() as f32,
+                convert_color(stop.color, stop.opacity.value() * opacity),
+            )
+        })
+        .collect()
+}
+
+fn convert_clip(tree: &usvg::Tree, node: &usvg::Node, id: &str) -> Option<Box<SvgClip>> {
+    let clip_node = tree.defs_by_id(id)?;
+
+    let clip = match *clip_node.borrow() {
+        usvg::NodeKind::ClipPath(ref clip) => {
+            let mut transform = convert_transform(clip.transform);
+
+            // Bounding box units map the clip contents into the box before the clip transform
+            if matches!(clip.units, usvg::Units::ObjectBoundingBox) {
+                let [minx, miny, maxx, maxy] = node_bounds(node, Transform2D::identity())?;
+                transform.premultiply(&Transform2D([maxx - minx, 0.0, 0.0, maxy - miny, minx, miny]));
+            }
+
+            let mut shapes = Vec::new();
+            collect_clip_shapes(&clip_node, Transform2D::identity(), &mut shapes);
+
+            SvgClip {
+                transform,
+                shapes,
+                clip: clip.clip_path.as_ref().and_then(|id| convert_clip(tree, node, id)),
+            }
+        }
+        _ => return None,
+    };
+
+    Some(Box::new(clip))
+}
+
+fn collect_clip_shapes(parent: &usvg::Node, parent_transform: Transform2D, shapes: &mut Vec<SvgClipShape>) {
+    for node in parent.children() {
+        match *node.borrow() {
+            usvg::NodeKind::Path(ref path) => {
+                let mut transform = convert_transform(path.transform);
+                transform.multiply(&parent_transform);
+
+                shapes.push(SvgClipShape {
+                    transform,
+                    path: RefCell::new(convert_path_data(&path.data)),
+                    fill_rule: path
+                        .fill
+                        .as_ref()
+                        .map_or(FillRule::NonZero, |fill| convert_fill_rule(fill.rule)),
+                });
+            }
+            usvg::NodeKind::Group(ref group) => {
+                let mut transform = convert_transform(group.transform);
+                transform.multiply(&parent_transform);
+
+                collect_clip_shapes(&node, transform, shapes);
+            }
+            _ => (),
+        }
+    }
+}
+
+// Bounding box of all paths below the node as [minx, miny, maxx, maxy], in the node's coordinate system
+fn node_bounds(node: &usvg::Node, transform: Transform2D) -> Option<[f32; 4]> {
+    let mut bounds: Option<[f32; 4]> = None;
+
+    for child in node.children() {
+        let child_bounds = match *child.borrow() {
+            usvg::NodeKind::Path(ref path) => path.data.bbox().map(|bbox| {
+                let mut path_transform = convert_transform(path.transform);
+                path_transform.multiply(&transform);
+
+                let (x, y) = (bbox.x() as f32, bbox.y() as f32);
+                let (w, h) = (bbox.width() as f32, bbox.height() as f32);
+
+                [(x, y), (x + w, y), (x, y + h), (x + w, y + h)]
+                    .iter()
+                    .map(|&(x, y)| path_transform.transform_point(x, y))
+                    .fold([f32::MAX, f32::MAX, f32::MIN, f32::MIN], |b, (x, y)| {
+                        [b[0].min(x), b[1].min(y), b[2].max(x), b[3].max(y)]
+                    })
+            }),
+            usvg::NodeKind::Group(ref group) => {
+                let mut group_transform = convert_transform(group.transform);
+                group_transform.multiply(&transform);
+
+                node_bounds(&child, group_transform)
+            }
+            _ => None,
+        };
+
+        if let Some(b) = child_bounds {
+            bounds = Some(match bounds {
+                Some(a) => [a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])],
+                None => b,
+            });
+        }
+    }
+
+    bounds
+}
+
+fn convert_transform(t: usvg::Transform) -> Transform2D {
+    Transform2D([t.a as f32, t.b as f32, t.c as f32, t.d as f32, t.e as f32, t.f as f32])
+}
+
+fn convert_color(color: usvg::Color, opacity: f64) -> Color {
+    Color::rgbaf(
+        color.red as f32 / 255.0,
+        color.green as f32 / 255.0,
+        color.blue as f32 / 255.0,
+        opacity as f32,
+    )
+}
+
+fn convert_fill_rule(rule: usvg::FillRule) -> FillRule {
+    match rule {
+        usvg::FillRule::NonZero => FillRule::NonZero,
+        usvg::FillRule::EvenOdd => FillRule::EvenOdd,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::Recorder;
+
+    // Draws a group of two overlapping rectangles and returns the number of commands
+    fn group_commands(opacity: &str) -> usize {
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="20">
+                <g opacity="{}">
+                    <rect width="10" height="10"/>
+                    <rect x="5" y="5" width="10" height="10"/>
+                </g>
+            </svg>"#,
+            opacity
+        );
+
+        let document = SvgDocument::from_data_with_options(svg.as_bytes(), &usvg::Options::default()).unwrap();
+
+        let recorder = Recorder::default();
+        let mut canvas = Canvas::new(recorder.clone()).unwrap();
+        canvas.set_size(20, 20, 1.0);
+        canvas.draw_svg(&document, Transform2D::identity());
+        canvas.flush(None);
+
+        let commands = recorder.0.borrow().commands;
+        commands
+    }
+
+    #[test]
+    fn group_opacity_composites_a_layer() {
+        // Switching to the layer and back, clearing it and drawing it once
+        assert_eq!(group_commands("0.5"), group_commands("1") + 4);
+    }
+}
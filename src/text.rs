@@ -4,6 +4,7 @@ use std::hash::{
     Hash,
     Hasher,
 };
+use std::ops::Range;
 use std::path::Path as FilePath;
 
 use fnv::{
@@ -20,6 +21,7 @@ use lru::LruCache;
 use unicode_bidi::BidiInfo;
 use unicode_segmentation::UnicodeSegmentation;
 
+use crate::paint::PaintFlavor;
 use crate::{
     Canvas,
     Color,
@@ -38,10 +40,46 @@ use crate::{
 mod atlas;
 pub use atlas::Atlas;
 
+mod color;
+use color::ColorGlyph;
+
+mod distance_field;
+
 mod font;
 use font::Font;
 pub use font::FontMetrics;
 
+mod font_db;
+use font_db::{
+    FaceInfo,
+    FaceSource,
+    FontDb,
+};
+pub use font_db::{
+    FontQuery,
+    FontStyle,
+};
+
+mod opentype;
+pub use opentype::{
+    FontFeature,
+    FontVariation,
+    TextLanguage,
+};
+
+mod paragraph;
+pub use paragraph::{
+    LineMetrics,
+    Paragraph,
+    ParagraphStyle,
+};
+
+mod rich_text;
+pub use rich_text::{
+    RichText,
+    TextSpan,
+};
+
 // This padding is an empty border around the glyph’s pixels but inside the
 // sampled area (texture coordinates) for the quad in render_atlas().
 const GLYPH_PADDING: u32 = 1;
@@ -52,6 +90,12 @@ const GLYPH_PADDING: u32 = 1;
 const GLYPH_MARGIN: u32 = 1;
 
 const TEXTURE_SIZE: usize = 512;
+// Glyph images larger than this are stored scaled down and stretched when drawn
+const MAX_BITMAP_GLYPH_SIZE: f32 = 256.0;
+// Glyphs in distance field atlases are rendered once at this size and scaled when drawn
+const DISTANCE_FIELD_SIZE: f32 = 48.0;
+// Distance in atlas pixels stored on each side of the outline
+const DISTANCE_FIELD_RANGE: f32 = 4.0;
 const LRU_CACHE_CAPACITY: usize = 1000;
 
 /// A font handle.
@@ -109,6 +153,69 @@ impl Default for RenderMode {
     }
 }
 
+/// How glyphs are rendered into the atlases text is drawn from: `Grayscale` (default), `Sdf`,
+/// `Msdf`, `SubpixelRgb` or `SubpixelBgr`.
+///
+/// Grayscale glyphs are rasterized for every font size and canvas scale, and text larger than
+/// 92 pixels is filled as paths. Distance field glyphs are rendered once and scaled, rotated
+/// and zoomed to any size without rasterizing them again, which suits canvases that are zoomed
+/// a lot. They look slightly softer than grayscale glyphs at small sizes. Strokes and color
+/// glyphs are always rendered like grayscale glyphs.
+///
+/// Subpixel glyphs use the red, green and blue stripes of LCD pixels as separate samples, which
+/// makes small text sharper on low density screens. The order has to match the screen. Their
+/// color channels are blended separately, which needs the `SourceOver` composite operation and
+/// text that isn't rotated, skewed or mirrored. Otherwise they are drawn as grayscale glyphs.
+///
+/// # Example
+/// ```
+/// use femtovg::{Color, GlyphRendering, Paint};
+///
+/// let mut paint = Paint::color(Color::black());
+/// paint.set_glyph_rendering(GlyphRendering::Msdf);
+/// ```
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum GlyphRendering {
+    /// Coverage masks rasterized at the size the text is drawn at.
+    Grayscale,
+    /// A signed distance field, which rounds sharp corners of large glyphs.
+    Sdf,
+    /// A multi-channel signed distance field, which keeps corners sharp at any size.
+    Msdf,
+    /// Coverage of each subpixel of screens with red on the left of the pixels.
+    SubpixelRgb,
+    /// Coverage of each subpixel of screens with blue on the left of the pixels.
+    SubpixelBgr,
+}
+
+impl Default for GlyphRendering {
+    fn default() -> Self {
+        Self::Grayscale
+    }
+}
+
+impl GlyphRendering {
+    pub(crate) fn is_distance_field(self) -> bool {
+        matches!(self, Self::Sdf | Self::Msdf)
+    }
+
+    pub(crate) fn is_subpixel(self) -> bool {
+        matches!(self, Self::SubpixelRgb | Self::SubpixelBgr)
+    }
+}
+
+// The atlases glyphs are rendered into
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub(crate) enum AtlasKind {
+    // Grayscale or subpixel coverage used as alpha mask
+    Mask,
+    // Premultiplied colors of color glyphs
+    Color,
+    // Signed distances to the outline
+    DistanceField,
+}
+
 #[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
 struct RenderedGlyphId {
     glyph_index: u32,
@@ -117,6 +224,10 @@ struct RenderedGlyphId {
     line_width: u32,
     render_mode: RenderMode,
     subpixel_location: u8,
+    font_variations: [Option<FontVariation>; 8],
+    color: bool,
+    foreground: Option<[u8; 4]>,
+    rendering: GlyphRendering,
 }
 
 impl RenderedGlyphId {
@@ -128,6 +239,10 @@ impl RenderedGlyphId {
             line_width: (paint.line_width * 10.0).trunc() as u32,
             render_mode: mode,
             subpixel_location,
+            font_variations: paint.font_variations,
+            color: false,
+            foreground: None,
+            rendering: GlyphRendering::Grayscale,
         }
     }
 }
@@ -141,6 +256,9 @@ struct RenderedGlyph {
     atlas_x: u32,
     atlas_y: u32,
     padding: u32,
+    atlas: AtlasKind,
+    // Size of the glyph on screen relative to its size in the atlas
+    scale: f32,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -172,6 +290,9 @@ struct ShapingId {
     size: u32,
     word_hash: u64,
     font_ids: [Option<FontId>; 8],
+    font_features: [Option<FontFeature>; 8],
+    font_variations: [Option<FontVariation>; 8],
+    language: Option<TextLanguage>,
 }
 
 impl ShapingId {
@@ -186,6 +307,9 @@ impl ShapingId {
             size: (paint.font_size * 10.0).trunc() as u32,
             word_hash: hasher.finish(),
             font_ids: paint.font_ids,
+            font_features: paint.font_features,
+            font_variations: paint.font_variations,
+            language: paint.language,
         }
     }
 }
@@ -200,9 +324,12 @@ struct FontTexture {
 
 pub(crate) struct TextContext {
     fonts: Arena<Font>,
+    font_db: FontDb,
     shaping_run_cache: ShapingRunCache<FnvBuildHasher>,
     shaped_words_cache: ShapedWordsCache<FnvBuildHasher>,
     textures: Vec<FontTexture>,
+    color_textures: Vec<FontTexture>,
+    distance_field_textures: Vec<FontTexture>,
     rendered_glyphs: FnvHashMap<RenderedGlyphId, RenderedGlyph>,
 }
 
@@ -213,9 +340,12 @@ impl Default for TextContext {
 
         Self {
             fonts: Default::default(),
+            font_db: Default::default(),
             shaping_run_cache: LruCache::with_hasher(LRU_CACHE_CAPACITY, fnv_run),
             shaped_words_cache: LruCache::with_hasher(LRU_CACHE_CAPACITY, fnv_words),
             textures: Default::default(),
+            color_textures: Default::default(),
+            distance_field_textures: Default::default(),
             rendered_glyphs: Default::default(),
         }
     }
@@ -245,18 +375,76 @@ impl TextContext {
     }
 
     pub fn add_font_file<T: AsRef<FilePath>>(&mut self, path: T) -> Result<FontId, ErrorKind> {
-        let data = std::fs::read(path)?;
+        let data = std::fs::read(path.as_ref())?;
+        let id = self.load_font(&data, 0)?;
 
-        self.add_font_mem(&data)
+        if let Some(face) = self.loaded_face(id) {
+            self.font_db.add_file_face(face, path.as_ref(), 0);
+        }
+
+        Ok(id)
     }
 
     pub fn add_font_mem(&mut self, data: &[u8]) -> Result<FontId, ErrorKind> {
+        let id = self.load_font(data, 0)?;
+
+        if let Some(face) = self.loaded_face(id) {
+            self.font_db.add_face(face);
+        }
+
+        Ok(id)
+    }
+
+    fn loaded_face(&self, id: FontId) -> Option<FaceInfo> {
+        self.fonts
+            .get(id.0)
+            .and_then(|font| FaceInfo::new(font.font_ref(), FaceSource::Loaded(id)))
+    }
+
+    fn load_font(&mut self, data: &[u8], index: u32) -> Result<FontId, ErrorKind> {
         self.clear_caches();
 
-        let font = Font::new(data)?;
+        let font = Font::new(data, index)?;
         Ok(FontId(self.fonts.insert(font)))
     }
 
+    pub fn add_font_instance(&mut self, id: FontId, variations: &[FontVariation]) -> Result<FontId, ErrorKind> {
+        let font = self.font(id).ok_or(ErrorKind::NoFontFound)?.instance(variations)?;
+
+        self.clear_caches();
+
+        Ok(FontId(self.fonts.insert(font)))
+    }
+
+    pub fn add_font_search_dir<T: AsRef<FilePath>>(&mut self, path: T) -> Result<(), ErrorKind> {
+        self.font_db.scan_dir(path.as_ref())
+    }
+
+    pub fn add_system_font_dirs(&mut self) {
+        self.font_db.scan_system_dirs();
+    }
+
+    pub fn font_families(&self) -> Vec<String> {
+        self.font_db.families()
+    }
+
+    // Font files found in the search directories are only loaded once a query matches them
+    pub fn find_font(&mut self, query: &FontQuery) -> Result<FontId, ErrorKind> {
+        let index = self.font_db.find(query).ok_or(ErrorKind::NoFontFound)?;
+
+        let (path, face_index) = match &self.font_db.faces_mut()[index].source {
+            FaceSource::Loaded(id) => return Ok(*id),
+            FaceSource::File { path, index } => (path.clone(), *index),
+        };
+
+        let data = std::fs::read(path)?;
+        let id = self.load_font(&data, face_index)?;
+
+        self.font_db.faces_mut()[index].source = FaceSource::Loaded(id);
+
+        Ok(id)
+    }
+
     pub fn font(&self, id: FontId) -> Option<&Font> {
         self.fonts.get(id.0)
     }
@@ -265,50 +453,54 @@ impl TextContext {
         self.fonts.get_mut(id.0)
     }
 
-    pub fn find_font<F, T>(&mut self, _text: &str, paint: &Paint, mut callback: F) -> Result<T, ErrorKind>
-    where
-        F: FnMut((FontId, &mut Font)) -> (bool, T),
-    {
-        // Try each font in the paint
-        for maybe_font_id in paint.font_ids.iter() {
-            if let Some(font_id) = maybe_font_id {
-                if let Some(font) = self.fonts.get_mut(font_id.0) {
-                    let (has_missing, result) = callback((*font_id, font));
-
-                    if !has_missing {
-                        return Ok(result);
-                    }
-                }
-            } else {
-                break;
+    // The fonts to shape with in order of preference, the paint's fonts followed by all other
+    // registered fonts
+    fn fallback_fonts(&self, paint: &Paint) -> Vec<FontId> {
+        let mut fonts: Vec<FontId> = paint
+            .font_ids
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|id| self.fonts.contains(id.0))
+            .collect();
+
+        for (index, _) in &self.fonts {
+            if !fonts.contains(&FontId(index)) {
+                fonts.push(FontId(index));
             }
         }
 
-        // Try each registered font
-        // An optimisation here would be to skip fonts that were tried by the paint
-        for (id, font) in &mut self.fonts {
-            let (has_missing, result) = callback((FontId(id), font));
+        fonts
+    }
 
-            if !has_missing {
-                return Ok(result);
-            }
-        }
+    fn clear_caches(&mut self) {
+        self.shaped_words_cache.clear();
+    }
 
-        // Just return the first font at this point and let it render .nodef glyphs
-        if let Some((id, font)) = self.fonts.iter_mut().next() {
-            return Ok(callback((FontId(id), font)).1);
+    fn textures(&self, atlas: AtlasKind) -> &Vec<FontTexture> {
+        match atlas {
+            AtlasKind::Mask => &self.textures,
+            AtlasKind::Color => &self.color_textures,
+            AtlasKind::DistanceField => &self.distance_field_textures,
         }
-
-        Err(ErrorKind::NoFontFound)
     }
 
-    fn clear_caches(&mut self) {
-        self.shaped_words_cache.clear();
+    fn textures_mut(&mut self, atlas: AtlasKind) -> &mut Vec<FontTexture> {
+        match atlas {
+            AtlasKind::Mask => &mut self.textures,
+            AtlasKind::Color => &mut self.color_textures,
+            AtlasKind::DistanceField => &mut self.distance_field_textures,
+        }
     }
 
     #[cfg(feature = "debug_inspector")]
     pub fn debug_inspector_get_textures(&self) -> Vec<ImageId> {
-        self.textures.iter().map(|t| t.image_id).collect()
+        self.textures
+            .iter()
+            .chain(&self.color_textures)
+            .chain(&self.distance_field_textures)
+            .map(|t| t.image_id)
+            .collect()
     }
 }
 
@@ -470,74 +662,221 @@ fn shape_word(
     context: &mut TextContext,
     paint: &Paint,
 ) -> Result<ShapedWord, ErrorKind> {
-    // find_font will call the closure with each font matching the provided style
-    // until a font capable of shaping the word is found
-    context.find_font(&word, paint, |(font_id, font)| {
-        // Call harfbuzz
-        let output = {
-            // TODO: It may be faster if this is created only once and stored inside the Font struct
-            let face = match rustybuzz::Face::from_slice(&font.data(), 0) {
-                Some(v) => v,
-                None => {
-                    eprintln!("Error: malformed font.");
-                    std::process::exit(1);
-                }
-            };
+    let fonts = context.fallback_fonts(paint);
 
-            let mut buffer = rustybuzz::UnicodeBuffer::new();
-            buffer.push_str(word);
-            buffer.set_direction(hb_direction);
+    if fonts.is_empty() {
+        return Err(ErrorKind::NoFontFound);
+    }
 
-            rustybuzz::shape(&face, &[], buffer)
-        };
+    let glyphs = shape_with_fallback(word, 0..word.len(), hb_direction, context, paint, &fonts)?;
 
-        let positions = output.glyph_positions();
-        let infos = output.glyph_infos();
+    let width = glyphs.iter().map(|glyph| glyph.advance_x + paint.letter_spacing).sum();
 
-        let mut shaped_word = ShapedWord {
-            glyphs: Vec::with_capacity(positions.len()),
-            width: 0.0,
-        };
+    Ok(ShapedWord { glyphs, width })
+}
 
-        let mut has_missing = false;
+// Shapes the range with the first font, then shapes grapheme clusters the font has no glyphs
+// for with the remaining fonts. Clusters no font has glyphs for keep the first font's .notdef
+// glyph.
+fn shape_with_fallback(
+    word: &str,
+    range: Range<usize>,
+    hb_direction: rustybuzz::Direction,
+    context: &mut TextContext,
+    paint: &Paint,
+    fonts: &[FontId],
+) -> Result<Vec<ShapedGlyph>, ErrorKind> {
+    let (font_id, fallback) = match fonts.split_first() {
+        Some(fonts) => fonts,
+        None => return Ok(Vec::new()),
+    };
 
-        for (position, (info, c)) in positions.iter().zip(infos.iter().zip(word.chars())) {
-            if info.codepoint == 0 {
-                has_missing = true;
-            }
+    let glyphs = shape_with_font(word, range.clone(), hb_direction, context, paint, *font_id)?;
+    let missing = missing_clusters(word, range, &glyphs);
 
-            let scale = font.scale(paint.font_size);
+    if missing.is_empty() || fallback.is_empty() {
+        return Ok(glyphs);
+    }
 
-            let mut g = ShapedGlyph {
-                x: 0.0,
-                y: 0.0,
-                c: c,
-                byte_index: info.cluster as usize,
-                font_id: font_id,
-                codepoint: info.codepoint,
-                width: 0.0,
-                height: 0.0,
-                advance_x: position.x_advance as f32 * scale,
-                advance_y: position.y_advance as f32 * scale,
-                offset_x: position.x_offset as f32 * scale,
-                offset_y: position.y_offset as f32 * scale,
-                bearing_x: 0.0,
-                bearing_y: 0.0,
-            };
+    let mut replacements = Vec::with_capacity(missing.len());
+
+    for missing_range in &missing {
+        // Skip fonts that don't even have the first character
+        let c = word[missing_range.clone()].chars().next();
+
+        let fonts: Vec<FontId> = fallback
+            .iter()
+            .copied()
+            .filter(|id| match (context.font(*id), c) {
+                (Some(font), Some(c)) => font.has_glyph(c),
+                _ => false,
+            })
+            .collect();
+
+        let shaped = shape_with_fallback(word, missing_range.clone(), hb_direction, context, paint, &fonts)?;
+
+        if shaped.is_empty() || shaped.iter().all(|glyph| glyph.codepoint == 0) {
+            replacements.push(None);
+        } else {
+            replacements.push(Some(shaped));
+        }
+    }
+
+    // Splice the fallback glyphs in where the first glyph of their cluster was
+    let mut result = Vec::with_capacity(glyphs.len());
+
+    for glyph in glyphs {
+        let replaced = missing
+            .iter()
+            .position(|missing_range| missing_range.contains(&glyph.byte_index));
+
+        match replaced.map(|index| &mut replacements[index]) {
+            Some(Some(shaped)) => result.append(shaped),
+            _ => result.push(glyph),
+        }
+    }
+
+    Ok(result)
+}
+
+// Byte ranges of the grapheme clusters with missing glyphs, in text order
+fn missing_clusters(word: &str, range: Range<usize>, glyphs: &[ShapedGlyph]) -> Vec<Range<usize>> {
+    let mut cluster_starts: Vec<usize> = glyphs.iter().map(|glyph| glyph.byte_index).collect();
+    cluster_starts.sort_unstable();
+    cluster_starts.dedup();
 
-            if let Some(glyph) = font.glyph(info.codepoint as u16) {
-                g.width = glyph.metrics.width * scale;
-                g.height = glyph.metrics.height * scale;
-                g.bearing_x = glyph.metrics.bearing_x * scale;
-                g.bearing_y = glyph.metrics.bearing_y * scale;
+    let cluster_end = |start: usize| match cluster_starts.binary_search(&start) {
+        Ok(index) => cluster_starts.get(index + 1).copied().unwrap_or(range.end),
+        Err(_) => range.end,
+    };
+
+    let boundaries: Vec<usize> = word[range.clone()]
+        .grapheme_indices(true)
+        .map(|(index, _)| range.start + index)
+        .chain(std::iter::once(range.end))
+        .collect();
+
+    let mut missing: Vec<Range<usize>> = glyphs
+        .iter()
+        .filter(|glyph| glyph.codepoint == 0)
+        .map(|glyph| {
+            // Emoji sequences and combining marks are replaced as a whole
+            let start = boundaries
+                .iter()
+                .rev()
+                .find(|boundary| **boundary <= glyph.byte_index)
+                .copied()
+                .unwrap_or(range.start);
+            let end = cluster_end(glyph.byte_index);
+            let end = boundaries
+                .iter()
+                .find(|boundary| **boundary >= end)
+                .copied()
+                .unwrap_or(range.end);
+
+            start..end
+        })
+        .collect();
+
+    missing.sort_unstable_by_key(|range| range.start);
+
+    // Neighbouring clusters are shaped together
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(missing.len());
+
+    for range in missing {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
+
+fn shape_with_font(
+    word: &str,
+    range: Range<usize>,
+    hb_direction: rustybuzz::Direction,
+    context: &mut TextContext,
+    paint: &Paint,
+    font_id: FontId,
+) -> Result<Vec<ShapedGlyph>, ErrorKind> {
+    let font = context.font_mut(font_id).ok_or(ErrorKind::NoFontFound)?;
+
+    // Call harfbuzz
+    let output = {
+        // TODO: It may be faster if this is created only once and stored inside the Font struct
+        let mut face = rustybuzz::Face::from_slice(font.data(), font.index()).ok_or(ErrorKind::FontParseError)?;
+
+        let variations: Vec<_> = font
+            .variations(&paint.font_variations)
+            .into_iter()
+            .map(FontVariation::to_rustybuzz)
+            .collect();
+        face.set_variations(&variations);
+
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str(&word[range.clone()]);
+        buffer.set_direction(hb_direction);
+
+        if let Some(language) = paint.language {
+            buffer.set_language(language.to_rustybuzz());
+
+            if let Some(script) = language.rustybuzz_script() {
+                buffer.set_script(script);
             }
+        }
+
+        let features: Vec<_> = paint.font_features().map(FontFeature::to_rustybuzz).collect();
+
+        rustybuzz::shape(&face, &features, buffer)
+    };
+
+    let positions = output.glyph_positions();
+    let infos = output.glyph_infos();
+
+    let scale = font.scale(paint.font_size);
+    let mut glyphs = Vec::with_capacity(positions.len());
+
+    for (position, info) in positions.iter().zip(infos.iter()) {
+        let byte_index = range.start + info.cluster as usize;
 
-            shaped_word.width += g.advance_x + paint.letter_spacing;
-            shaped_word.glyphs.push(g);
+        let mut g = ShapedGlyph {
+            x: 0.0,
+            y: 0.0,
+            c: word[byte_index..].chars().next().unwrap_or_default(),
+            byte_index,
+            font_id,
+            codepoint: info.codepoint,
+            width: 0.0,
+            height: 0.0,
+            advance_x: position.x_advance as f32 * scale,
+            advance_y: position.y_advance as f32 * scale,
+            offset_x: position.x_offset as f32 * scale,
+            offset_y: position.y_offset as f32 * scale,
+            bearing_x: 0.0,
+            bearing_y: 0.0,
+        };
+
+        // Color glyphs are drawn instead of the outlines, which emoji fonts often don't have
+        let metrics = match font.color_metrics(info.codepoint as u16, paint.font_size, &paint.font_variations) {
+            Some(metrics) => Some(metrics),
+            None => font
+                .glyph(info.codepoint as u16, &paint.font_variations)
+                .map(|glyph| glyph.metrics),
+        };
+
+        if let Some(metrics) = metrics {
+            g.width = metrics.width * scale;
+            g.height = metrics.height * scale;
+            g.bearing_x = metrics.bearing_x * scale;
+            g.bearing_y = metrics.bearing_y * scale;
         }
 
-        (has_missing, shaped_word)
-    })
+        glyphs.push(g);
+    }
+
+    Ok(glyphs)
 }
 
 // Calculates the x,y coordinates for each glyph based on their advances. Calculates total width and height of the shaped text run
@@ -591,6 +930,8 @@ fn layout(x: f32, y: f32, context: &mut TextContext, res: &mut TextMetrics, pain
 #[derive(Clone, Debug)]
 pub(crate) struct DrawCmd {
     pub image_id: ImageId,
+    /// The atlas of the quads, color atlases are drawn in their own colors instead of the paint's
+    pub atlas: AtlasKind,
     pub quads: Vec<Quad>,
 }
 
@@ -625,36 +966,90 @@ pub(crate) fn render_atlas<T: Renderer>(
     for glyph in &text_layout.glyphs {
         let subpixel_location = crate::geometry::quantize(glyph.x.fract(), 0.1) * 10.0;
 
-        let id = RenderedGlyphId::new(glyph.codepoint, glyph.font_id, paint, mode, subpixel_location as u8);
+        let mut id = RenderedGlyphId::new(glyph.codepoint, glyph.font_id, paint, mode, subpixel_location as u8);
+
+        // Color glyphs are drawn in their own colors, strokes use their outlines
+        let color_glyph = if mode == RenderMode::Fill {
+            let font = canvas
+                .text_context
+                .font_mut(glyph.font_id)
+                .ok_or(ErrorKind::NoFontFound)?;
+
+            font.color_glyph(glyph.codepoint as u16, paint.font_size)
+                .map(ColorGlyph::uses_foreground)
+        } else {
+            None
+        };
+
+        if let Some(uses_foreground) = color_glyph {
+            id.color = true;
+
+            if uses_foreground {
+                let color = foreground_color(paint);
+                id.foreground = Some([color.r, color.g, color.b, color.a].map(|c| (c * 255.0).round() as u8));
+            }
+        } else if uses_distance_field(paint, mode) {
+            // Distance fields are shared by all sizes and positions
+            id.size = 0;
+            id.line_width = 0;
+            id.subpixel_location = 0;
+            id.rendering = paint.glyph_rendering;
+        } else if paint.glyph_rendering.is_subpixel() {
+            id.rendering = paint.glyph_rendering;
+        }
 
         if !canvas.text_context.rendered_glyphs.contains_key(&id) {
-            let glyph = render_glyph(canvas, paint, mode, &glyph)?;
+            let rendered = if id.color {
+                render_color_glyph(canvas, paint, glyph)?
+            } else if id.rendering.is_distance_field() {
+                render_distance_field_glyph(canvas, paint, glyph)?
+            } else {
+                render_glyph(canvas, paint, mode, &glyph, id.rendering)?
+            };
 
-            canvas.text_context.rendered_glyphs.insert(id, glyph);
+            canvas.text_context.rendered_glyphs.insert(id, rendered);
         }
 
         let rendered = canvas.text_context.rendered_glyphs.get(&id).unwrap();
 
-        if let Some(texture) = canvas.text_context.textures.get(rendered.texture_index) {
+        if let Some(texture) = canvas.text_context.textures(rendered.atlas).get(rendered.texture_index) {
             let image_id = texture.image_id;
             let size = texture.atlas.size();
             let itw = 1.0 / size.0 as f32;
             let ith = 1.0 / size.1 as f32;
 
-            let cmd = cmd_map.entry(rendered.texture_index).or_insert_with(|| DrawCmd {
-                image_id,
-                quads: Vec::new(),
-            });
+            let cmd = cmd_map
+                .entry((rendered.atlas, rendered.texture_index))
+                .or_insert_with(|| DrawCmd {
+                    image_id,
+                    atlas: rendered.atlas,
+                    quads: Vec::new(),
+                });
 
             let mut q = Quad::default();
 
-            q.x0 = glyph.x.trunc() - line_width_offset - GLYPH_PADDING as f32;
-            q.y0 = (glyph.y + glyph.bearing_y).round()
-                - rendered.bearing_y as f32
-                - line_width_offset
-                - GLYPH_PADDING as f32;
-            q.x1 = q.x0 + rendered.width as f32;
-            q.y1 = q.y0 + rendered.height as f32;
+            let scale = if rendered.atlas == AtlasKind::DistanceField {
+                // Scaled glyphs are not snapped to pixels
+                let scale = paint.font_size / DISTANCE_FIELD_SIZE;
+
+                q.x0 = glyph.x - rendered.padding as f32 * scale;
+                q.y0 = glyph.y - rendered.padding as f32 * scale;
+
+                scale
+            } else {
+                let scale = rendered.scale;
+
+                q.x0 = glyph.x.trunc() - line_width_offset - GLYPH_PADDING as f32 * scale;
+                q.y0 = (glyph.y + glyph.bearing_y).round()
+                    - rendered.bearing_y as f32 * scale
+                    - line_width_offset
+                    - GLYPH_PADDING as f32 * scale;
+
+                scale
+            };
+
+            q.x1 = q.x0 + rendered.width as f32 * scale;
+            q.y1 = q.y0 + rendered.height as f32 * scale;
 
             q.s0 = rendered.atlas_x as f32 * itw;
             q.t0 = rendered.atlas_y as f32 * ith;
@@ -675,6 +1070,7 @@ fn render_glyph<T: Renderer>(
     paint: &Paint,
     mode: RenderMode,
     glyph: &ShapedGlyph,
+    rendering: GlyphRendering,
 ) -> Result<RenderedGlyph, ErrorKind> {
     let padding = GLYPH_PADDING + GLYPH_MARGIN;
 
@@ -686,10 +1082,14 @@ fn render_glyph<T: Renderer>(
 
     let line_width_offset = (line_width / 2.0).ceil();
 
-    let width = glyph.width.ceil() as u32 + (line_width_offset * 2.0) as u32 + padding * 2;
+    // The samples of subpixels reach a third of a pixel further
+    let subpixel_offset = if rendering.is_subpixel() { 1 } else { 0 };
+
+    let width = glyph.width.ceil() as u32 + (line_width_offset * 2.0) as u32 + padding * 2 + subpixel_offset;
     let height = glyph.height.ceil() as u32 + (line_width_offset * 2.0) as u32 + padding * 2;
 
-    let (dst_index, dst_image_id, (dst_x, dst_y)) = find_texture_or_alloc(canvas, width as usize, height as usize)?;
+    let (dst_index, dst_image_id, (dst_x, dst_y)) =
+        find_texture_or_alloc(canvas, width as usize, height as usize, AtlasKind::Mask)?;
 
     // render glyph to image
     canvas.save();
@@ -702,7 +1102,7 @@ fn render_glyph<T: Renderer>(
             .ok_or(ErrorKind::NoFontFound)?;
         let scale = font.scale(paint.font_size);
 
-        let path = if let Some(font_glyph) = font.glyph(glyph.codepoint as u16) {
+        let path = if let Some(font_glyph) = font.glyph(glyph.codepoint as u16, &paint.font_variations) {
             font_glyph.path.clone()
         } else {
             Path::new()
@@ -729,6 +1129,22 @@ fn render_glyph<T: Renderer>(
 
     let factor = 1.0 / 8.0;
 
+    // Each channel is sampled over a pixel around the center of its subpixel, which also filters
+    // the color fringes of the edges
+    let channels = match rendering {
+        GlyphRendering::SubpixelRgb => vec![
+            (Color::rgbf(factor, 0.0, 0.0), -1.0 / 3.0),
+            (Color::rgbf(0.0, factor, 0.0), 0.0),
+            (Color::rgbf(0.0, 0.0, factor), 1.0 / 3.0),
+        ],
+        GlyphRendering::SubpixelBgr => vec![
+            (Color::rgbf(0.0, 0.0, factor), -1.0 / 3.0),
+            (Color::rgbf(0.0, factor, 0.0), 0.0),
+            (Color::rgbf(factor, 0.0, 0.0), 1.0 / 3.0),
+        ],
+        _ => vec![(Color::rgbf(factor, factor, factor), 0.0)],
+    };
+
     let mut mask_paint = Paint::color(Color::rgbf(factor, factor, factor));
     mask_paint.set_fill_rule(FillRule::EvenOdd);
     mask_paint.set_anti_alias(false);
@@ -759,19 +1175,23 @@ fn render_glyph<T: Renderer>(
         (-5.0 / 16.0, 3.0 / 16.0),
     ];
 
-    for point in &points {
-        canvas.save();
-        canvas.translate(point.0, point.1);
+    for (color, offset) in channels {
+        mask_paint.set_color(color);
 
-        canvas.scale(scale, scale);
+        for point in &points {
+            canvas.save();
+            canvas.translate(point.0 + offset, point.1);
 
-        if mode == RenderMode::Stroke {
-            canvas.stroke_path(&mut path, mask_paint);
-        } else {
-            canvas.fill_path(&mut path, mask_paint);
-        }
+            canvas.scale(scale, scale);
 
-        canvas.restore();
+            if mode == RenderMode::Stroke {
+                canvas.stroke_path(&mut path, mask_paint);
+            } else {
+                canvas.fill_path(&mut path, mask_paint);
+            }
+
+            canvas.restore();
+        }
     }
 
     canvas.restore();
@@ -784,6 +1204,224 @@ fn render_glyph<T: Renderer>(
         atlas_y: dst_y as u32 + GLYPH_MARGIN,
         texture_index: dst_index,
         padding: padding - GLYPH_MARGIN,
+        atlas: AtlasKind::Mask,
+        scale: 1.0,
+    })
+}
+
+// The color of color glyph layers drawn with the text's paint
+fn foreground_color(paint: &Paint) -> Color {
+    match paint.flavor {
+        PaintFlavor::Color(color) => color,
+        _ => Color::black(),
+    }
+}
+
+fn render_color_glyph<T: Renderer>(
+    canvas: &mut Canvas<T>,
+    paint: &Paint,
+    glyph: &ShapedGlyph,
+) -> Result<RenderedGlyph, ErrorKind> {
+    let padding = GLYPH_PADDING + GLYPH_MARGIN;
+
+    let (color_glyph, scale) = {
+        let font = canvas
+            .text_context
+            .font_mut(glyph.font_id)
+            .ok_or(ErrorKind::NoFontFound)?;
+
+        let color_glyph = font
+            .color_glyph(glyph.codepoint as u16, paint.font_size)
+            .cloned()
+            .ok_or(ErrorKind::UnknownError)?;
+
+        (color_glyph, font.scale(paint.font_size))
+    };
+
+    match color_glyph {
+        ColorGlyph::Layers(layers) => {
+            let width = glyph.width.ceil() as u32 + padding * 2;
+            let height = glyph.height.ceil() as u32 + padding * 2;
+
+            let (dst_index, dst_image_id, (dst_x, dst_y)) =
+                find_texture_or_alloc(canvas, width as usize, height as usize, AtlasKind::Color)?;
+
+            let mut paths = Vec::with_capacity(layers.len());
+
+            {
+                let font = canvas
+                    .text_context
+                    .font_mut(glyph.font_id)
+                    .ok_or(ErrorKind::NoFontFound)?;
+
+                for layer in &layers {
+                    if let Some(font_glyph) = font.glyph(layer.glyph, &paint.font_variations) {
+                        let color = layer.color.unwrap_or_else(|| foreground_color(paint));
+                        paths.push((font_glyph.path.clone(), color));
+                    }
+                }
+            }
+
+            canvas.save();
+            canvas.reset();
+
+            let rendered_bearing_y = glyph.bearing_y.round();
+            let x_quant = crate::geometry::quantize(glyph.x.fract(), 0.1);
+            let x = dst_x as f32 - glyph.bearing_x + padding as f32 + x_quant;
+            let y = TEXTURE_SIZE as f32 - dst_y as f32 - rendered_bearing_y - padding as f32;
+
+            canvas.translate(x, y);
+
+            canvas.set_render_target(RenderTarget::Image(dst_image_id));
+            canvas.clear_rect(
+                dst_x as u32,
+                TEXTURE_SIZE as u32 - dst_y as u32 - height,
+                width,
+                height,
+                Color::rgbaf(0.0, 0.0, 0.0, 0.0),
+            );
+
+            canvas.scale(scale, scale);
+
+            // Layers are drawn bottom to top with normal blending, which leaves premultiplied colors
+            for (mut path, color) in paths {
+                let mut layer_paint = Paint::color(color);
+                layer_paint.set_fill_rule(FillRule::EvenOdd);
+
+                canvas.fill_path(&mut path, layer_paint);
+            }
+
+            canvas.restore();
+
+            Ok(RenderedGlyph {
+                width: width - 2 * GLYPH_MARGIN,
+                height: height - 2 * GLYPH_MARGIN,
+                bearing_y: rendered_bearing_y as i32,
+                atlas_x: dst_x as u32 + GLYPH_MARGIN,
+                atlas_y: dst_y as u32 + GLYPH_MARGIN,
+                texture_index: dst_index,
+                padding: padding - GLYPH_MARGIN,
+                atlas: AtlasKind::Color,
+                scale: 1.0,
+            })
+        }
+        ColorGlyph::Bitmap(bitmap) => {
+            let scale = (glyph.width.max(glyph.height) / MAX_BITMAP_GLYPH_SIZE).max(1.0);
+
+            let bitmap = color::resize_bitmap(
+                &bitmap,
+                (glyph.width / scale).ceil().max(1.0) as usize,
+                (glyph.height / scale).ceil().max(1.0) as usize,
+            );
+
+            let width = bitmap.width + padding as usize * 2;
+            let height = bitmap.height + padding as usize * 2;
+
+            let (dst_index, dst_image_id, (dst_x, dst_y)) =
+                find_texture_or_alloc(canvas, width, height, AtlasKind::Color)?;
+
+            // Images are uploaded directly, with transparent padding around them
+            let mut pixels = vec![rgb::RGBA8::new(0, 0, 0, 0); width * height];
+
+            for (row, src) in bitmap.pixels.chunks(bitmap.width).enumerate() {
+                let start = (row + padding as usize) * width + padding as usize;
+                pixels[start..start + bitmap.width].copy_from_slice(src);
+            }
+
+            canvas.update_image(
+                dst_image_id,
+                imgref::Img::new(pixels.as_slice(), width, height),
+                dst_x,
+                dst_y,
+            )?;
+
+            Ok(RenderedGlyph {
+                width: width as u32 - 2 * GLYPH_MARGIN,
+                height: height as u32 - 2 * GLYPH_MARGIN,
+                bearing_y: (glyph.bearing_y / scale).round() as i32,
+                atlas_x: dst_x as u32 + GLYPH_MARGIN,
+                atlas_y: dst_y as u32 + GLYPH_MARGIN,
+                texture_index: dst_index,
+                padding: padding - GLYPH_MARGIN,
+                atlas: AtlasKind::Color,
+                scale,
+            })
+        }
+    }
+}
+
+pub(crate) fn uses_distance_field(paint: &Paint, mode: RenderMode) -> bool {
+    mode == RenderMode::Fill && paint.glyph_rendering.is_distance_field()
+}
+
+// The distance in text layout pixels between the 0 and 1 values of the distance field of a glyph
+pub(crate) fn distance_field_range(font_size: f32) -> f32 {
+    2.0 * DISTANCE_FIELD_RANGE * font_size / DISTANCE_FIELD_SIZE
+}
+
+fn render_distance_field_glyph<T: Renderer>(
+    canvas: &mut Canvas<T>,
+    paint: &Paint,
+    glyph: &ShapedGlyph,
+) -> Result<RenderedGlyph, ErrorKind> {
+    let padding = DISTANCE_FIELD_RANGE as u32 + GLYPH_MARGIN;
+
+    let (path, metrics, scale) = {
+        let font = canvas
+            .text_context
+            .font_mut(glyph.font_id)
+            .ok_or(ErrorKind::NoFontFound)?;
+        let scale = font.scale(DISTANCE_FIELD_SIZE);
+
+        match font.glyph(glyph.codepoint as u16, &paint.font_variations) {
+            Some(font_glyph) => (font_glyph.path.clone(), Some(font_glyph.metrics), scale),
+            None => (Path::new(), None, scale),
+        }
+    };
+
+    let (glyph_width, glyph_height, bearing_x, bearing_y) = metrics.map_or((0.0, 0.0, 0.0, 0.0), |metrics| {
+        (
+            metrics.width * scale,
+            metrics.height * scale,
+            metrics.bearing_x * scale,
+            metrics.bearing_y * scale,
+        )
+    });
+
+    let width = glyph_width.ceil() as usize + padding as usize * 2;
+    let height = glyph_height.ceil() as usize + padding as usize * 2;
+
+    let (dst_index, dst_image_id, (dst_x, dst_y)) =
+        find_texture_or_alloc(canvas, width, height, AtlasKind::DistanceField)?;
+
+    // The top left corner of the glyph box is at the padding
+    let pixels = distance_field::generate(
+        &path,
+        scale,
+        (padding as f32 - bearing_x, padding as f32 + bearing_y),
+        width,
+        height,
+        DISTANCE_FIELD_RANGE,
+        paint.glyph_rendering == GlyphRendering::Msdf,
+    );
+
+    canvas.update_image(
+        dst_image_id,
+        imgref::Img::new(pixels.as_slice(), width, height),
+        dst_x,
+        dst_y,
+    )?;
+
+    Ok(RenderedGlyph {
+        width: width as u32 - 2 * GLYPH_MARGIN,
+        height: height as u32 - 2 * GLYPH_MARGIN,
+        bearing_y: 0,
+        atlas_x: dst_x as u32 + GLYPH_MARGIN,
+        atlas_y: dst_y as u32 + GLYPH_MARGIN,
+        texture_index: dst_index,
+        padding: padding - GLYPH_MARGIN,
+        atlas: AtlasKind::DistanceField,
+        scale: 1.0,
     })
 }
 
@@ -792,9 +1430,10 @@ fn find_texture_or_alloc<T: Renderer>(
     canvas: &mut Canvas<T>,
     width: usize,
     height: usize,
+    kind: AtlasKind,
 ) -> Result<(usize, ImageId, (usize, usize)), ErrorKind> {
     // Find a free location in one of the atlases
-    let mut textures = canvas.text_context.textures.iter_mut().enumerate();
+    let mut textures = canvas.text_context.textures_mut(kind).iter_mut().enumerate();
     let mut texture_search_result = textures.find_map(|(index, texture)| {
         texture
             .atlas
@@ -814,7 +1453,14 @@ fn find_texture_or_alloc<T: Renderer>(
         // We keep Rgba8 for now because it might be useful for sub-pixel
         // anti-aliasing (ClearType®), and the atlas debug display is much
         // clearer with different colors.
-        let info = ImageInfo::new(ImageFlags::empty(), atlas.size().0, atlas.size().1, PixelFormat::Rgba8);
+        // Color atlases hold premultiplied colors, like the ones rendered into them
+        let flags = if kind == AtlasKind::Color {
+            ImageFlags::PREMULTIPLIED
+        } else {
+            ImageFlags::empty()
+        };
+
+        let info = ImageInfo::new(flags, atlas.size().0, atlas.size().1, PixelFormat::Rgba8);
         let image_id = canvas.images.alloc(&mut canvas.renderer, info)?;
 
         #[cfg(feature = "debug_inspector")]
@@ -835,29 +1481,34 @@ fn find_texture_or_alloc<T: Renderer>(
             }
         }
 
-        canvas.text_context.textures.push(FontTexture { atlas, image_id });
+        let textures = canvas.text_context.textures_mut(kind);
+
+        textures.push(FontTexture { atlas, image_id });
 
-        let index = canvas.text_context.textures.len() - 1;
+        let index = textures.len() - 1;
         texture_search_result = Some((index, image_id, loc));
     }
 
     texture_search_result.ok_or(ErrorKind::UnknownError)
 }
 
+// Draws the glyph outlines as paths, returning the draw commands for glyph images and distance
+// fields which still come from the atlases
 pub(crate) fn render_direct<T: Renderer>(
     canvas: &mut Canvas<T>,
     text_layout: &TextMetrics,
     paint: &Paint,
     mode: RenderMode,
     invscale: f32,
-) -> Result<(), ErrorKind> {
+) -> Result<Vec<DrawCmd>, ErrorKind> {
     let mut paint = *paint;
     paint.set_fill_rule(FillRule::EvenOdd);
 
     let mut scaled = false;
+    let mut atlas_glyphs = TextMetrics::default();
 
     for glyph in &text_layout.glyphs {
-        let (mut path, scale) = {
+        let (paths, scale) = {
             let font = canvas
                 .text_context
                 .font_mut(glyph.font_id)
@@ -865,13 +1516,28 @@ pub(crate) fn render_direct<T: Renderer>(
 
             let scale = font.scale(paint.font_size);
 
-            let path = if let Some(font_glyph) = font.glyph(glyph.codepoint as u16) {
-                font_glyph.path.clone()
-            } else {
-                continue;
+            // Layers with no color are drawn with the text's paint
+            let layers = match font.color_glyph(glyph.codepoint as u16, paint.font_size) {
+                Some(ColorGlyph::Layers(layers)) if mode == RenderMode::Fill => layers.clone(),
+                Some(ColorGlyph::Bitmap(_)) if mode == RenderMode::Fill => {
+                    atlas_glyphs.glyphs.push(*glyph);
+                    continue;
+                }
+                _ => vec![color::ColorLayer {
+                    glyph: glyph.codepoint as u16,
+                    color: None,
+                }],
             };
 
-            (path, scale)
+            let mut paths = Vec::with_capacity(layers.len());
+
+            for layer in layers {
+                if let Some(font_glyph) = font.glyph(layer.glyph, &paint.font_variations) {
+                    paths.push((font_glyph.path.clone(), layer.color));
+                }
+            }
+
+            (paths, scale)
         };
 
         canvas.save();
@@ -887,14 +1553,177 @@ pub(crate) fn render_direct<T: Renderer>(
         );
         canvas.scale(scale * invscale, -scale * invscale);
 
-        if mode == RenderMode::Stroke {
-            canvas.stroke_path(&mut path, paint);
-        } else {
-            canvas.fill_path(&mut path, paint);
+        for (mut path, color) in paths {
+            let mut layer_paint = paint;
+
+            if let Some(color) = color {
+                layer_paint.flavor = PaintFlavor::Color(color);
+            }
+
+            if mode == RenderMode::Stroke {
+                canvas.stroke_path(&mut path, layer_paint);
+            } else {
+                canvas.fill_path(&mut path, layer_paint);
+            }
         }
 
         canvas.restore();
     }
 
-    Ok(())
+    if atlas_glyphs.glyphs.is_empty() {
+        Ok(Vec::new())
+    } else {
+        render_atlas(canvas, &atlas_glyphs, &paint, mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{
+        renderer::Recorder,
+        CompositeOperation,
+    };
+
+    #[test]
+    fn large_distance_field_text() {
+        let text = "The quick brown fox jumps over the lazy dog";
+
+        // Large distance field text is still drawn from the atlas, one quad per glyph in a single
+        // command, while large grayscale text is filled as paths
+        let recorder = Recorder::default();
+        let mut canvas = Canvas::new(recorder.clone()).unwrap();
+
+        let font = canvas.add_font("examples/assets/Roboto-Regular.ttf").unwrap();
+        let glyph_count = text.chars().count();
+
+        let mut paint = Paint::color(Color::black());
+        paint.set_font(&[font]);
+        paint.set_font_size(200.0);
+
+        for &rendering in &[GlyphRendering::Sdf, GlyphRendering::Msdf] {
+            paint.set_glyph_rendering(rendering);
+
+            canvas.set_size(1000, 1000, 1.0);
+            canvas.save();
+            canvas.scale(3.0, 3.0);
+            canvas.fill_text(10.0, 50.0, text, paint).unwrap();
+            canvas.restore();
+            canvas.flush(None);
+
+            // set_size records a render target command first
+            let recording = recorder.0.borrow();
+            assert_eq!(recording.commands, 2);
+            assert_eq!(recording.verts.len(), glyph_count * 6);
+        }
+
+        paint.set_glyph_rendering(GlyphRendering::Grayscale);
+        canvas.set_size(1000, 1000, 1.0);
+        canvas.fill_text(10.0, 50.0, text, paint).unwrap();
+        canvas.flush(None);
+        assert!(recorder.0.borrow().commands > 2);
+    }
+
+    #[test]
+    fn subpixel_glyphs() {
+        let text = "Sharp small text";
+
+        let recorder = Recorder::default();
+        let mut canvas = Canvas::new(recorder.clone()).unwrap();
+        canvas.set_size(200, 200, 1.0);
+
+        let font = canvas.add_font("examples/assets/Roboto-Regular.ttf").unwrap();
+
+        let mut paint = Paint::color(Color::black());
+        paint.set_font(&[font]);
+        paint.set_font_size(12.0);
+
+        let metrics = canvas.measure_text(10.0, 20.0, text, paint).unwrap();
+
+        // Draws the text in two frames and returns the command count of the second one, which finds
+        // its glyphs in the atlas. Grayscale text is one command, subpixel text is blended in two.
+        let mut draw = |paint: Paint, angle: f32, operation: CompositeOperation| {
+            for _ in 0..2 {
+                canvas.set_size(200, 200, 1.0);
+                canvas.save();
+                canvas.rotate(angle);
+                canvas.global_composite_operation(operation);
+                canvas.fill_text(10.0, 60.0, text, paint).unwrap();
+                canvas.stroke_text(10.0, 80.0, text, paint).unwrap();
+                canvas.restore();
+                canvas.flush(None);
+            }
+
+            recorder.0.borrow().commands
+        };
+
+        let grayscale = draw(paint, 0.0, CompositeOperation::SourceOver);
+
+        for &rendering in &[GlyphRendering::SubpixelRgb, GlyphRendering::SubpixelBgr] {
+            paint.set_glyph_rendering(rendering);
+
+            // Both the filled and the stroked text take an extra command
+            assert_eq!(draw(paint, 0.0, CompositeOperation::SourceOver), grayscale + 2);
+
+            // Rotated text and other composite operations fall back to grayscale glyphs
+            assert_eq!(draw(paint, 0.3, CompositeOperation::SourceOver), grayscale);
+            assert_eq!(draw(paint, 0.0, CompositeOperation::Lighter), grayscale);
+        }
+
+        assert_eq!(
+            canvas.measure_text(10.0, 20.0, text, paint).unwrap().width(),
+            metrics.width()
+        );
+    }
+
+    #[test]
+    fn subpixel_channel_order() {
+        // The leftmost subpixel is sampled first, into the red channel for RGB screens and into
+        // the blue channel for BGR screens, each channel a third of a pixel right of the previous one
+        for &(rendering, order) in &[
+            (GlyphRendering::SubpixelRgb, [0, 1, 2]),
+            (GlyphRendering::SubpixelBgr, [2, 1, 0]),
+        ] {
+            let recorder = Recorder::default();
+            let mut canvas = Canvas::new(recorder.clone()).unwrap();
+            canvas.set_size(100, 100, 1.0);
+
+            let font = canvas.add_font("examples/assets/Roboto-Regular.ttf").unwrap();
+
+            let mut paint = Paint::color(Color::black());
+            paint.set_font(&[font]);
+            paint.set_font_size(12.0);
+            paint.set_glyph_rendering(rendering);
+
+            canvas.fill_text(10.0, 20.0, "l", paint).unwrap();
+            canvas.flush(None);
+
+            let channel = |color: [f32; 4]| {
+                (0..3)
+                    .max_by(|a, b| color[*a].partial_cmp(&color[*b]).unwrap())
+                    .unwrap()
+            };
+
+            let fills = recorder.0.borrow().fills.clone();
+            let mut channels: Vec<_> = fills.iter().map(|(color, _)| channel(*color)).collect();
+            channels.dedup();
+            assert_eq!(channels, order);
+
+            // The sample points of a channel are centered on its subpixel
+            let mean_x = |channel: usize| {
+                let xs: Vec<f32> = fills
+                    .iter()
+                    .filter(|(color, _)| color[channel] > 0.0)
+                    .map(|(_, x)| *x)
+                    .collect();
+                xs.iter().sum::<f32>() / xs.len() as f32
+            };
+
+            for pair in order.windows(2) {
+                let offset = mean_x(pair[1]) - mean_x(pair[0]);
+                assert!((offset - 1.0 / 3.0).abs() < 0.01, "{:?} offset {}", rendering, offset);
+            }
+        }
+    }
 }
@@ -0,0 +1,240 @@
+use std::convert::TryInto;
+
+use owned_ttf_parser::Font as TtfFont;
+
+use crate::Color;
+
+// Palette index of layers drawn with the color of the text
+const FOREGROUND_PALETTE_INDEX: u16 = 0xFFFF;
+
+/// One outline of a layered color glyph.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct ColorLayer {
+    pub glyph: u16,
+    /// `None` for layers drawn with the paint of the text
+    pub color: Option<Color>,
+}
+
+/// A decoded glyph image from the `sbix` or `CBDT` table.
+#[derive(Clone, Debug)]
+pub(crate) struct BitmapGlyph {
+    pub width: usize,
+    pub height: usize,
+    /// Premultiplied RGBA pixels, top row first
+    pub pixels: Vec<rgb::RGBA8>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum ColorGlyph {
+    Layers(Vec<ColorLayer>),
+    Bitmap(BitmapGlyph),
+}
+
+impl ColorGlyph {
+    pub fn uses_foreground(&self) -> bool {
+        match self {
+            Self::Layers(layers) => layers.iter().any(|layer| layer.color.is_none()),
+            Self::Bitmap(_) => false,
+        }
+    }
+}
+
+/// The color tables of a font.
+///
+/// Only version 0 of the `COLR` table, solid colored layers, is supported. The paint graphs of
+/// version 1 are ignored, glyphs that only have those are drawn as outlines. Bitmap glyphs are PNG
+/// images, which are decoded with the `image` crate and ignored without the `png-glyphs` feature.
+#[derive(Copy, Clone, Default)]
+pub(crate) struct ColorTables {
+    colr: Option<(usize, usize)>,
+    cpal: Option<(usize, usize)>,
+    bitmaps: bool,
+}
+
+impl ColorTables {
+    pub fn new(data: &[u8], index: u32) -> Self {
+        let colr = find_table(data, index, b"COLR");
+        let cpal = find_table(data, index, b"CPAL");
+        let bitmaps = cfg!(feature = "png-glyphs")
+            && (find_table(data, index, b"sbix").is_some()
+                || (find_table(data, index, b"CBDT").is_some() && find_table(data, index, b"CBLC").is_some()));
+
+        // Layers need both the glyphs and the palette
+        Self {
+            colr: cpal.and(colr),
+            cpal: colr.and(cpal),
+            bitmaps,
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        self.colr.is_some() || self.bitmaps
+    }
+
+    pub fn has_bitmaps(&self) -> bool {
+        self.bitmaps
+    }
+
+    /// The layers of a glyph from the `COLR` table, colored with the first `CPAL` palette.
+    pub fn layers(&self, data: &[u8], glyph: u16) -> Option<Vec<ColorLayer>> {
+        let colr = table_slice(data, self.colr?)?;
+        let cpal = table_slice(data, self.cpal?)?;
+
+        let base_count = read_u16(colr, 2)? as usize;
+        let base_offset = read_u32(colr, 4)? as usize;
+        let layer_offset = read_u32(colr, 8)? as usize;
+
+        // Base glyph records are sorted by glyph id
+        let (mut low, mut high) = (0, base_count);
+        let mut base = None;
+
+        while low < high {
+            let mid = (low + high) / 2;
+            let record = base_offset + mid * 6;
+
+            match read_u16(colr, record)?.cmp(&glyph) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => {
+                    base = Some((
+                        read_u16(colr, record + 2)? as usize,
+                        read_u16(colr, record + 4)? as usize,
+                    ));
+                    break;
+                }
+            }
+        }
+
+        let (first_layer, layer_count) = base?;
+
+        let entry_count = read_u16(cpal, 2)?;
+        let records_offset = read_u32(cpal, 8)? as usize;
+        let first_record = read_u16(cpal, 12)? as usize;
+
+        (first_layer..first_layer + layer_count)
+            .map(|layer| {
+                let record = layer_offset + layer * 4;
+                let palette_index = read_u16(colr, record + 2)?;
+
+                let color = if palette_index == FOREGROUND_PALETTE_INDEX || palette_index >= entry_count {
+                    None
+                } else {
+                    let offset = records_offset + (first_record + palette_index as usize) * 4;
+                    let bgra = cpal.get(offset..offset + 4)?;
+
+                    Some(Color::rgba(bgra[2], bgra[1], bgra[0], bgra[3]))
+                };
+
+                Some(ColorLayer {
+                    glyph: read_u16(colr, record)?,
+                    color,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Decodes the glyph image from the strike closest to `pixels_per_em`.
+#[cfg(feature = "png-glyphs")]
+pub(crate) fn decode_bitmap(font: &TtfFont, glyph: u16, pixels_per_em: u16) -> Option<BitmapGlyph> {
+    let raster = font.glyph_raster_image(owned_ttf_parser::GlyphId(glyph), pixels_per_em)?;
+    let image = ::image::load_from_memory(raster.data).ok()?.to_rgba8();
+
+    let pixels = image
+        .pixels()
+        .map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+            let premultiply = |c: u8| ((c as u16 * a as u16 + 127) / 255) as u8;
+
+            rgb::RGBA8::new(premultiply(r), premultiply(g), premultiply(b), a)
+        })
+        .collect();
+
+    Some(BitmapGlyph {
+        width: image.width() as usize,
+        height: image.height() as usize,
+        pixels,
+    })
+}
+
+#[cfg(not(feature = "png-glyphs"))]
+pub(crate) fn decode_bitmap(_font: &TtfFont, _glyph: u16, _pixels_per_em: u16) -> Option<BitmapGlyph> {
+    None
+}
+
+/// Scales a bitmap to the given size with bilinear filtering.
+pub(crate) fn resize_bitmap(bitmap: &BitmapGlyph, width: usize, height: usize) -> BitmapGlyph {
+    if (width, height) == (bitmap.width, bitmap.height) || bitmap.width == 0 || bitmap.height == 0 {
+        return bitmap.clone();
+    }
+
+    let sample = |x: usize, y: usize| bitmap.pixels[y.min(bitmap.height - 1) * bitmap.width + x.min(bitmap.width - 1)];
+    let mut pixels = Vec::with_capacity(width * height);
+
+    for y in 0..height {
+        let src_y = ((y as f32 + 0.5) * bitmap.height as f32 / height as f32 - 0.5).max(0.0);
+        let (y0, fy) = (src_y as usize, src_y.fract());
+
+        for x in 0..width {
+            let src_x = ((x as f32 + 0.5) * bitmap.width as f32 / width as f32 - 0.5).max(0.0);
+            let (x0, fx) = (src_x as usize, src_x.fract());
+
+            let corners = [
+                (sample(x0, y0), (1.0 - fx) * (1.0 - fy)),
+                (sample(x0 + 1, y0), fx * (1.0 - fy)),
+                (sample(x0, y0 + 1), (1.0 - fx) * fy),
+                (sample(x0 + 1, y0 + 1), fx * fy),
+            ];
+
+            let channel = |get: fn(rgb::RGBA8) -> u8| {
+                let value: f32 = corners.iter().map(|(pixel, weight)| get(*pixel) as f32 * weight).sum();
+                value.round() as u8
+            };
+
+            pixels.push(rgb::RGBA8::new(
+                channel(|p| p.r),
+                channel(|p| p.g),
+                channel(|p| p.b),
+                channel(|p| p.a),
+            ));
+        }
+    }
+
+    BitmapGlyph { width, height, pixels }
+}
+
+// Returns the offset and length of a table, looking into the right face of font collections
+fn find_table(data: &[u8], index: u32, tag: &[u8; 4]) -> Option<(usize, usize)> {
+    let offset = if data.get(0..4)? == b"ttcf" {
+        read_u32(data, 12 + index as usize * 4)? as usize
+    } else {
+        0
+    };
+
+    let table_count = read_u16(data, offset + 4)? as usize;
+
+    (0..table_count).find_map(|table| {
+        let record = offset + 12 + table * 16;
+
+        if data.get(record..record + 4)? == tag {
+            Some((
+                read_u32(data, record + 8)? as usize,
+                read_u32(data, record + 12)? as usize,
+            ))
+        } else {
+            None
+        }
+    })
+}
+
+fn table_slice(data: &[u8], (offset, len): (usize, usize)) -> Option<&[u8]> {
+    data.get(offset..offset.checked_add(len)?)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
@@ -0,0 +1,425 @@
+use crate::path::Verb;
+use crate::Path;
+
+// Channels of the edge colors
+const RED: u8 = 1;
+const GREEN: u8 = 2;
+const BLUE: u8 = 4;
+
+const CYAN: u8 = GREEN | BLUE;
+const MAGENTA: u8 = RED | BLUE;
+const YELLOW: u8 = RED | GREEN;
+const WHITE: u8 = RED | GREEN | BLUE;
+
+// Sine of the smallest angle between two segments which makes a corner
+const CORNER_THRESHOLD: f32 = 0.14;
+
+// Longest line used to approximate a curve, in pixels
+const FLATTEN_LENGTH: f32 = 1.5;
+
+type Point = (f32, f32);
+
+/// A line of a glyph outline.
+#[derive(Copy, Clone, Debug)]
+struct Segment {
+    a: Point,
+    b: Point,
+    /// Channels the segment takes part in
+    color: u8,
+    /// The segment starts an edge, so points before it use the distance to its line
+    first: bool,
+    /// The segment ends an edge, so points after it use the distance to its line
+    last: bool,
+}
+
+// The nearest segment of a channel
+#[derive(Copy, Clone)]
+struct Nearest {
+    distance: f32,
+    orthogonality: f32,
+    segment: usize,
+    t: f32,
+}
+
+impl Nearest {
+    fn closer_than(&self, other: &Self) -> bool {
+        if (self.distance - other.distance).abs() > 1e-4 {
+            self.distance < other.distance
+        } else {
+            self.orthogonality > other.orthogonality
+        }
+    }
+}
+
+/// Generates the distance field of a glyph outline.
+///
+/// The outline is mapped to pixels with `x * scale + origin.0` and `origin.1 - y * scale`, since
+/// font units point up. Distances of `range` pixels and more are clamped, the edge of the glyph
+/// is at 0.5. Multi-channel fields keep the distances to differently colored edges in the red,
+/// green and blue channels so their median reconstructs sharp corners, single channel fields
+/// store the same distance in all of them. Alpha always has the true distance.
+pub(crate) fn generate(
+    path: &Path,
+    scale: f32,
+    origin: Point,
+    width: usize,
+    height: usize,
+    range: f32,
+    multi_channel: bool,
+) -> Vec<rgb::RGBA8> {
+    let segments = segments(path, scale, origin, multi_channel);
+
+    // Which side of the segments is inside depends on the direction of the outer contours
+    let area: f32 = segments.iter().map(|s| s.a.0 * s.b.1 - s.b.0 * s.a.1).sum();
+    let orientation = if area < 0.0 { -1.0 } else { 1.0 };
+
+    let encode = |distance: f32| ((0.5 + distance / (2.0 * range)).clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    let mut pixels = Vec::with_capacity(width * height);
+
+    for y in 0..height {
+        for x in 0..width {
+            let p = (x as f32 + 0.5, y as f32 + 0.5);
+
+            let mut nearest: [Option<Nearest>; 3] = [None; 3];
+            let mut min_distance = f32::MAX;
+            let mut winding = 0;
+
+            for (index, segment) in segments.iter().enumerate() {
+                let candidate = nearest_point(segment, p, index);
+                min_distance = min_distance.min(candidate.distance);
+                winding += crossing(segment, p);
+
+                for (channel, slot) in nearest.iter_mut().enumerate() {
+                    let closer = match slot {
+                        Some(nearest) => candidate.closer_than(nearest),
+                        None => true,
+                    };
+
+                    if segment.color & (1 << channel) != 0 && closer {
+                        *slot = Some(candidate);
+                    }
+                }
+            }
+
+            // Outlines are filled with the nonzero rule
+            let true_distance = if winding != 0 { min_distance } else { -min_distance };
+
+            if !multi_channel {
+                let value = encode(true_distance);
+                pixels.push(rgb::RGBA8::new(value, value, value, value));
+                continue;
+            }
+
+            let mut channels = [true_distance; 3];
+
+            for (channel, slot) in nearest.iter().enumerate() {
+                if let Some(nearest) = slot {
+                    channels[channel] = signed_distance(&segments[nearest.segment], nearest, p) * orientation;
+                }
+            }
+
+            let [r, g, b] = channels;
+            let median = r.min(g).max(r.max(g).min(b));
+
+            // Where the channels disagree with the outline the single channel distance is used,
+            // overlapping contours and tiny edges would otherwise leave holes and spikes
+            if (median > 0.0) != (true_distance > 0.0) {
+                channels = [true_distance; 3];
+            }
+
+            pixels.push(rgb::RGBA8::new(
+                encode(channels[0]),
+                encode(channels[1]),
+                encode(channels[2]),
+                encode(true_distance),
+            ));
+        }
+    }
+
+    pixels
+}
+
+fn nearest_point(segment: &Segment, p: Point, index: usize) -> Nearest {
+    let d = sub(segment.b, segment.a);
+    let ap = sub(p, segment.a);
+    let t = dot(ap, d) / dot(d, d);
+
+    let q = add(segment.a, mul(d, t.clamp(0.0, 1.0)));
+    let qp = sub(p, q);
+    let distance = length(qp);
+
+    // Of segments meeting at the nearest point, the one facing the point decides the side
+    let orthogonality = if t > 0.0 && t < 1.0 || distance == 0.0 {
+        1.0
+    } else {
+        cross(normalize(d), mul(qp, 1.0 / distance)).abs()
+    };
+
+    Nearest {
+        distance,
+        orthogonality,
+        segment: index,
+        t,
+    }
+}
+
+// Positive distances are left of the segment
+fn signed_distance(segment: &Segment, nearest: &Nearest, p: Point) -> f32 {
+    let direction = normalize(sub(segment.b, segment.a));
+
+    let pseudo = if nearest.t < 0.0 && segment.first {
+        Some(cross(direction, sub(p, segment.a)))
+    } else if nearest.t > 1.0 && segment.last {
+        Some(cross(direction, sub(p, segment.b)))
+    } else {
+        None
+    };
+
+    match pseudo {
+        Some(pseudo) if pseudo.abs() <= nearest.distance => pseudo,
+        _ => {
+            let side = cross(direction, sub(p, segment.a));
+            nearest.distance.copysign(side)
+        }
+    }
+}
+
+// Winding of the segment around the point, counted on a ray to the right
+fn crossing(segment: &Segment, p: Point) -> i32 {
+    let (a, b) = (segment.a, segment.b);
+    let side = cross(sub(b, a), sub(p, a));
+
+    if a.1 <= p.1 && b.1 > p.1 && side > 0.0 {
+        1
+    } else if a.1 > p.1 && b.1 <= p.1 && side < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
+
+// Flattens the outline into colored segments
+fn segments(path: &Path, scale: f32, origin: Point, multi_channel: bool) -> Vec<Segment> {
+    let map = |x: f32, y: f32| (x * scale + origin.0, origin.1 - y * scale);
+
+    // Points of each contour, flagged if they are ends of the original lines and curves
+    let mut contours: Vec<Vec<(Point, bool)>> = Vec::new();
+    let mut current: Vec<(Point, bool)> = Vec::new();
+    let mut last = (0.0, 0.0);
+
+    for verb in path.verbs() {
+        match verb {
+            Verb::MoveTo(x, y) => {
+                contours.push(std::mem::take(&mut current));
+                last = map(x, y);
+                current.push((last, true));
+            }
+            Verb::LineTo(x, y) => {
+                last = map(x, y);
+                current.push((last, true));
+            }
+            Verb::BezierTo(c1x, c1y, c2x, c2y, x, y) => {
+                let (p0, p1, p2, p3) = (last, map(c1x, c1y), map(c2x, c2y), map(x, y));
+                let hull = length(sub(p1, p0)) + length(sub(p2, p1)) + length(sub(p3, p2));
+                let steps = (hull / FLATTEN_LENGTH).ceil().clamp(1.0, 64.0) as usize;
+
+                for step in 1..=steps {
+                    let t = step as f32 / steps as f32;
+                    let mt = 1.0 - t;
+
+                    let point = add(
+                        add(mul(p0, mt * mt * mt), mul(p1, 3.0 * mt * mt * t)),
+                        add(mul(p2, 3.0 * mt * t * t), mul(p3, t * t * t)),
+                    );
+
+                    current.push((point, step == steps));
+                }
+
+                last = p3;
+            }
+            Verb::Close => contours.push(std::mem::take(&mut current)),
+            Verb::Solid | Verb::Hole => {}
+        }
+    }
+
+    contours.push(current);
+
+    let mut segments = Vec::new();
+
+    for mut points in contours {
+        // Contours are closed, the first point may be repeated at the end
+        points.dedup_by(|b, a| {
+            let same = length(sub(a.0, b.0)) < 1e-4;
+            a.1 |= same && b.1;
+            same
+        });
+
+        while points.len() > 1 && length(sub(points[0].0, points[points.len() - 1].0)) < 1e-4 {
+            let (_, corner) = points.pop().unwrap_or_default();
+            points[0].1 |= corner;
+        }
+
+        if points.len() < 2 {
+            continue;
+        }
+
+        let count = points.len();
+        let direction = |i: usize| normalize(sub(points[(i + 1) % count].0, points[i].0));
+
+        let corners: Vec<usize> = (0..count)
+            .filter(|&i| {
+                let (incoming, outgoing) = (direction((i + count - 1) % count), direction(i));
+                points[i].1 && (dot(incoming, outgoing) <= 0.0 || cross(incoming, outgoing).abs() > CORNER_THRESHOLD)
+            })
+            .collect();
+
+        let mut contour: Vec<Segment> = (0..count)
+            .map(|i| Segment {
+                a: points[i].0,
+                b: points[(i + 1) % count].0,
+                color: WHITE,
+                first: false,
+                last: false,
+            })
+            .collect();
+
+        if multi_channel {
+            color_edges(&mut contour, &corners);
+        }
+
+        segments.extend(contour);
+    }
+
+    segments
+}
+
+// Colors the edges between corners so neighbouring edges share only one channel
+fn color_edges(contour: &mut [Segment], corners: &[usize]) {
+    let count = contour.len();
+
+    let (starts, colors): (Vec<usize>, Vec<u8>) = match corners.len() {
+        // Smooth contours have no corners to keep
+        0 => return,
+        1 if count < 3 => return,
+        // A teardrop is split in three edges
+        1 => {
+            let start = corners[0];
+            let third = (count / 3).max(1);
+
+            (
+                vec![start, (start + third) % count, (start + 2 * third) % count],
+                vec![MAGENTA, WHITE, YELLOW],
+            )
+        }
+        edges => {
+            let mut colors: Vec<u8> = (0..edges).map(|i| [CYAN, MAGENTA, YELLOW][i % 3]).collect();
+
+            // The last edge also meets the first one
+            if colors[edges - 1] == colors[0] {
+                colors[edges - 1] = MAGENTA;
+            }
+
+            (corners.to_vec(), colors)
+        }
+    };
+
+    for (edge, (&start, &color)) in starts.iter().zip(&colors).enumerate() {
+        let end = starts[(edge + 1) % starts.len()];
+        let len = (end + count - start - 1) % count + 1;
+
+        for i in 0..len {
+            let segment = &mut contour[(start + i) % count];
+            segment.color = color;
+            segment.first = i == 0;
+            segment.last = i == len - 1;
+        }
+    }
+}
+
+fn add(a: Point, b: Point) -> Point {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn mul(a: Point, s: f32) -> Point {
+    (a.0 * s, a.1 * s)
+}
+
+fn dot(a: Point, b: Point) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn cross(a: Point, b: Point) -> f32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn length(a: Point) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn normalize(a: Point) -> Point {
+    let len = length(a);
+
+    if len == 0.0 {
+        (0.0, 0.0)
+    } else {
+        mul(a, 1.0 / len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn field(path: &Path, multi_channel: bool) -> Vec<rgb::RGBA8> {
+        generate(path, 1.0, (0.0, 20.0), 20, 20, 4.0, multi_channel)
+    }
+
+    fn median(pixel: rgb::RGBA8) -> u8 {
+        pixel.r.min(pixel.g).max(pixel.r.max(pixel.g).min(pixel.b))
+    }
+
+    #[test]
+    fn square_distance_field() {
+        // A square from (5, 5) to (15, 15) in pixels, drawn in both directions
+        for &clockwise in &[false, true] {
+            let mut path = Path::new();
+            path.move_to(5.0, 5.0);
+
+            if clockwise {
+                path.line_to(5.0, 15.0);
+                path.line_to(15.0, 15.0);
+                path.line_to(15.0, 5.0);
+            } else {
+                path.line_to(15.0, 5.0);
+                path.line_to(15.0, 15.0);
+                path.line_to(5.0, 15.0);
+            }
+
+            path.close();
+
+            for &multi_channel in &[false, true] {
+                let pixels = field(&path, multi_channel);
+                let at = |x: usize, y: usize| median(pixels[y * 20 + x]);
+
+                // Deep inside and far outside are clamped
+                assert_eq!(at(10, 10), 255);
+                assert_eq!(at(0, 0), 0);
+
+                // Half a pixel from the edge
+                assert!(at(5, 10) > 128 && at(5, 10) < 160);
+                assert!(at(4, 10) > 96 && at(4, 10) < 128);
+
+                // Outside of the corner the channels keep it sharp, the true distance rounds it
+                let corner = pixels[3 * 20 + 16];
+                assert!(median(corner) < 128);
+                assert!(corner.a < median(corner) || !multi_channel);
+            }
+        }
+    }
+}
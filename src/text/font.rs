@@ -6,11 +6,21 @@ use owned_ttf_parser::{
     OwnedFont,
 };
 
+use super::color::{
+    self,
+    ColorGlyph,
+    ColorTables,
+};
+use super::FontVariation;
 use crate::{
     ErrorKind,
     Path,
 };
 
+// Variation axis values set on a paint
+pub(crate) type Variations = [Option<FontVariation>; 8];
+
+#[derive(Copy, Clone)]
 pub struct GlyphMetrics {
     pub width: f32,
     pub height: f32,
@@ -35,6 +45,7 @@ pub struct FontMetrics {
     bold: bool,
     oblique: bool,
     variable: bool,
+    color: bool,
     weight: u16,
     width: u16,
 }
@@ -80,6 +91,11 @@ impl FontMetrics {
         self.variable
     }
 
+    /// Returns true if the font has color glyphs, as layers or images
+    pub fn color(&self) -> bool {
+        self.color
+    }
+
     pub fn weight(&self) -> u16 {
         self.weight
     }
@@ -91,22 +107,45 @@ impl FontMetrics {
 
 pub(crate) struct Font {
     data: Vec<u8>,
+    index: u32,
     owned_ttf_font: OwnedFont,
     units_per_em: u16,
     metrics: FontMetrics,
-    glyphs: FnvHashMap<u16, Glyph>,
+    variations: Vec<FontVariation>,
+    glyphs: FnvHashMap<(u16, Variations), Glyph>,
+    color_tables: ColorTables,
+    color_glyphs: FnvHashMap<(u16, u16), Option<ColorGlyph>>,
 }
 
 impl Font {
-    pub fn new(data: &[u8]) -> Result<Self, ErrorKind> {
-        let owned_ttf_font = OwnedFont::from_vec(data.to_owned(), 0).ok_or(ErrorKind::FontParseError)?;
+    pub fn new(data: &[u8], index: u32) -> Result<Self, ErrorKind> {
+        Self::with_variations(data, index, Vec::new())
+    }
+
+    /// Creates a named instance of a variable font, with the variations of this font followed by
+    /// the new ones
+    pub fn instance(&self, variations: &[FontVariation]) -> Result<Self, ErrorKind> {
+        let mut all = self.variations.clone();
+        all.extend_from_slice(variations);
+
+        Self::with_variations(&self.data, self.index, all)
+    }
+
+    fn with_variations(data: &[u8], index: u32, variations: Vec<FontVariation>) -> Result<Self, ErrorKind> {
+        let owned_ttf_font = OwnedFont::from_vec(data.to_owned(), index).ok_or(ErrorKind::FontParseError)?;
 
         let units_per_em = owned_ttf_font
             .as_font()
             .units_per_em()
             .ok_or(ErrorKind::FontInfoExtracionError)?;
 
-        let ttf_font = owned_ttf_font.as_font();
+        let mut ttf_font = owned_ttf_font.as_font().clone();
+
+        for variation in &variations {
+            ttf_font.set_variation(variation.ttf_tag(), variation.value());
+        }
+
+        let color_tables = ColorTables::new(data, index);
 
         let metrics = FontMetrics {
             ascender: ttf_font.ascender() as f32,
@@ -117,16 +156,21 @@ impl Font {
             bold: ttf_font.is_bold(),
             oblique: ttf_font.is_oblique(),
             variable: ttf_font.is_variable(),
-            weight: ttf_font.width().to_number(),
-            width: ttf_font.weight().to_number(),
+            color: color_tables.any(),
+            weight: ttf_font.weight().to_number(),
+            width: ttf_font.width().to_number(),
         };
 
         Ok(Self {
             data: data.to_owned(),
+            index,
             owned_ttf_font,
             units_per_em,
             metrics,
+            variations,
             glyphs: Default::default(),
+            color_tables,
+            color_glyphs: Default::default(),
         })
     }
 
@@ -134,10 +178,34 @@ impl Font {
         self.data.as_ref()
     }
 
-    fn font_ref(&self) -> &TtfFont<'_> {
+    /// Index of the face in a font collection, 0 for single fonts
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn font_ref(&self) -> &TtfFont<'_> {
         self.owned_ttf_font.as_font()
     }
 
+    /// The variations of the font instance, followed by the ones of the paint
+    pub fn variations(&self, paint_variations: &Variations) -> Vec<FontVariation> {
+        self.variations
+            .iter()
+            .chain(paint_variations.iter().flatten())
+            .copied()
+            .collect()
+    }
+
+    fn varied_font_ref(&self, paint_variations: &Variations) -> TtfFont<'_> {
+        let mut font = self.font_ref().clone();
+
+        for variation in self.variations(paint_variations) {
+            font.set_variation(variation.ttf_tag(), variation.value());
+        }
+
+        font
+    }
+
     pub fn metrics(&self, size: f32) -> FontMetrics {
         let mut metrics = self.metrics;
 
@@ -146,19 +214,98 @@ impl Font {
         metrics
     }
 
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.font_ref().glyph_index(c).is_some()
+    }
+
+    /// Metrics of the color version of a glyph in font units: the image closest to the font size
+    /// or the box around all color layers
+    pub fn color_metrics(&mut self, codepoint: u16, size: f32, variations: &Variations) -> Option<GlyphMetrics> {
+        if self.color_tables.has_bitmaps() {
+            if let Some(raster) = self
+                .font_ref()
+                .glyph_raster_image(GlyphId(codepoint), size.ceil() as u16)
+            {
+                let units = self.units_per_em as f32 / raster.pixels_per_em as f32;
+
+                return Some(GlyphMetrics {
+                    width: raster.width as f32 * units,
+                    height: raster.height as f32 * units,
+                    bearing_x: raster.x as f32 * units,
+                    bearing_y: (raster.y as f32 + raster.height as f32) * units,
+                });
+            }
+        }
+
+        let layers = self.color_tables.layers(&self.data, codepoint)?;
+
+        let (mut min_x, mut min_y) = (f32::MAX, f32::MAX);
+        let (mut max_x, mut max_y) = (f32::MIN, f32::MIN);
+
+        for layer in layers {
+            if let Some(glyph) = self.glyph(layer.glyph, variations) {
+                let metrics = glyph.metrics;
+
+                min_x = min_x.min(metrics.bearing_x);
+                max_x = max_x.max(metrics.bearing_x + metrics.width);
+                min_y = min_y.min(metrics.bearing_y - metrics.height);
+                max_y = max_y.max(metrics.bearing_y);
+            }
+        }
+
+        if min_x > max_x {
+            return None;
+        }
+
+        Some(GlyphMetrics {
+            width: max_x - min_x,
+            height: max_y - min_y,
+            bearing_x: min_x,
+            bearing_y: max_y,
+        })
+    }
+
+    /// The color layers or the image of a glyph, with images from the strike closest to the size
+    pub fn color_glyph(&mut self, codepoint: u16, size: f32) -> Option<&ColorGlyph> {
+        if !self.color_tables.any() {
+            return None;
+        }
+
+        // Layers don't depend on the size
+        let strike = self
+            .font_ref()
+            .glyph_raster_image(GlyphId(codepoint), size.ceil() as u16)
+            .map_or(0, |raster| raster.pixels_per_em);
+
+        let key = (codepoint, strike);
+
+        if !self.color_glyphs.contains_key(&key) {
+            let glyph = match self.color_tables.layers(&self.data, codepoint) {
+                Some(layers) => Some(ColorGlyph::Layers(layers)),
+                None => color::decode_bitmap(self.font_ref(), codepoint, strike).map(ColorGlyph::Bitmap),
+            };
+
+            self.color_glyphs.insert(key, glyph);
+        }
+
+        self.color_glyphs.get(&key).and_then(Option::as_ref)
+    }
+
     pub fn scale(&self, size: f32) -> f32 {
         size / self.units_per_em as f32
     }
 
-    pub fn glyph(&mut self, codepoint: u16) -> Option<&mut Glyph> {
-        if !self.glyphs.contains_key(&codepoint) {
+    pub fn glyph(&mut self, codepoint: u16, variations: &Variations) -> Option<&mut Glyph> {
+        let key = (codepoint, *variations);
+
+        if !self.glyphs.contains_key(&key) {
             let mut path = Path::new();
 
             let id = GlyphId(codepoint);
 
-            if let Some(bbox) = self.font_ref().outline_glyph(id, &mut path) {
+            if let Some(bbox) = self.varied_font_ref(variations).outline_glyph(id, &mut path) {
                 self.glyphs.insert(
-                    codepoint,
+                    key,
                     Glyph {
                         path,
                         metrics: GlyphMetrics {
@@ -172,6 +319,6 @@ impl Font {
             }
         }
 
-        self.glyphs.get_mut(&codepoint)
+        self.glyphs.get_mut(&key)
     }
 }
@@ -0,0 +1,571 @@
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs::{
+    self,
+    File,
+};
+use std::io::{
+    Read,
+    Seek,
+    SeekFrom,
+};
+use std::path::{
+    Path as FilePath,
+    PathBuf,
+};
+use std::str::FromStr;
+
+use owned_ttf_parser::Font as TtfFont;
+
+use super::FontId;
+use crate::ErrorKind;
+
+// Families tried for the generic CSS family names, most common on Linux first
+const SANS_SERIF: &[&str] = &[
+    "Noto Sans",
+    "DejaVu Sans",
+    "Liberation Sans",
+    "Roboto",
+    "Helvetica",
+    "Arial",
+];
+const SERIF: &[&str] = &["Noto Serif", "DejaVu Serif", "Liberation Serif", "Times New Roman"];
+const MONOSPACE: &[&str] = &[
+    "Noto Sans Mono",
+    "DejaVu Sans Mono",
+    "Liberation Mono",
+    "Menlo",
+    "Consolas",
+    "Courier New",
+];
+
+// The tables the parser needs to accept a face and the ones the names and styles are read from
+const INDEX_TABLES: [&[u8; 4]; 5] = [b"head", b"hhea", b"maxp", b"name", b"OS/2"];
+
+/// Font slant: `Normal` (default), `Italic`, `Oblique`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum FontStyle {
+    /// Upright glyphs.
+    Normal,
+    /// Glyphs designed as italics.
+    Italic,
+    /// Slanted upright glyphs.
+    Oblique,
+}
+
+impl Default for FontStyle {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+/// A request for a font by family, weight, width and style, resolved with
+/// [`Canvas::find_font`](struct.Canvas.html#method.find_font).
+///
+/// Queries can be parsed from a comma separated list of family names and keywords, the
+/// keywords being CSS font weights (`thin` to `black` or a number), widths (`condensed`,
+/// `expanded` and their variants) and styles (`italic`, `oblique`). The generic families
+/// `sans-serif`, `serif` and `monospace` map to common fonts.
+///
+/// # Example
+/// ```
+/// use femtovg::{FontQuery, FontStyle};
+///
+/// let query: FontQuery = "Inter, \"Noto Sans\", sans-serif, bold, italic".parse().unwrap();
+///
+/// assert_eq!(query.families(), ["Inter", "Noto Sans", "sans-serif"]);
+/// assert_eq!(query.weight(), 700);
+/// assert_eq!(query.style(), FontStyle::Italic);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct FontQuery {
+    families: Vec<String>,
+    weight: u16,
+    width: u16,
+    style: FontStyle,
+}
+
+impl FontQuery {
+    /// Creates a query for the regular face of a family.
+    pub fn new<S: Into<String>>(family: S) -> Self {
+        Self {
+            families: vec![family.into()],
+            weight: 400,
+            width: 5,
+            style: FontStyle::Normal,
+        }
+    }
+
+    /// The families in order of preference.
+    pub fn families(&self) -> &[String] {
+        &self.families
+    }
+
+    /// Adds a family that is used if none of the previous ones are found.
+    pub fn add_family<S: Into<String>>(&mut self, family: S) {
+        self.families.push(family.into());
+    }
+
+    /// The weight, from 100 (thin) over 400 (regular) and 700 (bold) to 900 (black).
+    pub fn weight(&self) -> u16 {
+        self.weight
+    }
+
+    /// Sets the weight, from 100 (thin) over 400 (regular) and 700 (bold) to 900 (black).
+    pub fn set_weight(&mut self, weight: u16) {
+        self.weight = weight;
+    }
+
+    /// The width, from 1 (ultra condensed) over 5 (normal) to 9 (ultra expanded).
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Sets the width, from 1 (ultra condensed) over 5 (normal) to 9 (ultra expanded).
+    pub fn set_width(&mut self, width: u16) {
+        self.width = width;
+    }
+
+    /// The slant.
+    pub fn style(&self) -> FontStyle {
+        self.style
+    }
+
+    /// Sets the slant.
+    pub fn set_style(&mut self, style: FontStyle) {
+        self.style = style;
+    }
+}
+
+impl FromStr for FontQuery {
+    type Err = ErrorKind;
+
+    fn from_str(text: &str) -> Result<Self, ErrorKind> {
+        let mut query = Self {
+            families: Vec::new(),
+            ..Self::new("")
+        };
+
+        for token in text.split(',').map(str::trim).filter(|token| !token.is_empty()) {
+            let keyword = token.to_ascii_lowercase();
+
+            let weight = match keyword.as_str() {
+                "thin" | "hairline" => Some(100),
+                "extra-light" | "ultra-light" => Some(200),
+                "light" => Some(300),
+                "normal" | "regular" => Some(400),
+                "medium" => Some(500),
+                "semi-bold" | "demi-bold" => Some(600),
+                "bold" => Some(700),
+                "extra-bold" | "ultra-bold" => Some(800),
+                "black" | "heavy" => Some(900),
+                _ => keyword.parse().ok().filter(|weight| (1..=1000).contains(weight)),
+            };
+
+            let width = match keyword.as_str() {
+                "ultra-condensed" => Some(1),
+                "extra-condensed" => Some(2),
+                "condensed" => Some(3),
+                "semi-condensed" => Some(4),
+                "semi-expanded" => Some(6),
+                "expanded" => Some(7),
+                "extra-expanded" => Some(8),
+                "ultra-expanded" => Some(9),
+                _ => None,
+            };
+
+            let style = match keyword.as_str() {
+                "italic" => Some(FontStyle::Italic),
+                "oblique" => Some(FontStyle::Oblique),
+                _ => None,
+            };
+
+            if let Some(weight) = weight {
+                query.weight = weight;
+            } else if let Some(width) = width {
+                query.width = width;
+            } else if let Some(style) = style {
+                query.style = style;
+            } else {
+                query
+                    .families
+                    .push(token.trim_matches(|c| c == '"' || c == '\'').to_string());
+            }
+        }
+
+        if query.families.is_empty() {
+            return Err(ErrorKind::GeneralError("font query without a family".to_string()));
+        }
+
+        Ok(query)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum FaceSource {
+    File { path: PathBuf, index: u32 },
+    Loaded(FontId),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct FaceInfo {
+    pub family: String,
+    pub weight: u16,
+    pub width: u16,
+    pub style: FontStyle,
+    pub source: FaceSource,
+}
+
+impl FaceInfo {
+    pub fn new(font: &TtfFont, source: FaceSource) -> Option<Self> {
+        let style = if font.is_italic() {
+            FontStyle::Italic
+        } else if font.is_oblique() {
+            FontStyle::Oblique
+        } else {
+            FontStyle::Normal
+        };
+
+        Some(Self {
+            family: font.family_name()?,
+            weight: font.weight().to_number(),
+            width: font.width().to_number(),
+            style,
+            source,
+        })
+    }
+}
+
+/// Index of the faces of loaded fonts and of font files that are loaded once they are matched.
+#[derive(Default)]
+pub(crate) struct FontDb {
+    faces: Vec<FaceInfo>,
+    // Canonical path and face index of every face read from a file, loaded or not
+    files: HashSet<(PathBuf, u32)>,
+}
+
+impl FontDb {
+    pub fn add_face(&mut self, face: FaceInfo) {
+        self.faces.push(face);
+    }
+
+    /// Adds the face of a font loaded from a file, which directory scans then skip.
+    pub fn add_file_face(&mut self, face: FaceInfo, path: &FilePath, index: u32) {
+        self.files.insert((canonical(path), index));
+        self.faces.push(face);
+    }
+
+    /// Indexes the font files in a directory and its subdirectories.
+    pub fn scan_dir(&mut self, path: &FilePath) -> Result<(), ErrorKind> {
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                // Unreadable subdirectories are common in system font directories
+                let _ = self.scan_dir(&path);
+                continue;
+            }
+
+            let extension = path.extension().and_then(OsStr::to_str).map(str::to_ascii_lowercase);
+
+            if let Some("ttf") | Some("otf") | Some("ttc") | Some("otc") = extension.as_deref() {
+                self.scan_file(&path);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn scan_file(&mut self, path: &FilePath) {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return,
+        };
+
+        let canonical = canonical(path);
+
+        for index in 0..faces_in_file(&mut file).unwrap_or(0) {
+            // Already indexed through another directory or added to the canvas
+            if self.files.contains(&(canonical.clone(), index)) {
+                continue;
+            }
+
+            let source = FaceSource::File {
+                path: path.to_path_buf(),
+                index,
+            };
+
+            if let Some(face) = read_index_tables(&mut file, index)
+                .and_then(|data| TtfFont::from_data(&data, 0).and_then(|font| FaceInfo::new(&font, source)))
+            {
+                self.files.insert((canonical.clone(), index));
+                self.faces.push(face);
+            }
+        }
+    }
+
+    /// Indexes the standard font directories of the platform, ignoring the ones that don't exist.
+    pub fn scan_system_dirs(&mut self) {
+        for dir in system_font_dirs() {
+            if dir.is_dir() {
+                let _ = self.scan_dir(&dir);
+            }
+        }
+    }
+
+    pub fn faces_mut(&mut self) -> &mut [FaceInfo] {
+        &mut self.faces
+    }
+
+    pub fn families(&self) -> Vec<String> {
+        let mut families: Vec<String> = self.faces.iter().map(|face| face.family.clone()).collect();
+        families.sort_unstable();
+        families.dedup();
+        families
+    }
+
+    /// Returns the index of the face matching the query best, following the CSS font matching
+    /// rules: the first family with any faces is used, then the closest width, style and
+    /// weight are picked in that order.
+    pub fn find(&self, query: &FontQuery) -> Option<usize> {
+        let candidates = query
+            .families
+            .iter()
+            .flat_map(|family| expand_generic(family))
+            .map(|family| {
+                self.faces
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, face)| face.family.eq_ignore_ascii_case(&family))
+                    .map(|(index, _)| index)
+                    .collect::<Vec<_>>()
+            })
+            .find(|faces| !faces.is_empty())?;
+
+        let width = best(&candidates, |index| width_rank(query.width, self.faces[index].width));
+        let candidates: Vec<_> = candidates
+            .into_iter()
+            .filter(|index| self.faces[*index].width == self.faces[width].width)
+            .collect();
+
+        let style = best(&candidates, |index| style_rank(query.style, self.faces[index].style));
+        let candidates: Vec<_> = candidates
+            .into_iter()
+            .filter(|index| self.faces[*index].style == self.faces[style].style)
+            .collect();
+
+        Some(best(&candidates, |index| {
+            weight_rank(query.weight, self.faces[index].weight)
+        }))
+    }
+}
+
+fn canonical(path: &FilePath) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*data.get(offset)?, *data.get(offset + 1)?]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes([
+        *data.get(offset)?,
+        *data.get(offset + 1)?,
+        *data.get(offset + 2)?,
+        *data.get(offset + 3)?,
+    ]))
+}
+
+fn read_at(file: &mut File, offset: u32, len: usize) -> Option<Vec<u8>> {
+    let mut data = vec![0; len];
+    file.seek(SeekFrom::Start(offset.into())).ok()?;
+    file.read_exact(&mut data).ok()?;
+    Some(data)
+}
+
+// The number of faces in a font file or collection
+fn faces_in_file(file: &mut File) -> Option<u32> {
+    let header = read_at(file, 0, 12)?;
+
+    if &header[0..4] == b"ttcf" {
+        read_u32(&header, 8)
+    } else {
+        Some(1)
+    }
+}
+
+// Copies the tables needed for indexing a face of a font file or collection into a font of
+// their own, so that only the table directories and a few small tables are read from the file
+fn read_index_tables(file: &mut File, index: u32) -> Option<Vec<u8>> {
+    let header = read_at(file, 0, 12)?;
+
+    let offset = if &header[0..4] == b"ttcf" {
+        read_u32(&read_at(file, 12 + 4 * index, 4)?, 0)?
+    } else {
+        0
+    };
+
+    let header = read_at(file, offset, 12)?;
+    let num_tables = read_u16(&header, 4)? as usize;
+    let records = read_at(file, offset + 12, num_tables * 16)?;
+
+    let mut tables = Vec::new();
+
+    for record in records.chunks_exact(16) {
+        if INDEX_TABLES.iter().any(|tag| tag[..] == record[0..4]) {
+            let data = read_at(file, read_u32(record, 8)?, read_u32(record, 12)? as usize)?;
+            tables.push((&record[0..4], data));
+        }
+    }
+
+    // The search fields of the header aren't used by the parser
+    let mut font = header[0..4].to_vec();
+    font.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+    font.extend_from_slice(&[0; 6]);
+
+    let mut table_offset = 12 + tables.len() * 16;
+
+    for (tag, data) in &tables {
+        font.extend_from_slice(tag);
+        font.extend_from_slice(&[0; 4]);
+        font.extend_from_slice(&(table_offset as u32).to_be_bytes());
+        font.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        table_offset += (data.len() + 3) & !3;
+    }
+
+    for (_, data) in &tables {
+        font.extend_from_slice(data);
+        font.resize((font.len() + 3) & !3, 0);
+    }
+
+    Some(font)
+}
+
+fn expand_generic(family: &str) -> Vec<String> {
+    let generic = match family.to_ascii_lowercase().as_str() {
+        "sans-serif" => SANS_SERIF,
+        "serif" => SERIF,
+        "monospace" => MONOSPACE,
+        _ => return vec![family.to_string()],
+    };
+
+    generic.iter().map(|family| family.to_string()).collect()
+}
+
+// The candidate with the lowest rank, the first of them if several rank the same
+fn best<F: Fn(usize) -> (u16, u16)>(candidates: &[usize], rank: F) -> usize {
+    candidates.iter().copied().min_by_key(|index| rank(*index)).unwrap_or(0)
+}
+
+// Narrower widths are preferred for condensed and normal queries, wider ones otherwise
+fn width_rank(desired: u16, width: u16) -> (u16, u16) {
+    let narrower_first = desired <= 5;
+
+    match width {
+        width if width == desired => (0, 0),
+        width if (width < desired) == narrower_first => (1, desired.max(width) - desired.min(width)),
+        width => (2, desired.max(width) - desired.min(width)),
+    }
+}
+
+fn style_rank(desired: FontStyle, style: FontStyle) -> (u16, u16) {
+    let order = match desired {
+        FontStyle::Normal => [FontStyle::Normal, FontStyle::Oblique, FontStyle::Italic],
+        FontStyle::Italic => [FontStyle::Italic, FontStyle::Oblique, FontStyle::Normal],
+        FontStyle::Oblique => [FontStyle::Oblique, FontStyle::Italic, FontStyle::Normal],
+    };
+
+    (order.iter().position(|other| *other == style).unwrap_or(3) as u16, 0)
+}
+
+// Weights between 400 and 500 look up to 500 first, then lighter, then heavier. Lighter weights
+// prefer lighter faces and heavier weights heavier faces.
+fn weight_rank(desired: u16, weight: u16) -> (u16, u16) {
+    let distance = desired.max(weight) - desired.min(weight);
+
+    if weight == desired {
+        (0, 0)
+    } else if (400..=500).contains(&desired) {
+        if weight > desired && weight <= 500 {
+            (1, distance)
+        } else if weight < desired {
+            (2, distance)
+        } else {
+            (3, distance)
+        }
+    } else if (desired < 400) == (weight < desired) {
+        (1, distance)
+    } else {
+        (2, distance)
+    }
+}
+
+fn system_font_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+
+    if cfg!(target_os = "macos") {
+        dirs.push(PathBuf::from("/System/Library/Fonts"));
+        dirs.push(PathBuf::from("/Library/Fonts"));
+        dirs.extend(home.map(|home| home.join("Library/Fonts")));
+    } else if cfg!(target_os = "windows") {
+        let windows = std::env::var_os("WINDIR").map_or_else(|| PathBuf::from("C:\\Windows"), PathBuf::from);
+        dirs.push(windows.join("Fonts"));
+    } else {
+        dirs.push(PathBuf::from("/usr/share/fonts"));
+        dirs.push(PathBuf::from("/usr/local/share/fonts"));
+
+        match std::env::var_os("XDG_DATA_HOME") {
+            Some(data) => dirs.push(PathBuf::from(data).join("fonts")),
+            None => dirs.extend(home.as_ref().map(|home| home.join(".local/share/fonts"))),
+        }
+
+        dirs.extend(home.map(|home| home.join(".fonts")));
+    }
+
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_tables_match_the_full_font() {
+        let path = FilePath::new("examples/assets/Roboto-Bold.ttf");
+        let full = fs::read(path).unwrap();
+        let full = TtfFont::from_data(&full, 0).unwrap();
+
+        let mut file = File::open(path).unwrap();
+        assert_eq!(faces_in_file(&mut file), Some(1));
+
+        let tables = read_index_tables(&mut file, 0).unwrap();
+        let font = TtfFont::from_data(&tables, 0).unwrap();
+
+        assert!(tables.len() < fs::metadata(path).unwrap().len() as usize / 4);
+        assert_eq!(font.family_name(), full.family_name());
+        assert_eq!(font.weight(), full.weight());
+        assert_eq!(font.width(), full.width());
+        assert_eq!(font.is_italic(), full.is_italic());
+    }
+
+    #[test]
+    fn files_are_indexed_once() {
+        let mut db = FontDb::default();
+
+        let regular = fs::read("examples/assets/Roboto-Regular.ttf").unwrap();
+        let face = FaceInfo::new(
+            &TtfFont::from_data(&regular, 0).unwrap(),
+            FaceSource::Loaded(FontId(generational_arena::Index::from_raw_parts(0, 0))),
+        );
+        db.add_file_face(face.unwrap(), FilePath::new("examples/assets/Roboto-Regular.ttf"), 0);
+
+        db.scan_dir(FilePath::new("examples/assets")).unwrap();
+        db.scan_dir(FilePath::new("examples/../examples/assets")).unwrap();
+
+        let roboto: Vec<_> = db.faces.iter().filter(|face| face.family == "Roboto").collect();
+        assert_eq!(roboto.len(), 3);
+        assert!(matches!(roboto[0].source, FaceSource::Loaded(_)));
+    }
+}
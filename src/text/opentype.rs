@@ -0,0 +1,296 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{
+    Hash,
+    Hasher,
+};
+use std::str::FromStr;
+
+use crate::ErrorKind;
+
+const MAX_LANGUAGE_LEN: usize = 16;
+
+/// An OpenType feature setting passed to the shaper, like `tnum` for tabular numbers or
+/// `liga` to turn ligatures on or off.
+///
+/// Features can be parsed from the CSS `font-feature-settings` syntax: `tnum`, `+smcp`, `-liga`
+/// or `ss01=1`.
+///
+/// # Example
+/// ```
+/// use femtovg::{Color, FontFeature, Paint};
+///
+/// let mut paint = Paint::color(Color::black());
+/// paint.set_font_features(&[FontFeature::on(b"tnum"), "-liga".parse().unwrap()]);
+///
+/// assert_eq!(paint.font_features().nth(1), Some(FontFeature::off(b"liga")));
+/// ```
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct FontFeature {
+    tag: [u8; 4],
+    value: u32,
+}
+
+impl FontFeature {
+    /// Creates a feature setting. A value of 0 disables the feature, 1 enables it and larger
+    /// values pick an alternate for features like `salt` or `aalt`.
+    pub fn new(tag: &[u8; 4], value: u32) -> Self {
+        Self { tag: *tag, value }
+    }
+
+    /// Enables a feature.
+    pub fn on(tag: &[u8; 4]) -> Self {
+        Self::new(tag, 1)
+    }
+
+    /// Disables a feature.
+    pub fn off(tag: &[u8; 4]) -> Self {
+        Self::new(tag, 0)
+    }
+
+    /// The four letter feature tag.
+    pub fn tag(&self) -> [u8; 4] {
+        self.tag
+    }
+
+    /// The value of the feature, 0 if it is disabled.
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    pub(crate) fn to_rustybuzz(self) -> rustybuzz::Feature {
+        rustybuzz::Feature::new(rustybuzz::Tag::from_bytes(&self.tag), self.value, ..)
+    }
+}
+
+impl FromStr for FontFeature {
+    type Err = ErrorKind;
+
+    fn from_str(text: &str) -> Result<Self, ErrorKind> {
+        let invalid = || ErrorKind::GeneralError(format!("invalid font feature {:?}", text));
+
+        let text = text.trim();
+
+        let (text, default) = match text.as_bytes().first() {
+            Some(b'+') => (&text[1..], 1),
+            Some(b'-') => (&text[1..], 0),
+            _ => (text, 1),
+        };
+
+        let mut parts = text.splitn(2, '=');
+        let tag = parts
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'');
+
+        let value = match parts.next() {
+            Some(value) => value.trim().parse().map_err(|_| invalid())?,
+            None => default,
+        };
+
+        if tag.len() != 4 || !tag.is_ascii() {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(tag.as_bytes());
+
+        Ok(Self::new(&bytes, value))
+    }
+}
+
+/// A value for a variation axis of a variable font, like `wght` for the weight, `wdth` for the
+/// width, `opsz` for the optical size or a custom axis of the font.
+///
+/// Variations can be parsed from the CSS `font-variation-settings` syntax, `wght 650` or
+/// `"wdth" 75`, or written as `wght=650`. Values outside of an axis' range are clamped and axes
+/// the font doesn't have are ignored.
+///
+/// # Example
+/// ```
+/// use femtovg::{Color, FontVariation, Paint};
+///
+/// let mut paint = Paint::color(Color::black());
+/// paint.set_font_variations(&[FontVariation::new(b"wght", 650.0), "wdth 75".parse().unwrap()]);
+///
+/// assert_eq!(paint.font_variations().nth(1), Some(FontVariation::new(b"wdth", 75.0)));
+/// ```
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct FontVariation {
+    tag: [u8; 4],
+    value: f32,
+}
+
+impl FontVariation {
+    /// Creates a value for an axis.
+    pub fn new(tag: &[u8; 4], value: f32) -> Self {
+        Self { tag: *tag, value }
+    }
+
+    /// The four letter axis tag.
+    pub fn tag(&self) -> [u8; 4] {
+        self.tag
+    }
+
+    /// The value on the axis, in the units of the axis like the CSS weight for `wght`.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    pub(crate) fn ttf_tag(self) -> owned_ttf_parser::Tag {
+        owned_ttf_parser::Tag::from_bytes(&self.tag)
+    }
+
+    pub(crate) fn to_rustybuzz(self) -> rustybuzz::Variation {
+        rustybuzz::Variation {
+            tag: rustybuzz::Tag::from_bytes(&self.tag),
+            value: self.value,
+        }
+    }
+
+    // Variations are used in cache keys, so they compare by the bits of the value
+    fn key(&self) -> ([u8; 4], u32) {
+        (self.tag, self.value.to_bits())
+    }
+}
+
+impl PartialEq for FontVariation {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for FontVariation {}
+
+impl Hash for FontVariation {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialOrd for FontVariation {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FontVariation {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl FromStr for FontVariation {
+    type Err = ErrorKind;
+
+    fn from_str(text: &str) -> Result<Self, ErrorKind> {
+        let invalid = || ErrorKind::GeneralError(format!("invalid font variation {:?}", text));
+
+        let text = text.trim();
+        let split = text.find(|c: char| c == '=' || c.is_whitespace()).ok_or_else(invalid)?;
+
+        let tag = text[..split].trim_matches(|c| c == '"' || c == '\'');
+        let value: f32 = text[split..]
+            .trim_start_matches(|c: char| c == '=' || c.is_whitespace())
+            .parse()
+            .map_err(|_| invalid())?;
+
+        if tag.len() != 4 || !tag.is_ascii() || !value.is_finite() {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(tag.as_bytes());
+
+        Ok(Self::new(&bytes, value))
+    }
+}
+
+/// The language of a text as a BCP 47 tag like `tr`, `sr-Latn` or `zh-Hant-HK`.
+///
+/// The shaper uses it to pick language specific glyphs and features of a font, for example the
+/// Turkish dotted i in small caps. A script subtag also sets the script of the text instead of
+/// guessing it from the characters.
+///
+/// # Example
+/// ```
+/// use femtovg::TextLanguage;
+///
+/// let language: TextLanguage = "sr-Latn-RS".parse().unwrap();
+///
+/// assert_eq!(language.as_str(), "sr-Latn-RS");
+/// assert_eq!(language.script(), Some(*b"Latn"));
+/// ```
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct TextLanguage {
+    tag: [u8; MAX_LANGUAGE_LEN],
+    len: u8,
+}
+
+impl TextLanguage {
+    /// The language tag.
+    pub fn as_str(&self) -> &str {
+        // Only ASCII is accepted when parsing
+        std::str::from_utf8(&self.tag[..self.len as usize]).unwrap_or_default()
+    }
+
+    /// The four letter ISO 15924 script subtag, if the tag has one.
+    pub fn script(&self) -> Option<[u8; 4]> {
+        self.as_str()
+            .split('-')
+            .skip(1)
+            .take_while(|subtag| subtag.len() > 1)
+            .find(|subtag| subtag.len() == 4 && subtag.bytes().all(|c| c.is_ascii_alphabetic()))
+            .map(|subtag| {
+                let mut script = [0; 4];
+                script.copy_from_slice(subtag.as_bytes());
+                script
+            })
+    }
+
+    pub(crate) fn to_rustybuzz(self) -> rustybuzz::Language {
+        // Parsing only fails for empty tags, which aren't accepted
+        self.as_str().parse().expect("language tag is empty")
+    }
+
+    pub(crate) fn rustybuzz_script(self) -> Option<rustybuzz::Script> {
+        self.script()
+            .and_then(|script| rustybuzz::Script::from_iso15924_tag(rustybuzz::Tag::from_bytes(&script)))
+    }
+}
+
+impl FromStr for TextLanguage {
+    type Err = ErrorKind;
+
+    fn from_str(text: &str) -> Result<Self, ErrorKind> {
+        let text = text.trim();
+
+        let valid = !text.is_empty()
+            && text.len() <= MAX_LANGUAGE_LEN
+            && text
+                .split('-')
+                .all(|subtag| !subtag.is_empty() && subtag.bytes().all(|c| c.is_ascii_alphanumeric()));
+
+        if !valid {
+            return Err(ErrorKind::GeneralError(format!("invalid language tag {:?}", text)));
+        }
+
+        let mut tag = [0; MAX_LANGUAGE_LEN];
+        tag[..text.len()].copy_from_slice(text.as_bytes());
+
+        Ok(Self {
+            tag,
+            len: text.len() as u8,
+        })
+    }
+}
+
+impl fmt::Debug for TextLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TextLanguage").field(&self.as_str()).finish()
+    }
+}
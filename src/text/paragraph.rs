@@ -0,0 +1,941 @@
+use std::iter;
+use std::ops::Range;
+
+use unicode_bidi::BidiInfo;
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{
+    shape,
+    Align,
+    Baseline,
+    RenderMode,
+    RichText,
+    ShapedGlyph,
+    TextContext,
+    TextMetrics,
+};
+
+use crate::{
+    Canvas,
+    ErrorKind,
+    Paint,
+    Rect,
+    Renderer,
+};
+
+/// Options controlling how [`Canvas::layout_paragraph`](struct.Canvas.html#method.layout_paragraph)
+/// breaks text into lines and positions them.
+///
+/// The default style does not wrap, uses the font's line height and aligns lines to the left.
+///
+/// # Example
+/// ```
+/// use femtovg::{Align, ParagraphStyle};
+///
+/// let mut style = ParagraphStyle::new();
+/// style.set_max_width(Some(300.0));
+/// style.set_line_height(1.5);
+/// style.set_align(Align::Center);
+/// style.set_max_lines(Some(3));
+///
+/// assert_eq!(style.max_width(), Some(300.0));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParagraphStyle {
+    max_width: Option<f32>,
+    line_height: f32,
+    align: Align,
+    justify: bool,
+    max_lines: Option<usize>,
+}
+
+impl Default for ParagraphStyle {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            line_height: 1.0,
+            align: Align::Left,
+            justify: false,
+            max_lines: None,
+        }
+    }
+}
+
+impl ParagraphStyle {
+    /// Creates the default paragraph style.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the width lines are wrapped to.
+    pub fn max_width(&self) -> Option<f32> {
+        self.max_width
+    }
+
+    /// Sets the width lines are wrapped to. Lines are broken between words where possible and
+    /// between grapheme clusters when a single word doesn't fit. `None` only breaks lines at
+    /// newlines.
+    pub fn set_max_width(&mut self, width: Option<f32>) {
+        self.max_width = width;
+    }
+
+    /// Returns the line height as a multiple of the font's line height.
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    /// Sets the line height as a multiple of the font's line height. The extra space is split
+    /// evenly above and below the text of each line.
+    pub fn set_line_height(&mut self, line_height: f32) {
+        self.line_height = line_height;
+    }
+
+    /// Returns the horizontal alignment of the lines.
+    pub fn align(&self) -> Align {
+        self.align
+    }
+
+    /// Sets the horizontal alignment of the lines within the maximum width, or within the
+    /// widest line if there is no maximum width.
+    pub fn set_align(&mut self, align: Align) {
+        self.align = align;
+    }
+
+    /// Returns true if wrapped lines are justified.
+    pub fn justify(&self) -> bool {
+        self.justify
+    }
+
+    /// Stretches the spaces of wrapped lines so that they fill the maximum width. The last line
+    /// of a paragraph and lines ending in a newline keep the alignment set with
+    /// [`set_align`](#method.set_align).
+    pub fn set_justify(&mut self, justify: bool) {
+        self.justify = justify;
+    }
+
+    /// Returns the maximum number of lines.
+    pub fn max_lines(&self) -> Option<usize> {
+        self.max_lines
+    }
+
+    /// Sets the maximum number of lines, any text after them is left out.
+    pub fn set_max_lines(&mut self, max_lines: Option<usize>) {
+        self.max_lines = max_lines;
+    }
+}
+
+/// Position and size of a line in a [`Paragraph`](struct.Paragraph.html).
+///
+/// All coordinates are relative to the top left corner of the paragraph.
+#[derive(Clone, Debug, Default)]
+pub struct LineMetrics {
+    range: Range<usize>,
+    visible_end: usize,
+    glyphs: Range<usize>,
+    x: f32,
+    y: f32,
+    baseline: f32,
+    width: f32,
+    height: f32,
+    ascender: f32,
+    descender: f32,
+    justify_spacing: f32,
+}
+
+impl LineMetrics {
+    /// The byte range of the line in the paragraph text, including trailing whitespace but not
+    /// the newline ending it.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// The range of the line's glyphs in [`Paragraph::glyphs`](struct.Paragraph.html#method.glyphs).
+    pub fn glyph_range(&self) -> Range<usize> {
+        self.glyphs.clone()
+    }
+
+    /// The left edge of the line's text.
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+
+    /// The top of the line box.
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+
+    /// The alphabetic baseline of the line.
+    pub fn baseline(&self) -> f32 {
+        self.baseline
+    }
+
+    /// The width of the line's text, without trailing whitespace.
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    /// The height of the line box, including line spacing.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// The distance from the baseline to the top of the highest glyph.
+    pub fn ascender(&self) -> f32 {
+        self.ascender
+    }
+
+    /// The distance from the baseline to the bottom of the lowest descender, usually negative.
+    pub fn descender(&self) -> f32 {
+        self.descender
+    }
+
+    pub(crate) fn visible_range(&self) -> Range<usize> {
+        self.range.start..self.visible_end
+    }
+}
+
+/// Text broken into lines and positioned, ready to be drawn with
+/// [`Canvas::fill_paragraph`](struct.Canvas.html#method.fill_paragraph).
+///
+/// The layout is done for the canvas transform at the time
+/// [`Canvas::layout_paragraph`](struct.Canvas.html#method.layout_paragraph) or
+/// [`Canvas::layout_rich_text`](struct.Canvas.html#method.layout_rich_text) is called, lay the
+/// paragraph out again when the scale or the text changes.
+///
+/// # Example
+/// ```
+/// use femtovg::{Canvas, Color, Paint, ParagraphStyle, renderer::Void};
+///
+/// let mut canvas = Canvas::new(Void).expect("Cannot create canvas");
+/// canvas.set_size(400, 300, 1.0);
+///
+/// let font = canvas.add_font("examples/assets/Roboto-Regular.ttf").expect("Cannot add font");
+///
+/// let mut paint = Paint::color(Color::black());
+/// paint.set_font(&[font]);
+///
+/// let mut style = ParagraphStyle::new();
+/// style.set_max_width(Some(120.0));
+///
+/// let paragraph = canvas
+///     .layout_paragraph("The quick brown fox jumps over the lazy dog", &style, paint)
+///     .expect("Cannot lay out paragraph");
+///
+/// assert!(paragraph.lines().len() > 1);
+/// assert!(paragraph.width() <= 120.0);
+///
+/// canvas.fill_paragraph(10.0, 10.0, &paragraph).expect("Cannot draw paragraph");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Paragraph {
+    text: RichText,
+    lines: Vec<LineMetrics>,
+    glyphs: Vec<ShapedGlyph>,
+    width: f32,
+    height: f32,
+    truncated: bool,
+}
+
+impl Paragraph {
+    /// The laid out text.
+    pub fn text(&self) -> &str {
+        self.text.text()
+    }
+
+    /// The laid out text with the paints of its spans.
+    pub fn rich_text(&self) -> &RichText {
+        &self.text
+    }
+
+    /// The lines of the paragraph from top to bottom.
+    pub fn lines(&self) -> &[LineMetrics] {
+        &self.lines
+    }
+
+    /// Positions of the glyphs of all lines, relative to the top left corner of the paragraph.
+    pub fn glyphs(&self) -> &[ShapedGlyph] {
+        &self.glyphs
+    }
+
+    /// The width of the paragraph, which is the maximum width if one was set or the width of
+    /// the widest line otherwise.
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    /// The total height of all lines.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// Returns true if lines were left out because of the maximum number of lines.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    pub(crate) fn new<T: Renderer>(
+        canvas: &mut Canvas<T>,
+        text: RichText,
+        style: &ParagraphStyle,
+    ) -> Result<Self, ErrorKind> {
+        let mut paragraph = Self {
+            text,
+            lines: Vec::new(),
+            glyphs: Vec::new(),
+            width: 0.0,
+            height: 0.0,
+            truncated: false,
+        };
+
+        if paragraph.text.spans().is_empty() {
+            return Ok(paragraph);
+        }
+
+        // Layout happens in device pixels, the resulting metrics are divided by the scale
+        let scale = canvas.font_scale() * canvas.device_px_ratio;
+        let paints = scaled_paints(canvas, &paragraph.text);
+        let context = &mut canvas.text_context;
+        let text = &paragraph.text;
+
+        let max_width = style.max_width.map(|width| width * scale);
+        let LineBreaks {
+            lines: ranges,
+            truncated,
+        } = break_lines(context, text, &paints, max_width, style.max_lines)?;
+
+        // Shape each line on its own, positioning needs the widest line
+        let mut shaped = Vec::with_capacity(ranges.len());
+
+        for (range, hard_break) in ranges {
+            let visible_end = range.start + text.text()[range.clone()].trim_end().len();
+            let line = shape_line(context, text, &paints, range.start..visible_end, 0.0, 0.0)?;
+            let font_metrics = line_font_metrics(context, text, &paints, range.start..visible_end)?;
+
+            shaped.push((range, visible_end, hard_break, line, font_metrics));
+        }
+
+        let box_width = match max_width {
+            Some(max_width) => max_width,
+            None => shaped.iter().map(|(_, _, _, line, _)| line.width).fold(0.0, f32::max),
+        };
+
+        let mut y = 0.0;
+
+        for (range, visible_end, hard_break, mut line, (ascender, descender, font_height)) in shaped {
+            let mut width = line.width;
+            let mut justify_spacing = 0.0;
+
+            let spaces = line.glyphs.iter().filter(|glyph| glyph.c.is_whitespace()).count();
+
+            if style.justify && !hard_break && max_width.is_some() && spaces > 0 {
+                justify_spacing = (box_width - width) / spaces as f32;
+                width = box_width;
+            }
+
+            let x = match style.align {
+                Align::Left => 0.0,
+                Align::Center => (box_width - width) / 2.0,
+                Align::Right => box_width - width,
+            };
+
+            let line_height = font_height * style.line_height;
+            let baseline = y + (line_height - (ascender - descender)) / 2.0 + ascender;
+
+            justify(&mut line.glyphs, justify_spacing);
+
+            let glyph_start = paragraph.glyphs.len();
+
+            for mut glyph in line.glyphs {
+                glyph.x = (glyph.x + x) / scale;
+                glyph.y = (glyph.y + baseline) / scale;
+                glyph.width /= scale;
+                glyph.height /= scale;
+                glyph.advance_x /= scale;
+                glyph.advance_y /= scale;
+                glyph.offset_x /= scale;
+                glyph.offset_y /= scale;
+                glyph.bearing_x /= scale;
+                glyph.bearing_y /= scale;
+                paragraph.glyphs.push(glyph);
+            }
+
+            paragraph.lines.push(LineMetrics {
+                range,
+                visible_end,
+                glyphs: glyph_start..paragraph.glyphs.len(),
+                x: x / scale,
+                y: y / scale,
+                baseline: baseline / scale,
+                width: width / scale,
+                height: line_height / scale,
+                ascender: ascender / scale,
+                descender: descender / scale,
+                justify_spacing: justify_spacing / scale,
+            });
+
+            y += line_height;
+        }
+
+        paragraph.width = box_width / scale;
+        paragraph.height = y / scale;
+        paragraph.truncated = truncated;
+
+        Ok(paragraph)
+    }
+
+    pub(crate) fn draw<T: Renderer>(
+        &self,
+        canvas: &mut Canvas<T>,
+        x: f32,
+        y: f32,
+        mode: RenderMode,
+    ) -> Result<(), ErrorKind> {
+        let scale = canvas.font_scale() * canvas.device_px_ratio;
+        let paints = scaled_paints(canvas, &self.text);
+
+        for line in &self.lines {
+            let mut shaped = shape_line(
+                &mut canvas.text_context,
+                &self.text,
+                &paints,
+                line.visible_range(),
+                (x + line.x) * scale,
+                (y + line.baseline) * scale,
+            )?;
+
+            justify(&mut shaped.glyphs, line.justify_spacing * scale);
+
+            // Each run of glyphs from the same span is drawn with the span's paint
+            let mut start = 0;
+
+            while start < shaped.glyphs.len() {
+                let span = shaped.spans[start];
+                let count = shaped.spans[start..].iter().take_while(|other| **other == span).count();
+
+                let layout = TextMetrics {
+                    glyphs: shaped.glyphs[start..start + count].to_vec(),
+                    ..Default::default()
+                };
+
+                canvas.render_text(&layout, paints[span], mode)?;
+                start += count;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Hit testing
+
+impl Paragraph {
+    /// Returns the byte index of the grapheme cluster boundary closest to x, y, relative to the
+    /// top left corner of the paragraph. Points above or below the paragraph hit the first or
+    /// last line, points beside a line hit its start or end.
+    ///
+    /// # Example
+    /// ```
+    /// # use femtovg::{Canvas, Color, Paint, ParagraphStyle, renderer::Void};
+    /// # let mut canvas = Canvas::new(Void).unwrap();
+    /// # let font = canvas.add_font("examples/assets/Roboto-Regular.ttf").unwrap();
+    /// # let mut paint = Paint::color(Color::black());
+    /// # paint.set_font(&[font]);
+    /// let paragraph = canvas.layout_paragraph("Hello", &ParagraphStyle::new(), paint).unwrap();
+    ///
+    /// assert_eq!(paragraph.index_at(-10.0, 0.0), 0);
+    /// assert_eq!(paragraph.index_at(paragraph.width() + 10.0, 0.0), 5);
+    ///
+    /// // The caret goes before the character under the left half of it
+    /// let caret = paragraph.caret_rect(1);
+    /// assert_eq!(paragraph.index_at(caret.x + 1.0, 0.0), 1);
+    /// ```
+    pub fn index_at(&self, x: f32, y: f32) -> usize {
+        let line = match self.lines.iter().position(|line| y < line.y + line.height) {
+            Some(index) => &self.lines[index],
+            None => match self.lines.last() {
+                Some(line) => line,
+                None => return 0,
+            },
+        };
+
+        let graphemes = self.graphemes(line);
+
+        let hit = graphemes
+            .iter()
+            .find(|grapheme| x < grapheme.right)
+            .or_else(|| graphemes.last());
+
+        let grapheme = match hit {
+            Some(grapheme) => grapheme,
+            None => return line.range.start,
+        };
+
+        // Left of the middle is the leading edge for left to right text, the trailing edge otherwise
+        if (x < (grapheme.left + grapheme.right) / 2.0) != grapheme.rtl {
+            grapheme.range.start
+        } else {
+            grapheme.range.end
+        }
+    }
+
+    /// Returns the caret for the byte index, a zero width rectangle as high as the line.
+    ///
+    /// The caret is placed before the grapheme cluster starting at the index, after the last one
+    /// if the index is at the end of a line. An index where a line wraps is at the start of the
+    /// next line.
+    pub fn caret_rect(&self, index: usize) -> Rect {
+        let line = match self.line_at(index) {
+            Some(line) => line,
+            None => return Rect::new(0.0, 0.0, 0.0, 0.0),
+        };
+
+        let graphemes = self.graphemes(line);
+
+        // The grapheme containing the index, or the one ending the line
+        let before = graphemes.iter().find(|grapheme| grapheme.range.contains(&index));
+
+        let x = match before {
+            Some(grapheme) if !grapheme.rtl => grapheme.left,
+            Some(grapheme) => grapheme.right,
+            None => line_end_x(line, &graphemes),
+        };
+
+        Rect::new(x, line.y, 0.0, line.height)
+    }
+
+    /// Returns rectangles covering the selected byte range, at least one per line it touches.
+    /// Bidirectional text may need several rectangles on a line. Trailing whitespace isn't laid out,
+    /// selecting only that gets a zero width rectangle at the end of the line.
+    pub fn selection_rects(&self, range: Range<usize>) -> Vec<Rect> {
+        let mut rects: Vec<Rect> = Vec::new();
+
+        if range.start >= range.end {
+            return rects;
+        }
+
+        for line in &self.lines {
+            if line.range.start >= range.end || line.range.end <= range.start {
+                continue;
+            }
+
+            let first = rects.len();
+            let graphemes = self.graphemes(line);
+
+            for grapheme in &graphemes {
+                if grapheme.range.start >= range.end || grapheme.range.end <= range.start {
+                    continue;
+                }
+
+                match rects[first..].last_mut() {
+                    // Graphemes next to each other share a rectangle
+                    Some(rect) if (rect.x + rect.w - grapheme.left).abs() < 0.01 => {
+                        rect.w = grapheme.right - rect.x;
+                    }
+                    _ => rects.push(Rect::new(
+                        grapheme.left,
+                        line.y,
+                        grapheme.right - grapheme.left,
+                        line.height,
+                    )),
+                }
+            }
+
+            if rects.len() == first {
+                rects.push(Rect::new(line_end_x(line, &graphemes), line.y, 0.0, line.height));
+            }
+        }
+
+        rects
+    }
+
+    // The last line starting at or before the index
+    fn line_at(&self, index: usize) -> Option<&LineMetrics> {
+        self.lines
+            .iter()
+            .take_while(|line| line.range.start <= index)
+            .last()
+            .or_else(|| self.lines.first())
+    }
+
+    // The graphemes of a line in visual order. Glyph clusters spanning several graphemes, like
+    // ligatures, are divided evenly between them.
+    fn graphemes(&self, line: &LineMetrics) -> Vec<GraphemeBox> {
+        let glyphs = &self.glyphs[line.glyphs.clone()];
+        let text = self.text.text();
+
+        let bidi_info = BidiInfo::new(&text[line.visible_range()], Some(unicode_bidi::Level::ltr()));
+
+        let mut cluster_starts: Vec<usize> = glyphs.iter().map(|glyph| glyph.byte_index).collect();
+        cluster_starts.sort_unstable();
+        cluster_starts.dedup();
+
+        let mut graphemes = Vec::new();
+        let mut index = 0;
+
+        while index < glyphs.len() {
+            let start = glyphs[index].byte_index;
+            let count = glyphs[index..]
+                .iter()
+                .take_while(|glyph| glyph.byte_index == start)
+                .count();
+
+            let left = pen_x(&glyphs[index]);
+            let right = match glyphs.get(index + count) {
+                Some(next) => pen_x(next),
+                None => line.x + line.width,
+            };
+
+            let end = match cluster_starts.binary_search(&start) {
+                Ok(position) => cluster_starts.get(position + 1).copied().unwrap_or(line.visible_end),
+                Err(_) => line.visible_end,
+            };
+
+            let rtl = matches!(bidi_info.levels.get(start - line.range.start), Some(level) if level.is_rtl());
+
+            let boundaries: Vec<usize> = text[start..end]
+                .grapheme_indices(true)
+                .map(|(offset, _)| start + offset)
+                .chain(iter::once(end))
+                .collect();
+
+            let count_graphemes = boundaries.len().saturating_sub(1).max(1);
+            let step = (right - left) / count_graphemes as f32;
+
+            let mut cluster: Vec<GraphemeBox> = boundaries
+                .windows(2)
+                .enumerate()
+                .map(|(position, pair)| {
+                    // Right to left graphemes are laid out from the right edge of the cluster
+                    let position = if rtl { count_graphemes - 1 - position } else { position };
+
+                    GraphemeBox {
+                        range: pair[0]..pair[1],
+                        left: left + step * position as f32,
+                        right: left + step * (position + 1) as f32,
+                        rtl,
+                    }
+                })
+                .collect();
+
+            if rtl {
+                cluster.reverse();
+            }
+
+            graphemes.extend(cluster);
+            index += count;
+        }
+
+        graphemes
+    }
+}
+
+struct GraphemeBox {
+    range: Range<usize>,
+    left: f32,
+    right: f32,
+    rtl: bool,
+}
+
+// Where the caret goes after the last grapheme of the line
+fn line_end_x(line: &LineMetrics, graphemes: &[GraphemeBox]) -> f32 {
+    match graphemes.iter().max_by_key(|grapheme| grapheme.range.end) {
+        Some(grapheme) if !grapheme.rtl => grapheme.right,
+        Some(grapheme) => grapheme.left,
+        None => line.x,
+    }
+}
+
+// The pen position the glyph was placed from
+fn pen_x(glyph: &ShapedGlyph) -> f32 {
+    glyph.x - glyph.offset_x - glyph.bearing_x
+}
+
+struct ShapedLine {
+    glyphs: Vec<ShapedGlyph>,
+    // Index of the span each glyph belongs to
+    spans: Vec<usize>,
+    width: f32,
+}
+
+// The span paints with font sizes in device pixels
+fn scaled_paints<T: Renderer>(canvas: &Canvas<T>, text: &RichText) -> Vec<Paint> {
+    text.spans()
+        .iter()
+        .map(|span| {
+            let mut paint = *span.paint();
+            paint.text_align = Align::Left;
+            paint.text_baseline = Baseline::Alphabetic;
+            canvas.transform_text_paint(&mut paint);
+            paint
+        })
+        .collect()
+}
+
+// Shapes a range of text without line breaks, with the left end of the baseline at x, y
+fn shape_line(
+    context: &mut TextContext,
+    text: &RichText,
+    paints: &[Paint],
+    range: Range<usize>,
+    x: f32,
+    y: f32,
+) -> Result<ShapedLine, ErrorKind> {
+    let mut line = ShapedLine {
+        glyphs: Vec::new(),
+        spans: Vec::new(),
+        width: 0.0,
+    };
+
+    let bidi_info = BidiInfo::new(&text.text()[range.clone()], Some(unicode_bidi::Level::ltr()));
+
+    let paragraph = match bidi_info.paragraphs.first() {
+        Some(paragraph) => paragraph,
+        None => return Ok(line),
+    };
+
+    let (levels, runs) = bidi_info.visual_runs(paragraph, paragraph.range.clone());
+    let mut cursor_x = x;
+
+    for run in runs {
+        let mut pieces = shaping_pieces(text, paints, range.start + run.start..range.start + run.end);
+
+        if levels[run.start].is_rtl() {
+            pieces.reverse();
+        }
+
+        for (piece, paint) in pieces {
+            let metrics = shape(cursor_x, y, context, &paints[paint], &text.text()[piece.clone()], None)?;
+
+            for mut glyph in metrics.glyphs.iter().copied() {
+                glyph.byte_index += piece.start;
+                line.spans.push(text.span_at(glyph.byte_index).unwrap_or(paint));
+                line.glyphs.push(glyph);
+            }
+
+            cursor_x += metrics.width();
+        }
+    }
+
+    line.width = cursor_x - x;
+
+    Ok(line)
+}
+
+// Splits the range where the font changes. Spans that only differ in color are shaped together,
+// which keeps kerning and ligatures across them.
+fn shaping_pieces(text: &RichText, paints: &[Paint], range: Range<usize>) -> Vec<(Range<usize>, usize)> {
+    let mut pieces: Vec<(Range<usize>, usize)> = Vec::new();
+
+    for (index, span) in text.spans().iter().enumerate() {
+        let start = span.range().start.max(range.start);
+        let end = span.range().end.min(range.end);
+
+        if start >= end {
+            continue;
+        }
+
+        match pieces.last_mut() {
+            Some((piece, paint)) if piece.end == start && same_shaping(&paints[*paint], &paints[index]) => {
+                piece.end = end;
+            }
+            _ => pieces.push((start..end, index)),
+        }
+    }
+
+    pieces
+}
+
+fn same_shaping(a: &Paint, b: &Paint) -> bool {
+    a.font_ids == b.font_ids
+        && a.font_size == b.font_size
+        && a.letter_spacing == b.letter_spacing
+        && a.font_features == b.font_features
+        && a.font_variations == b.font_variations
+        && a.language == b.language
+}
+
+// Returns the largest ascender, smallest descender and largest line height of the spans in range
+fn line_font_metrics(
+    context: &mut TextContext,
+    text: &RichText,
+    paints: &[Paint],
+    range: Range<usize>,
+) -> Result<(f32, f32, f32), ErrorKind> {
+    let mut spans: Vec<usize> = (0..paints.len())
+        .filter(|index| {
+            let span = text.spans()[*index].range();
+            span.start < range.end && span.end > range.start
+        })
+        .collect();
+
+    // An empty line gets its height from the span it's in
+    if spans.is_empty() {
+        spans.extend(text.span_at(range.start));
+    }
+
+    let mut result: Option<(f32, f32, f32)> = None;
+
+    for span in spans {
+        let paint = &paints[span];
+
+        let font = paint.font_ids.iter().flatten().find_map(|id| context.font(*id));
+
+        if let Some(font) = font {
+            let metrics = font.metrics(paint.font_size);
+
+            result = Some(match result {
+                Some((ascender, descender, height)) => (
+                    ascender.max(metrics.ascender()),
+                    descender.min(metrics.descender()),
+                    height.max(metrics.height()),
+                ),
+                None => (metrics.ascender(), metrics.descender(), metrics.height()),
+            });
+        }
+    }
+
+    result.ok_or(ErrorKind::NoFontFound)
+}
+
+// Moves every glyph right by `spacing` for each whitespace glyph before it
+fn justify(glyphs: &mut [ShapedGlyph], spacing: f32) {
+    if spacing == 0.0 {
+        return;
+    }
+
+    let mut offset = 0.0;
+
+    for glyph in glyphs {
+        glyph.x += offset;
+
+        if glyph.c.is_whitespace() {
+            offset += spacing;
+        }
+    }
+}
+
+struct LineBreaks {
+    // Byte range of each line and whether it ends in a newline or the end of the text
+    lines: Vec<(Range<usize>, bool)>,
+    truncated: bool,
+}
+
+fn break_lines(
+    context: &mut TextContext,
+    text: &RichText,
+    paints: &[Paint],
+    max_width: Option<f32>,
+    max_lines: Option<usize>,
+) -> Result<LineBreaks, ErrorKind> {
+    let mut lines = Vec::new();
+    let mut paragraph_start = 0;
+    let max_lines = max_lines.unwrap_or(usize::MAX);
+
+    for paragraph in text.text().split('\n') {
+        let paragraph_end = paragraph_start + paragraph.trim_end_matches('\r').len();
+
+        let breaks = match max_width {
+            Some(max_width) => break_paragraph(context, text, paints, paragraph_start..paragraph_end, max_width)?,
+            None => Vec::new(),
+        };
+
+        let mut start = paragraph_start;
+
+        for end in breaks.into_iter().chain(iter::once(paragraph_end)) {
+            if lines.len() == max_lines {
+                return Ok(LineBreaks { lines, truncated: true });
+            }
+
+            lines.push((start..end, end == paragraph_end));
+            start = end;
+        }
+
+        paragraph_start += paragraph.len() + 1;
+    }
+
+    Ok(LineBreaks {
+        lines,
+        truncated: false,
+    })
+}
+
+// Returns the byte offsets where the lines of a paragraph without newlines end. Lines are broken
+// between words, whitespace at a break stays on the line before it. Words wider than a line are
+// broken between grapheme clusters.
+fn break_paragraph(
+    context: &mut TextContext,
+    text: &RichText,
+    paints: &[Paint],
+    range: Range<usize>,
+    max_width: f32,
+) -> Result<Vec<usize>, ErrorKind> {
+    let mut breaks = Vec::new();
+    let mut line_start = range.start;
+    let mut line_width = 0.0;
+    let mut word_start = range.start;
+
+    for word in text.text()[range].split_word_bounds() {
+        let word_range = word_start..word_start + word.len();
+        word_start = word_range.end;
+
+        let shaped = shape_line(context, text, paints, word_range.clone(), 0.0, 0.0)?;
+        let width = shaped.width;
+
+        if word.trim().is_empty() {
+            line_width += width;
+            continue;
+        }
+
+        if line_start < word_range.start && line_width + width > max_width {
+            breaks.push(word_range.start);
+            line_start = word_range.start;
+            line_width = 0.0;
+        }
+
+        if width <= max_width {
+            line_width += width;
+            continue;
+        }
+
+        // The word starts a line here, the first grapheme cluster stays on it even if it doesn't fit.
+        // Summing the advances of the word shaped as a whole keeps long words linear, the pieces
+        // aren't shaped again after a break.
+        let mut advances: Vec<(usize, f32)> = shaped
+            .glyphs
+            .iter()
+            .zip(&shaped.spans)
+            .map(|(glyph, &span)| (glyph.byte_index, glyph.advance_x + paints[span].letter_spacing))
+            .collect();
+
+        advances.sort_by_key(|&(byte_index, _)| byte_index);
+
+        let mut advances = advances.into_iter().peekable();
+        let mut end = word_range.start;
+        line_width = 0.0;
+
+        for (index, grapheme) in word.grapheme_indices(true) {
+            let next = word_range.start + index + grapheme.len();
+            let mut grapheme_width = 0.0;
+
+            while let Some(&(byte_index, advance)) = advances.peek() {
+                if byte_index >= next {
+                    break;
+                }
+
+                grapheme_width += advance;
+                advances.next();
+            }
+
+            if end > line_start && line_width + grapheme_width > max_width {
+                breaks.push(end);
+                line_start = end;
+                line_width = 0.0;
+            }
+
+            line_width += grapheme_width;
+            end = next;
+        }
+    }
+
+    Ok(breaks)
+}
@@ -0,0 +1,106 @@
+use std::ops::Range;
+
+use crate::Paint;
+
+/// A part of a [`RichText`](struct.RichText.html) drawn with one paint.
+#[derive(Clone, Debug)]
+pub struct TextSpan {
+    range: Range<usize>,
+    paint: Paint,
+}
+
+impl TextSpan {
+    /// The byte range of the span in the text.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// The paint the span is drawn with, including its font, size and letter spacing.
+    pub fn paint(&self) -> &Paint {
+        &self.paint
+    }
+}
+
+/// Text made of spans with different fonts, sizes, colors and letter spacing.
+///
+/// Lay it out with [`Canvas::layout_rich_text`](struct.Canvas.html#method.layout_rich_text).
+/// Neighbouring spans that only differ in how they are painted are shaped together, so kerning
+/// and ligatures are kept across them. Bidirectional text is reordered across all spans of a line.
+///
+/// # Example
+/// ```
+/// use femtovg::{Color, Paint, RichText};
+///
+/// let regular = Paint::color(Color::black());
+///
+/// let mut bold = regular;
+/// bold.set_font_size(20.0);
+///
+/// let mut text = RichText::new();
+/// text.push("Press ", regular);
+/// text.push("here", bold);
+/// text.push(" to continue", regular);
+///
+/// assert_eq!(text.text(), "Press here to continue");
+/// assert_eq!(text.spans()[1].range(), 6..10);
+/// assert_eq!(text.paint_at(7).map(|paint| paint.font_size()), Some(20.0));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RichText {
+    text: String,
+    spans: Vec<TextSpan>,
+}
+
+impl RichText {
+    /// Creates an empty text.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a span of text drawn with `paint`.
+    pub fn push<S: AsRef<str>>(&mut self, text: S, paint: Paint) {
+        let start = self.text.len();
+        self.text.push_str(text.as_ref());
+
+        self.spans.push(TextSpan {
+            range: start..self.text.len(),
+            paint,
+        });
+    }
+
+    /// The text of all spans.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The spans in text order.
+    pub fn spans(&self) -> &[TextSpan] {
+        &self.spans
+    }
+
+    /// Returns true if there is no text.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Returns the paint of the span containing the byte at `index`.
+    pub fn paint_at(&self, index: usize) -> Option<&Paint> {
+        if index >= self.text.len() {
+            return None;
+        }
+
+        self.span_at(index).map(|span| &self.spans[span].paint)
+    }
+
+    // Index of the span containing the byte, or of the span closest before it. Empty spans
+    // still count, an empty text has the font metrics of its only span.
+    pub(crate) fn span_at(&self, index: usize) -> Option<usize> {
+        if self.spans.is_empty() {
+            return None;
+        }
+
+        let span = self.spans.partition_point(|span| span.range.start <= index);
+
+        Some(span.saturating_sub(1))
+    }
+}
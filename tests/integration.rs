@@ -1,13 +1,48 @@
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
 use femtovg::{
     renderer::Void,
+    Align,
     Baseline,
     Canvas,
     Color,
+    ColorMatrix,
+    CompositeOperation,
+    ErrorKind,
     FillRule,
+    FontFeature,
+    FontQuery,
+    FontStyle,
+    FontVariation,
+    GlyphRendering,
+    ImageFilter,
+    ImageFlags,
+    ImageId,
+    ImageInfo,
+    ImageSampler,
+    ImageSource,
+    Mask,
+    MipmapMode,
+    NineSlice,
+    NineSliceMode,
     Paint,
+    ParagraphStyle,
     Path,
+    PixelFormat,
+    Point,
+    Rect,
+    RenderTarget,
+    RichText,
     Solidity,
+    SpriteBatch,
+    TextLanguage,
+    WrapMode,
 };
+use imgref::ImgVec;
+use rgb::RGBA8;
 
 #[test]
 fn path_with_single_move_to() {
@@ -170,3 +205,1273 @@ fn text_location_respects_scale() {
     assert_eq!(res.x, 100.0);
     assert_eq!(res.y, 100.0);
 }
+
+#[test]
+fn paragraph_layout() {
+    let mut canvas = Canvas::new(Void).unwrap();
+    canvas.set_size(400, 300, 1.0);
+
+    let font = canvas
+        .add_font("examples/assets/Roboto-Regular.ttf")
+        .expect("Font not found");
+
+    let mut paint = Paint::color(Color::black());
+    paint.set_font(&[font]);
+    paint.set_font_size(20.0);
+
+    let text = "The quick brown fox jumps over the lazy dog\nSupercalifragilisticexpialidocious";
+
+    let mut style = ParagraphStyle::new();
+    style.set_max_width(Some(150.0));
+
+    let paragraph = canvas.layout_paragraph(text, &style, paint).unwrap();
+    let lines = paragraph.lines();
+
+    assert!(lines.len() > 3);
+    assert!(!paragraph.truncated());
+    assert_eq!(paragraph.width(), 150.0);
+    assert_eq!(lines[0].range().start, 0);
+    assert!(lines.iter().all(|line| line.width() <= 150.0));
+    assert_eq!(paragraph.height(), lines.iter().map(|line| line.height()).sum::<f32>());
+
+    // Lines cover the text without gaps, apart from the newline
+    for pair in lines.windows(2) {
+        let gap = &text[pair[0].range().end..pair[1].range().start];
+        assert!(gap.is_empty() || gap == "\n");
+        assert!(pair[1].baseline() > pair[0].baseline());
+    }
+
+    assert_eq!(lines.last().unwrap().range().end, text.len());
+
+    // The long word doesn't fit on a line and is broken between characters
+    let newline = text.find('\n').unwrap() + 1;
+    let word_lines = lines.iter().filter(|line| line.range().start >= newline).count();
+    assert!(word_lines > 1);
+
+    // Spacing adds to the line height, centered lines leave room on both sides
+    style.set_line_height(2.0);
+    style.set_align(Align::Center);
+    let centered = canvas.layout_paragraph(text, &style, paint).unwrap();
+
+    assert_eq!(centered.lines().len(), lines.len());
+    assert_eq!(centered.lines()[0].height(), lines[0].height() * 2.0);
+    assert!(centered.lines()[0].baseline() > lines[0].baseline());
+    assert!(centered.lines()[0].x() > 0.0);
+
+    // Justified lines fill the width, except the last line of each paragraph
+    style.set_justify(true);
+    style.set_max_lines(Some(2));
+    let justified = canvas.layout_paragraph(text, &style, paint).unwrap();
+
+    assert_eq!(justified.lines().len(), 2);
+    assert!(justified.truncated());
+    assert_eq!(justified.lines()[0].x(), 0.0);
+    assert_eq!(justified.lines()[0].width(), 150.0);
+
+    let glyphs = &justified.glyphs()[justified.lines()[0].glyph_range()];
+    let last = glyphs.last().unwrap();
+    assert!(last.x + last.advance_x > lines[0].width());
+
+    canvas.fill_paragraph(10.0, 10.0, &justified).unwrap();
+    canvas.stroke_paragraph(10.0, 10.0, &paragraph).unwrap();
+
+    // Without a maximum width only newlines break lines
+    let unwrapped = canvas.layout_paragraph(text, &ParagraphStyle::new(), paint).unwrap();
+    assert_eq!(unwrapped.lines().len(), 2);
+    assert_eq!(unwrapped.lines()[1].range(), newline..text.len());
+}
+
+#[test]
+fn rich_text_layout() {
+    let mut canvas = Canvas::new(Void).unwrap();
+    canvas.set_size(400, 300, 1.0);
+
+    let font = canvas
+        .add_font("examples/assets/Roboto-Regular.ttf")
+        .expect("Font not found");
+
+    let mut regular = Paint::color(Color::black());
+    regular.set_font(&[font]);
+    regular.set_font_size(20.0);
+
+    let mut link = regular;
+    link.set_color(Color::rgb(0, 0, 255));
+
+    let mut large = regular;
+    large.set_font_size(40.0);
+
+    // Spans differing only in color are shaped as if they were one
+    let mut colored = RichText::new();
+    colored.push("Kerning ", regular);
+    colored.push("AV", link);
+    colored.push("AV", regular);
+
+    let plain = canvas
+        .layout_paragraph(colored.text(), &ParagraphStyle::new(), regular)
+        .unwrap();
+    let rich = canvas.layout_rich_text(&colored, &ParagraphStyle::new()).unwrap();
+
+    assert_eq!(rich.glyphs().len(), plain.glyphs().len());
+    assert_eq!(rich.lines()[0].width(), plain.lines()[0].width());
+
+    for (a, b) in rich.glyphs().iter().zip(plain.glyphs()) {
+        assert_eq!((a.x, a.y, a.byte_index), (b.x, b.y, b.byte_index));
+    }
+
+    // Larger spans advance further and make their line taller
+    let mut mixed = RichText::new();
+    mixed.push("small ", regular);
+    mixed.push("large", large);
+    mixed.push("\nsmall", regular);
+
+    assert_eq!(mixed.paint_at(7).unwrap().font_size(), 40.0);
+    assert!(mixed.paint_at(mixed.text().len()).is_none());
+
+    let paragraph = canvas.layout_rich_text(&mixed, &ParagraphStyle::new()).unwrap();
+    let lines = paragraph.lines();
+
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].height() > lines[1].height());
+    assert!(lines[0].ascender() > lines[1].ascender());
+
+    let small = canvas.measure_text(0.0, 0.0, "small large", regular).unwrap();
+    assert!(lines[0].width() > small.width());
+
+    canvas.fill_paragraph(0.0, 0.0, &paragraph).unwrap();
+
+    assert_eq!(
+        canvas
+            .layout_rich_text(&RichText::new(), &ParagraphStyle::new())
+            .unwrap()
+            .lines()
+            .len(),
+        0
+    );
+}
+
+#[test]
+fn paragraph_hit_testing() {
+    let mut canvas = Canvas::new(Void).unwrap();
+    canvas.set_size(400, 300, 1.0);
+
+    let roboto = canvas
+        .add_font("examples/assets/Roboto-Regular.ttf")
+        .expect("Font not found");
+    let amiri = canvas
+        .add_font("examples/assets/amiri-regular.ttf")
+        .expect("Font not found");
+
+    let mut paint = Paint::color(Color::black());
+    paint.set_font(&[roboto]);
+    paint.set_font_size(20.0);
+
+    let mut style = ParagraphStyle::new();
+    style.set_max_width(Some(100.0));
+
+    let text = "office hours flow";
+    let paragraph = canvas.layout_paragraph(text, &style, paint).unwrap();
+    let lines = paragraph.lines();
+    assert!(lines.len() > 1);
+
+    // Every grapheme boundary has its own caret, even inside ligatures
+    let mut previous: Option<Rect> = None;
+
+    for index in (0..=lines[0].range().end).filter(|index| text.is_char_boundary(*index)) {
+        let caret = paragraph.caret_rect(index);
+
+        if index < lines[0].range().end {
+            assert_eq!(caret.y, lines[0].y());
+            assert_eq!(caret.h, lines[0].height());
+
+            if let Some(previous) = previous {
+                assert!(caret.x >= previous.x);
+            }
+
+            assert_eq!(paragraph.index_at(caret.x + 0.1, caret.y + 1.0), index);
+        }
+
+        previous = Some(caret);
+    }
+
+    // Wrapping moves the caret to the start of the next line
+    let wrapped = paragraph.caret_rect(lines[1].range().start);
+    assert_eq!((wrapped.x, wrapped.y), (lines[1].x(), lines[1].y()));
+
+    // Points outside of the text hit the nearest line end
+    assert_eq!(paragraph.index_at(-5.0, -5.0), 0);
+    assert_eq!(paragraph.index_at(500.0, 500.0), text.len());
+
+    // A selection across lines gets a rectangle on each line
+    let rects = paragraph.selection_rects(2..lines[1].range().start + 2);
+    assert_eq!(rects.len(), 2);
+    assert!(rects[0].x > 0.0);
+    assert_eq!(rects[1].x, lines[1].x());
+    assert!(paragraph.selection_rects(3..3).is_empty());
+
+    // Selected trailing whitespace still marks its line
+    let end = lines[0].range().end;
+    assert_eq!(
+        paragraph.selection_rects(end - 1..end),
+        vec![Rect::new(
+            paragraph.caret_rect(end - 1).x,
+            lines[0].y(),
+            0.0,
+            lines[0].height()
+        )]
+    );
+
+    // Right to left text starts on the right
+    paint.set_font(&[amiri]);
+    let arabic = "مرحبا بالعالم";
+    let paragraph = canvas.layout_paragraph(arabic, &ParagraphStyle::new(), paint).unwrap();
+
+    let start = paragraph.caret_rect(0);
+    let end = paragraph.caret_rect(arabic.len());
+    assert!(start.x > end.x);
+
+    let second = arabic.char_indices().nth(1).unwrap().0;
+    assert!(paragraph.caret_rect(second).x < start.x);
+    assert_eq!(paragraph.index_at(start.x - 0.1, 1.0), 0);
+}
+
+#[test]
+fn font_fallback_per_cluster() {
+    let mut canvas = Canvas::new(Void).unwrap();
+
+    let roboto = canvas
+        .add_font("examples/assets/Roboto-Regular.ttf")
+        .expect("Font not found");
+    let amiri = canvas
+        .add_font("examples/assets/amiri-regular.ttf")
+        .expect("Font not found");
+    let entypo = canvas.add_font("examples/assets/entypo.ttf").expect("Font not found");
+
+    let mut paint = Paint::color(Color::black());
+    paint.set_font(&[amiri, roboto]);
+
+    // Latin and Cyrillic letters form one word, only the Cyrillic ones come from the fallback
+    let metrics = canvas.measure_text(0.0, 0.0, "abжc", paint).unwrap();
+    let fonts: Vec<_> = metrics.glyphs.iter().map(|glyph| glyph.font_id).collect();
+
+    assert_eq!(fonts, vec![amiri, amiri, roboto, amiri]);
+    assert!(metrics.glyphs.iter().all(|glyph| glyph.codepoint != 0));
+
+    // Registered fonts that aren't part of the paint are tried last
+    paint.set_font(&[roboto]);
+    let metrics = canvas.measure_text(0.0, 0.0, "ok\u{E740}", paint).unwrap();
+
+    assert_eq!(metrics.glyphs.len(), 3);
+    assert_eq!(metrics.glyphs[2].font_id, entypo);
+    assert_eq!(metrics.glyphs[2].byte_index, 2);
+    assert!(metrics.glyphs[0].x < metrics.glyphs[2].x);
+
+    // Without any font for it, the paint's font shows its .notdef glyph
+    let metrics = canvas.measure_text(0.0, 0.0, "\u{10FFFD}", paint).unwrap();
+    assert_eq!(metrics.glyphs[0].font_id, roboto);
+    assert_eq!(metrics.glyphs[0].codepoint, 0);
+}
+
+#[test]
+fn opentype_features() {
+    let mut canvas = Canvas::new(Void).unwrap();
+
+    let roboto = canvas
+        .add_font("examples/assets/Roboto-Regular.ttf")
+        .expect("Font not found");
+    let amiri = canvas
+        .add_font("examples/assets/amiri-regular.ttf")
+        .expect("Font not found");
+
+    let mut paint = Paint::color(Color::black());
+    paint.set_font(&[amiri]);
+
+    let ligature = canvas.measure_text(0.0, 0.0, "fi", paint).unwrap();
+    assert_eq!(ligature.glyphs.len(), 1);
+
+    // Features are part of the shaping cache key, the cached result above must not be reused
+    paint.set_font_features(&["-liga".parse().unwrap()]);
+    let no_ligature = canvas.measure_text(0.0, 0.0, "fi", paint).unwrap();
+    assert_eq!(no_ligature.glyphs.len(), 2);
+
+    paint.set_font(&[roboto]);
+    paint.set_font_features(&[]);
+    let digits = canvas.measure_text(0.0, 0.0, "0110", paint).unwrap();
+
+    paint.set_font_features(&[FontFeature::on(b"lnum")]);
+    let lining = canvas.measure_text(0.0, 0.0, "0110", paint).unwrap();
+
+    assert_eq!(digits.glyphs.len(), lining.glyphs.len());
+    assert_ne!(digits.glyphs[0].codepoint, lining.glyphs[0].codepoint);
+
+    paint.set_language(Some("sr-Latn".parse().unwrap()));
+    assert!(canvas.measure_text(0.0, 0.0, "0110", paint).is_ok());
+
+    assert!("sr_Latn".parse::<TextLanguage>().is_err());
+    assert!("liga=x".parse::<FontFeature>().is_err());
+}
+
+#[test]
+fn font_variations() {
+    let mut canvas = Canvas::new(Void).unwrap();
+
+    let roboto = canvas
+        .add_font("examples/assets/Roboto-Regular.ttf")
+        .expect("Font not found");
+    let bold = FontVariation::new(b"wght", 700.0);
+
+    // Instances are fonts of their own, fonts that aren't variable keep their default look
+    let instance = canvas.add_font_instance(roboto, &[bold]).unwrap();
+    assert_ne!(instance, roboto);
+
+    let mut paint = Paint::color(Color::black());
+    paint.set_font(&[roboto]);
+    let regular = canvas.measure_text(0.0, 0.0, "Variable", paint).unwrap();
+    let regular_metrics = canvas.measure_font(paint).unwrap();
+
+    paint.set_font(&[instance]);
+    let instanced = canvas.measure_text(0.0, 0.0, "Variable", paint).unwrap();
+    assert_eq!(regular.width(), instanced.width());
+    assert_eq!(
+        regular_metrics.ascender(),
+        canvas.measure_font(paint).unwrap().ascender()
+    );
+
+    paint.set_font_variations(&[bold, "wdth 75".parse().unwrap()]);
+    assert_eq!(paint.font_variations().count(), 2);
+
+    let varied = canvas.measure_text(0.0, 0.0, "Variable", paint).unwrap();
+    assert_eq!(regular.width(), varied.width());
+    assert!(canvas.fill_text(0.0, 0.0, "Variable", paint).is_ok());
+
+    assert_eq!("\"wght\" 650".parse::<FontVariation>().unwrap().value(), 650.0);
+    assert_eq!("wght=650".parse::<FontVariation>().unwrap().tag(), *b"wght");
+    assert!("wght".parse::<FontVariation>().is_err());
+    assert!(canvas.add_font_instance(roboto, &[]).is_ok());
+}
+
+#[test]
+fn variable_font_axes() {
+    let mut canvas = Canvas::new(Void).unwrap();
+
+    // A subset with the glyphs " hno" and wght (28 - 194), wdth (70 - 100) and opsz axes,
+    // from the Unicode text rendering tests
+    let font = canvas
+        .add_font("examples/assets/TestCVARGVAROne.ttf")
+        .expect("Font not found");
+
+    let mut paint = Paint::color(Color::black());
+    paint.set_font(&[font]);
+    paint.set_font_size(40.0);
+
+    let mut width = |variations: &[FontVariation]| {
+        paint.set_font_variations(variations);
+        canvas.measure_text(0.0, 0.0, "hon", paint).unwrap().width()
+    };
+
+    let regular = width(&[]);
+    let bold = width(&[FontVariation::new(b"wght", 194.0)]);
+    let condensed = width(&[FontVariation::new(b"wdth", 70.0)]);
+
+    assert!(bold > regular, "{} {}", bold, regular);
+    assert!(condensed < regular, "{} {}", condensed, regular);
+
+    // Axes the font doesn't have are ignored
+    assert_eq!(width(&["xxxx 500".parse().unwrap()]), regular);
+    assert_eq!(
+        width(&[FontVariation::new(b"wght", 194.0), "xxxx 500".parse().unwrap()]),
+        bold
+    );
+
+    // Instances look the same as the variations applied through the paint
+    let instance = canvas
+        .add_font_instance(font, &[FontVariation::new(b"wght", 194.0)])
+        .unwrap();
+    paint.set_font(&[instance]);
+    paint.set_font_variations(&[]);
+    assert_eq!(canvas.measure_text(0.0, 0.0, "hon", paint).unwrap().width(), bold);
+    assert!(canvas.fill_text(0.0, 0.0, "hon", paint).is_ok());
+}
+
+// Returns a copy of the font with the tables added to it
+fn font_with_tables(font: &[u8], tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let read_u32 =
+        |offset: usize| u32::from_be_bytes([font[offset], font[offset + 1], font[offset + 2], font[offset + 3]]);
+
+    let table_count = u16::from_be_bytes([font[4], font[5]]) as usize;
+    let shift = tables.len() as u32 * 16;
+
+    let mut records: Vec<[u8; 16]> = (0..table_count)
+        .map(|table| {
+            let mut record = [0; 16];
+            record.copy_from_slice(&font[12 + table * 16..28 + table * 16]);
+            record[8..12].copy_from_slice(&(read_u32(20 + table * 16) + shift).to_be_bytes());
+            record
+        })
+        .collect();
+
+    let mut data = font[12 + table_count * 16..].to_vec();
+
+    for (tag, table) in tables {
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+
+        let offset = (12 + (table_count + tables.len()) * 16 + data.len()) as u32;
+
+        let mut record = [0; 16];
+        record[0..4].copy_from_slice(*tag);
+        record[8..12].copy_from_slice(&offset.to_be_bytes());
+        record[12..16].copy_from_slice(&(table.len() as u32).to_be_bytes());
+        records.push(record);
+
+        data.extend_from_slice(table);
+    }
+
+    records.sort_by_key(|record| [record[0], record[1], record[2], record[3]]);
+
+    let mut result = font[0..4].to_vec();
+    result.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    result.extend_from_slice(&font[6..12]);
+    records.iter().for_each(|record| result.extend_from_slice(record));
+    result.extend_from_slice(&data);
+    result
+}
+
+#[test]
+fn color_glyphs() {
+    let roboto = std::fs::read("examples/assets/Roboto-Regular.ttf").unwrap();
+    let (a, b) = (36u16, 37u16);
+
+    // "A" is drawn as a red A below a B in the text color
+    let mut colr = Vec::new();
+    for value in &[0u16, 1] {
+        colr.extend_from_slice(&value.to_be_bytes());
+    }
+    for value in &[14u32, 20] {
+        colr.extend_from_slice(&value.to_be_bytes());
+    }
+    for value in &[2u16, a, 0, 2, a, 0, b, 0xFFFF] {
+        colr.extend_from_slice(&value.to_be_bytes());
+    }
+
+    let mut cpal = Vec::new();
+    for value in &[0u16, 1, 1, 1] {
+        cpal.extend_from_slice(&value.to_be_bytes());
+    }
+    cpal.extend_from_slice(&14u32.to_be_bytes());
+    cpal.extend_from_slice(&0u16.to_be_bytes());
+    cpal.extend_from_slice(&[0, 0, 255, 255]);
+
+    let mut canvas = Canvas::new(Void).unwrap();
+    canvas.set_size(200, 200, 1.0);
+
+    let regular = canvas.add_font_mem(&roboto).unwrap();
+    let layered = canvas
+        .add_font_mem(&font_with_tables(&roboto, &[(b"COLR", colr), (b"CPAL", cpal)]))
+        .unwrap();
+
+    let mut paint = Paint::color(Color::black());
+    paint.set_font(&[regular]);
+    assert!(!canvas.measure_font(paint).unwrap().color());
+
+    canvas.fill_text(10.0, 50.0, "AB", paint).unwrap();
+    let memory_usage = canvas.image_memory_usage();
+
+    paint.set_font(&[layered]);
+    assert!(canvas.measure_font(paint).unwrap().color());
+
+    // Glyphs without layers stay in the grayscale atlas, color glyphs get an RGBA atlas
+    canvas.fill_text(10.0, 50.0, "B", paint).unwrap();
+    assert_eq!(canvas.image_memory_usage(), memory_usage);
+
+    canvas.fill_text(10.0, 50.0, "A", paint).unwrap();
+    let color_memory_usage = canvas.image_memory_usage();
+    assert!(color_memory_usage > memory_usage);
+
+    // Strokes use the outlines, large text draws the layers as paths
+    canvas.stroke_text(10.0, 50.0, "A", paint).unwrap();
+    paint.set_font_size(120.0);
+    canvas.fill_text(10.0, 150.0, "A", paint).unwrap();
+    assert_eq!(canvas.image_memory_usage(), color_memory_usage);
+
+    canvas.flush(None);
+}
+
+#[cfg(feature = "png-glyphs")]
+#[test]
+fn bitmap_glyphs() {
+    let roboto = std::fs::read("examples/assets/Roboto-Regular.ttf").unwrap();
+    let a = 36u16;
+
+    let mut canvas = Canvas::new(Void).unwrap();
+    canvas.set_size(200, 200, 1.0);
+
+    let mut paint = Paint::color(Color::black());
+
+    // A 20 pixel per em strike with an image for "A"
+    let mut png = Vec::new();
+    image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(14, 20, image::Rgba([255, 0, 0, 128])))
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .unwrap();
+
+    let maxp = roboto.windows(4).step_by(1).position(|tag| tag == b"maxp").unwrap();
+    let maxp_offset =
+        u32::from_be_bytes([roboto[maxp + 8], roboto[maxp + 9], roboto[maxp + 10], roboto[maxp + 11]]) as usize;
+    let glyph_count = u16::from_be_bytes([roboto[maxp_offset + 4], roboto[maxp_offset + 5]]) as u32;
+
+    let mut sbix = Vec::new();
+    sbix.extend_from_slice(&[0, 1, 0, 1, 0, 0, 0, 1, 0, 0, 0, 12]);
+    sbix.extend_from_slice(&[0, 20, 0, 72]);
+
+    let data_offset = 4 + (glyph_count + 1) * 4;
+    for glyph in 0..=glyph_count {
+        let offset = data_offset + if glyph > a as u32 { 8 + png.len() as u32 } else { 0 };
+        sbix.extend_from_slice(&offset.to_be_bytes());
+    }
+    sbix.extend_from_slice(&[0, 0, 0, 0]);
+    sbix.extend_from_slice(b"png ");
+    sbix.extend_from_slice(&png);
+
+    let bitmap = canvas
+        .add_font_mem(&font_with_tables(&roboto, &[(b"sbix", sbix)]))
+        .unwrap();
+
+    paint.set_font(&[bitmap]);
+    paint.set_font_size(40.0);
+
+    // The image is scaled from the 20 pixel strike to the font size
+    let metrics = canvas.measure_text(0.0, 0.0, "A", paint).unwrap();
+    assert_eq!(metrics.glyphs[0].width, 28.0);
+    assert_eq!(metrics.glyphs[0].height, 40.0);
+
+    canvas.fill_text(10.0, 50.0, "AB", paint).unwrap();
+    paint.set_font_size(400.0);
+    canvas.fill_text(10.0, 50.0, "A", paint).unwrap();
+
+    // The same image in the CBLC/CBDT tables of Noto Color Emoji: one 20 pixel strike with an
+    // index subtable of format 1 pointing at a glyph with small metrics and PNG data (format 17)
+    let mut cblc = Vec::new();
+    cblc.extend_from_slice(&[0, 3, 0, 0, 0, 0, 0, 1]);
+    cblc.extend_from_slice(&56u32.to_be_bytes());
+    cblc.extend_from_slice(&20u32.to_be_bytes());
+    cblc.extend_from_slice(&1u32.to_be_bytes());
+    cblc.extend_from_slice(&[0; 28]);
+    cblc.extend_from_slice(&a.to_be_bytes());
+    cblc.extend_from_slice(&a.to_be_bytes());
+    cblc.extend_from_slice(&[20, 20, 32, 1]);
+
+    cblc.extend_from_slice(&a.to_be_bytes());
+    cblc.extend_from_slice(&a.to_be_bytes());
+    cblc.extend_from_slice(&8u32.to_be_bytes());
+    cblc.extend_from_slice(&[0, 1, 0, 17, 0, 0, 0, 4]);
+    cblc.extend_from_slice(&0u32.to_be_bytes());
+    cblc.extend_from_slice(&(9 + png.len() as u32).to_be_bytes());
+
+    let mut cbdt = Vec::new();
+    cbdt.extend_from_slice(&[0, 3, 0, 0]);
+    cbdt.extend_from_slice(&[20, 14, 0, 20, 14]);
+    cbdt.extend_from_slice(&(png.len() as u32).to_be_bytes());
+    cbdt.extend_from_slice(&png);
+
+    let bitmap = canvas
+        .add_font_mem(&font_with_tables(&roboto, &[(b"CBDT", cbdt), (b"CBLC", cblc)]))
+        .unwrap();
+
+    paint.set_font(&[bitmap]);
+    paint.set_font_size(40.0);
+
+    let metrics = canvas.measure_text(0.0, 0.0, "A", paint).unwrap();
+    assert_eq!(metrics.glyphs[0].width, 28.0);
+    assert_eq!(metrics.glyphs[0].height, 40.0);
+
+    // Glyphs without an image in the strike keep their outlines
+    let metrics = canvas.measure_text(0.0, 0.0, "B", paint).unwrap();
+    assert!(metrics.glyphs[0].width < 28.0);
+
+    canvas.fill_text(10.0, 50.0, "AB", paint).unwrap();
+
+    canvas.flush(None);
+}
+
+#[test]
+fn distance_field_glyphs() {
+    let text = "The quick brown fox jumps over the lazy dog";
+
+    let mut canvas = Canvas::new(Void).unwrap();
+    canvas.set_size(1000, 1000, 1.0);
+
+    let font = canvas.add_font("examples/assets/Roboto-Regular.ttf").unwrap();
+
+    let mut paint = Paint::color(Color::black());
+    paint.set_font(&[font]);
+    assert_eq!(paint.glyph_rendering(), GlyphRendering::Grayscale);
+
+    // Grayscale glyphs are rasterized for every size and fill more than one atlas
+    let memory_usage = canvas.image_memory_usage();
+
+    for size in 10..90 {
+        paint.set_font_size(size as f32);
+        canvas.fill_text(10.0, 50.0, text, paint).unwrap();
+    }
+
+    let grayscale_usage = canvas.image_memory_usage() - memory_usage;
+    assert!(grayscale_usage > 512 * 512 * 4);
+
+    // Distance fields are rendered once for all sizes, scales and rotations, both kinds share
+    // one atlas
+    let memory_usage = canvas.image_memory_usage();
+
+    for &rendering in &[GlyphRendering::Sdf, GlyphRendering::Msdf] {
+        paint.set_glyph_rendering(rendering);
+
+        for size in 10..90 {
+            paint.set_font_size(size as f32);
+            canvas.fill_text(10.0, 50.0, text, paint).unwrap();
+        }
+
+        canvas.save();
+        canvas.scale(3.0, 3.0);
+        canvas.rotate(0.5);
+        paint.set_font_size(200.0);
+        canvas.fill_text(10.0, 50.0, text, paint).unwrap();
+        canvas.restore();
+
+        assert_eq!(canvas.image_memory_usage() - memory_usage, 512 * 512 * 4);
+
+        // Layout doesn't depend on how glyphs are rendered
+        paint.set_font_size(30.0);
+        let metrics = canvas.measure_text(0.0, 0.0, text, paint).unwrap();
+        paint.set_glyph_rendering(GlyphRendering::Grayscale);
+        assert_eq!(
+            canvas.measure_text(0.0, 0.0, text, paint).unwrap().width(),
+            metrics.width()
+        );
+    }
+
+    canvas.flush(None);
+}
+
+#[test]
+fn font_database_matching() {
+    let mut canvas = Canvas::new(Void).unwrap();
+
+    let regular = canvas
+        .add_font("examples/assets/Roboto-Regular.ttf")
+        .expect("Font not found");
+    canvas.add_font_search_dir("examples/assets").unwrap();
+
+    assert!(canvas.font_families().iter().any(|family| family == "Roboto"));
+
+    let mut paint = Paint::color(Color::black());
+
+    // Fonts added to the canvas are matched without loading them again
+    assert_eq!(canvas.find_font(&FontQuery::new("roboto")).ok(), Some(regular));
+
+    let bold = canvas.find_font(&"Roboto, bold".parse().unwrap()).unwrap();
+    paint.set_font(&[bold]);
+    let metrics = canvas.measure_font(paint).unwrap();
+    assert!(metrics.bold());
+    assert_eq!(metrics.weight(), 700);
+    assert_eq!(metrics.width(), 5);
+
+    // Search directory fonts are loaded once
+    assert_eq!(canvas.find_font(&"Roboto, 700".parse().unwrap()).ok(), Some(bold));
+
+    let light = canvas
+        .find_font(&"Nonexistent, Roboto, light".parse().unwrap())
+        .unwrap();
+    paint.set_font(&[light]);
+    assert_eq!(canvas.measure_font(paint).unwrap().weight(), 300);
+
+    // Lighter weights than available go to the lightest heavier face
+    let mut query = FontQuery::new("Roboto");
+    query.set_weight(100);
+    assert_eq!(canvas.find_font(&query).ok(), Some(light));
+
+    // Weights from 400 to 500 prefer lighter faces over heavier ones
+    query.set_weight(500);
+    assert_eq!(canvas.find_font(&query).ok(), Some(regular));
+
+    // Missing italics fall back to the upright faces
+    query.set_style(FontStyle::Italic);
+    query.set_weight(600);
+    assert_eq!(canvas.find_font(&query).ok(), Some(bold));
+
+    assert!(canvas.find_font(&FontQuery::new("Nonexistent")).is_err());
+}
+
+#[test]
+fn color_matrix_composition() {
+    let sepia = ColorMatrix::sepia(1.0);
+
+    assert_eq!(ColorMatrix::identity().then(&sepia), sepia);
+    assert_eq!(sepia.then(&ColorMatrix::identity()), sepia);
+
+    // Contrast offsets are scaled by the matrix applied after them
+    let m = ColorMatrix::contrast(0.5).then(&ColorMatrix::brightness(2.0));
+    assert_eq!(m.0[0], 1.0);
+    assert_eq!(m.0[4], 0.5);
+    assert_eq!(m.0[19], 0.0);
+
+    for (a, b) in ColorMatrix::grayscale(0.0)
+        .0
+        .iter()
+        .zip(ColorMatrix::identity().0.iter())
+    {
+        assert!((a - b).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn paint_with_mask() {
+    let mut canvas = Canvas::new(Void).unwrap();
+    canvas.set_size(100, 100, 1.0);
+
+    let image = canvas
+        .create_image_empty(16, 16, PixelFormat::Gray8, ImageFlags::FLIP_Y)
+        .unwrap();
+
+    let mut mask = Mask::luminance(image, 0.0, 0.0, 50.0, 50.0);
+    mask.set_transform(femtovg::Transform2D::new_translation(10.0, 10.0));
+
+    let mut paint = Paint::color(Color::black());
+    paint.set_mask(Some(mask));
+    assert_eq!(paint.mask().map(|mask| mask.image()), Some(image));
+
+    let mut path = Path::new();
+    path.rect(10.0, 10.0, 50.0, 50.0);
+    canvas.fill_path(&mut path, paint);
+    canvas.stroke_path(&mut path, paint);
+
+    // Masks referencing deleted images are ignored
+    canvas.delete_image(image);
+    canvas.fill_path(&mut path, paint);
+}
+
+#[test]
+fn create_images_with_extra_pixel_formats() {
+    use imgref::Img;
+    use rgb::alt::{
+        BGRA8,
+        GRAYA8,
+    };
+
+    let mut canvas = Canvas::new(Void).unwrap();
+
+    let bgra = vec![
+        BGRA8 {
+            b: 255,
+            g: 0,
+            r: 0,
+            a: 255
+        };
+        4 * 4
+    ];
+    let image = canvas
+        .create_image(Img::new(bgra.as_slice(), 4, 4), ImageFlags::empty())
+        .unwrap();
+    assert_eq!(canvas.image_info(image).unwrap().format(), PixelFormat::Bgra8);
+
+    let gray_alpha = vec![GRAYA8::new(128, 255); 2 * 2];
+    let image = canvas
+        .create_image(Img::new(gray_alpha.as_slice(), 2, 2), ImageFlags::empty())
+        .unwrap();
+    assert_eq!(canvas.image_info(image).unwrap().format(), PixelFormat::GrayAlpha8);
+
+    let half = vec![rgb::RGBA::new(0x3c00u16, 0x3c00, 0x3c00, 0x3c00); 2 * 2];
+    let image = canvas
+        .create_image(
+            femtovg::ImageSource::Rgba16F(Img::new(half.as_slice(), 2, 2)),
+            ImageFlags::empty(),
+        )
+        .unwrap();
+    assert_eq!(canvas.image_info(image).unwrap().format(), PixelFormat::Rgba16F);
+    assert_eq!(PixelFormat::Rgba16F.bytes_per_pixel(), 8);
+}
+
+#[test]
+fn yuv_to_rgb_matrix() {
+    use femtovg::{
+        YuvMatrix,
+        YuvRange,
+    };
+
+    let apply = |m: &ColorMatrix, yuv: [f32; 3]| -> [f32; 3] {
+        let mut out = [0.0; 3];
+
+        for (row, out) in out.iter_mut().enumerate() {
+            let r = &m.0[row * 5..row * 5 + 5];
+            *out = r[0] * yuv[0] + r[1] * yuv[1] + r[2] * yuv[2] + r[3] + r[4];
+        }
+
+        out
+    };
+
+    let neutral = 128.0 / 255.0;
+
+    for &matrix in &[YuvMatrix::Bt601, YuvMatrix::Bt709] {
+        let limited = ColorMatrix::yuv_to_rgb(matrix, YuvRange::Limited);
+        let full = ColorMatrix::yuv_to_rgb(matrix, YuvRange::Full);
+
+        for (m, black, white) in &[(limited, 16.0 / 255.0, 235.0 / 255.0), (full, 0.0, 1.0)] {
+            for c in apply(m, [*black, neutral, neutral]).iter() {
+                assert!(c.abs() < 1e-5);
+            }
+
+            for c in apply(m, [*white, neutral, neutral]).iter() {
+                assert!((c - 1.0).abs() < 1e-5);
+            }
+        }
+    }
+}
+
+#[test]
+fn create_yuv_images() {
+    use imgref::Img;
+    use rgb::alt::{
+        GRAY8,
+        GRAYA8,
+    };
+
+    let mut canvas = Canvas::new(Void).unwrap();
+
+    let luma = vec![GRAY8::new(0); 5 * 3];
+    let uv = vec![GRAYA8::new(128, 128); 3 * 2];
+    let src = femtovg::ImageSource::Nv12 {
+        y: Img::new(luma.as_slice(), 5, 3),
+        uv: Img::new(uv.as_slice(), 3, 2),
+    };
+
+    let image = canvas.create_image(src, ImageFlags::empty()).unwrap();
+    assert_eq!(canvas.image_size(image).unwrap(), (5, 3));
+    assert!(canvas.image_info(image).unwrap().format().is_yuv());
+
+    let chroma = vec![GRAY8::new(128); 3 * 2];
+    let src = femtovg::ImageSource::I420 {
+        y: Img::new(luma.as_slice(), 5, 3),
+        u: Img::new(chroma.as_slice(), 3, 2),
+        v: Img::new(chroma.as_slice(), 3, 2),
+    };
+
+    let image = canvas.create_image(src, ImageFlags::empty()).unwrap();
+    assert_eq!(canvas.image_info(image).unwrap().format(), PixelFormat::I420);
+
+    // Chroma planes have to cover the luma plane
+    let small = vec![GRAY8::new(128); 2 * 2];
+    let src = femtovg::ImageSource::I420 {
+        y: Img::new(luma.as_slice(), 5, 3),
+        u: Img::new(chroma.as_slice(), 3, 2),
+        v: Img::new(small.as_slice(), 2, 2),
+    };
+    assert!(canvas.create_image(src, ImageFlags::empty()).is_err());
+
+    let src = femtovg::ImageSource::Nv12 {
+        y: Img::new(luma.as_slice(), 5, 3),
+        uv: Img::new(&uv[..3], 3, 1),
+    };
+    assert!(canvas.create_image(src, ImageFlags::empty()).is_err());
+
+    // Updates start on even pixels, where chroma samples begin
+    let image = canvas
+        .create_image_empty(8, 8, PixelFormat::Nv12, ImageFlags::empty())
+        .unwrap();
+    let luma = vec![GRAY8::new(0); 4 * 4];
+    let uv = vec![GRAYA8::new(128, 128); 2 * 2];
+    let src = femtovg::ImageSource::Nv12 {
+        y: Img::new(luma.as_slice(), 4, 4),
+        uv: Img::new(uv.as_slice(), 2, 2),
+    };
+
+    assert!(canvas.update_image(image, src, 4, 2).is_ok());
+    assert!(matches!(
+        canvas.update_image(image, src, 3, 2),
+        Err(ErrorKind::ImageUpdateWithOddOffset)
+    ));
+    assert!(matches!(
+        canvas.update_image(image, src, 2, 1),
+        Err(ErrorKind::ImageUpdateWithOddOffset)
+    ));
+}
+
+#[test]
+fn read_image_region() {
+    let mut canvas = Canvas::new(Void).unwrap();
+
+    let image = canvas
+        .create_image_empty(32, 16, PixelFormat::Rgba8, ImageFlags::empty())
+        .unwrap();
+
+    canvas.set_render_target(RenderTarget::Image(image));
+    canvas.clear_rect(0, 0, 32, 16, Color::rgb(255, 0, 0));
+    canvas.set_render_target(RenderTarget::Screen);
+    canvas.flush(None);
+
+    let pixels = canvas.read_image(image, Rect::new(0.0, 0.0, 32.0, 16.0)).unwrap();
+    assert_eq!((pixels.width(), pixels.height()), (32, 16));
+
+    // Fractional rectangles are rounded out to whole pixels
+    let pixels = canvas.read_image(image, Rect::new(2.5, 1.5, 4.0, 4.0)).unwrap();
+    assert_eq!((pixels.width(), pixels.height()), (5, 5));
+
+    assert!(canvas.read_image(image, Rect::new(16.0, 0.0, 32.0, 16.0)).is_err());
+    assert!(canvas.read_image(image, Rect::new(-1.0, 0.0, 4.0, 4.0)).is_err());
+
+    canvas.delete_image(image);
+    assert!(canvas.read_image(image, Rect::new(0.0, 0.0, 1.0, 1.0)).is_err());
+}
+
+#[test]
+fn image_memory_budget_evicts_least_recently_drawn() {
+    let mut canvas = Canvas::new(Void).unwrap();
+    let base_usage = canvas.image_memory_usage();
+
+    let flags = ImageFlags::PURGEABLE;
+    let a = canvas.create_image_empty(16, 16, PixelFormat::Rgba8, flags).unwrap();
+    let b = canvas.create_image_empty(16, 16, PixelFormat::Rgba8, flags).unwrap();
+    let pinned = canvas
+        .create_image_empty(16, 16, PixelFormat::Gray8, ImageFlags::empty())
+        .unwrap();
+
+    assert_eq!(canvas.image_memory_usage() - base_usage, 16 * 16 * 4 * 2 + 16 * 16);
+
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let evicted_clone = evicted.clone();
+    canvas.set_image_eviction_callback(move |id| evicted_clone.lock().unwrap().push(id));
+
+    let draw = |canvas: &mut Canvas<Void>, id| {
+        let mut path = Path::new();
+        path.rect(0.0, 0.0, 16.0, 16.0);
+        canvas.fill_path(&mut path, Paint::image(id, 0.0, 0.0, 16.0, 16.0, 0.0, 1.0));
+        canvas.flush(None);
+        canvas.end_frame();
+    };
+
+    draw(&mut canvas, a);
+    draw(&mut canvas, b);
+
+    // Room for a single purgeable image, a is the least recently drawn
+    canvas.set_image_memory_budget(Some(canvas.image_memory_usage() - 16 * 16 * 4));
+    draw(&mut canvas, b);
+
+    assert_eq!(*evicted.lock().unwrap(), vec![a]);
+    assert!(canvas.image_info(a).is_err());
+    assert!(canvas.image_info(b).is_ok());
+
+    // Images which are not purgeable are kept even when over budget
+    canvas.set_image_memory_budget(Some(0));
+    draw(&mut canvas, pinned);
+
+    assert_eq!(*evicted.lock().unwrap(), vec![a, b]);
+    assert!(canvas.image_info(pinned).is_ok());
+
+    // The callback doesn't keep the canvas from being sent to another thread
+    std::thread::spawn(move || canvas.end_frame()).join().unwrap();
+}
+
+#[test]
+fn image_memory_budget_spans_frames() {
+    let mut canvas = Canvas::new(Void).unwrap();
+
+    let flags = ImageFlags::PURGEABLE;
+    let drawn = canvas.create_image_empty(16, 16, PixelFormat::Rgba8, flags).unwrap();
+    let target = canvas.create_image_empty(16, 16, PixelFormat::Rgba8, flags).unwrap();
+
+    canvas.set_image_memory_budget(Some(0));
+
+    // Flushing doesn't end the frame
+    canvas.flush(None);
+    canvas.flush(None);
+    assert!(canvas.image_info(drawn).is_ok());
+    assert!(canvas.image_info(target).is_ok());
+
+    // Render targets count as used, the same as drawn images
+    canvas.set_render_target(RenderTarget::Image(target));
+    let mut path = Path::new();
+    path.rect(0.0, 0.0, 16.0, 16.0);
+    canvas.fill_path(&mut path, Paint::image(drawn, 0.0, 0.0, 16.0, 16.0, 0.0, 1.0));
+    canvas.set_render_target(RenderTarget::Screen);
+    canvas.flush(None);
+    canvas.end_frame();
+
+    assert!(canvas.image_info(drawn).is_ok());
+    assert!(canvas.image_info(target).is_ok());
+
+    canvas.end_frame();
+
+    assert!(canvas.image_info(drawn).is_err());
+    assert!(canvas.image_info(target).is_err());
+}
+
+#[test]
+fn external_flag_is_reserved_for_imports() {
+    let mut canvas = Canvas::new(Void).unwrap();
+
+    // Images created by the canvas are always owned by it
+    assert!(matches!(
+        canvas.create_image_empty(16, 16, PixelFormat::Rgba8, ImageFlags::EXTERNAL),
+        Err(ErrorKind::InvalidImageFlags)
+    ));
+
+    let pixels = vec![RGBA8::new(0, 0, 0, 255); 4 * 4];
+    let src = ImgVec::new(pixels, 4, 4);
+    assert!(canvas
+        .create_image(src.as_ref(), ImageFlags::EXTERNAL | ImageFlags::PURGEABLE)
+        .is_err());
+
+    let image = canvas
+        .create_image_empty(16, 16, PixelFormat::Rgba8, ImageFlags::empty())
+        .unwrap();
+    assert!(canvas
+        .realloc_image(image, 32, 32, PixelFormat::Rgba8, ImageFlags::EXTERNAL)
+        .is_err());
+    assert_eq!(canvas.image_size(image).unwrap(), (16, 16));
+}
+
+#[test]
+fn image_sampler_options() {
+    let mut canvas = Canvas::new(Void).unwrap();
+
+    let image = canvas
+        .create_image_empty(16, 16, PixelFormat::Rgba8, ImageFlags::GENERATE_MIPMAPS)
+        .unwrap();
+
+    assert_eq!(canvas.image_info(image).unwrap().sampler(), ImageSampler::default());
+
+    let mut sampler = ImageSampler::nearest();
+    sampler.set_min_filter(ImageFilter::Linear);
+    sampler.set_mipmap_mode(MipmapMode::Linear);
+    sampler.set_wrap(WrapMode::MirroredRepeat, WrapMode::ClampToBorder);
+    sampler.set_bicubic(true);
+
+    canvas.set_image_sampler(image, sampler).unwrap();
+    assert_eq!(canvas.image_info(image).unwrap().sampler(), sampler);
+
+    // Reallocating keeps the sampling options
+    canvas
+        .realloc_image(image, 32, 32, PixelFormat::Rgba8, ImageFlags::empty())
+        .unwrap();
+    assert_eq!(canvas.image_info(image).unwrap().sampler(), sampler);
+
+    let mut path = Path::new();
+    path.rect(0.0, 0.0, 32.0, 32.0);
+    canvas.fill_path(&mut path, Paint::image(image, 0.0, 0.0, 64.0, 64.0, 0.0, 1.0));
+    canvas.flush(None);
+
+    canvas.delete_image(image);
+    assert!(canvas.set_image_sampler(image, sampler).is_err());
+}
+
+#[test]
+fn generate_mipmaps() {
+    let mut canvas = Canvas::new(Void).unwrap();
+    canvas.set_size(100, 100, 1.0);
+
+    let image = canvas
+        .create_image_empty(32, 32, PixelFormat::Rgba8, ImageFlags::GENERATE_MIPMAPS)
+        .unwrap();
+
+    canvas.set_render_target(RenderTarget::Image(image));
+    canvas.clear_rect(0, 0, 32, 32, Color::rgb(255, 0, 0));
+    canvas.set_render_target(RenderTarget::Screen);
+    canvas.flush(None);
+
+    assert!(canvas.generate_mipmaps(image).is_ok());
+
+    canvas.delete_image(image);
+    assert!(canvas.generate_mipmaps(image).is_err());
+}
+
+#[cfg(feature = "image-loading")]
+#[test]
+fn load_image_async() {
+    let mut canvas = Canvas::new(Void).unwrap();
+
+    let mut png = Vec::new();
+    image::DynamicImage::ImageRgba8(image::RgbaImage::new(7, 5))
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .unwrap();
+
+    let pending = canvas.load_image_mem_async(png, ImageFlags::GENERATE_MIPMAPS);
+    while !pending.is_ready() {
+        std::thread::yield_now();
+    }
+
+    let image = canvas.upload_pending_image(pending).unwrap();
+    let info = canvas.image_info(image).unwrap();
+    assert_eq!((info.width(), info.height()), (7, 5));
+    assert_eq!(info.flags(), ImageFlags::GENERATE_MIPMAPS);
+
+    // Decoding errors surface on upload, waiting for the decoder if needed
+    let pending = canvas.load_image_mem_async(vec![1, 2, 3], ImageFlags::empty());
+    assert!(canvas.upload_pending_image(pending).is_err());
+
+    let pending = canvas.load_image_file_async("does/not/exist.png", ImageFlags::empty());
+    assert!(canvas.upload_pending_image(pending).is_err());
+}
+
+#[cfg(feature = "svg")]
+#[test]
+fn draw_svg_document() {
+    use femtovg::{
+        SvgDocument,
+        Transform2D,
+    };
+
+    let svg: SvgDocument = r##"
+        <svg xmlns="http://www.w3.org/2000/svg" width="100" height="50" viewBox="0 0 200 100">
+            <defs>
+                <linearGradient id="fade" gradientTransform="rotate(45)">
+                    <stop offset="0" stop-color="#f00"/>
+                    <stop offset="1" stop-color="#00f" stop-opacity="0.5"/>
+                </linearGradient>
+                <clipPath id="circle">
+                    <circle cx="50" cy="50" r="40"/>
+                </clipPath>
+            </defs>
+            <g opacity="0.5" clip-path="url(#circle)">
+                <rect width="100" height="100" fill="url(#fade)" stroke="black" stroke-width="4"/>
+            </g>
+        </svg>
+    "##
+    .parse()
+    .unwrap();
+
+    assert_eq!((svg.width(), svg.height()), (100.0, 50.0));
+    assert!("<svg".parse::<SvgDocument>().is_err());
+
+    let mut canvas = Canvas::new(Void).unwrap();
+    canvas.set_size(100, 100, 1.0);
+
+    let memory_usage = canvas.image_memory_usage();
+    canvas.draw_svg(&svg, Transform2D::new_translation(10.0, 10.0));
+
+    // The clip mask lives until the frame is rendered
+    assert!(canvas.image_memory_usage() > memory_usage);
+    canvas.flush(None);
+    assert_eq!(canvas.image_memory_usage(), memory_usage);
+}
+
+#[cfg(feature = "lottie")]
+#[test]
+fn draw_lottie_animation() {
+    use femtovg::LottieAnimation;
+
+    let animation: LottieAnimation = r##"{
+        "v": "5.7.0", "fr": 25, "ip": 0, "op": 50, "w": 200, "h": 100,
+        "layers": [
+            {
+                "ty": 4, "ind": 1, "parent": 2, "ip": 0, "op": 50, "st": 0,
+                "ks": {
+                    "p": { "a": 1, "k": [
+                        { "t": 0, "s": [0, 0], "o": { "x": [0.4], "y": [0] }, "i": { "x": [0.6], "y": [1] } },
+                        { "t": 50, "s": [100, 50] }
+                    ] },
+                    "o": { "a": 0, "k": 80 }
+                },
+                "masksProperties": [{
+                    "mode": "a", "inv": false, "o": { "a": 0, "k": 100 },
+                    "pt": { "a": 0, "k": {
+                        "c": true,
+                        "v": [[0, 0], [50, 0], [50, 50], [0, 50]],
+                        "i": [[0, 0], [0, 0], [0, 0], [0, 0]],
+                        "o": [[0, 0], [0, 0], [0, 0], [0, 0]]
+                    } }
+                }],
+                "shapes": [{
+                    "ty": "gr",
+                    "it": [
+                        { "ty": "rc", "p": { "a": 0, "k": [0, 0] }, "s": { "a": 0, "k": [40, 20] }, "r": { "a": 0, "k": 4 } },
+                        { "ty": "el", "p": { "a": 0, "k": [20, 0] }, "s": { "a": 0, "k": [10, 10] } },
+                        { "ty": "st", "c": { "a": 0, "k": [0, 0, 0, 1] }, "o": { "a": 0, "k": 100 }, "w": { "a": 0, "k": 2 }, "lc": 2, "lj": 2 },
+                        {
+                            "ty": "gf", "t": 1, "o": { "a": 0, "k": 100 },
+                            "s": { "a": 0, "k": [-20, 0] }, "e": { "a": 0, "k": [20, 0] },
+                            "g": { "p": 2, "k": { "a": 0, "k": [0, 1, 0, 0, 1, 0, 0, 1, 0, 1, 1, 0.5] } }
+                        },
+                        { "ty": "tr", "p": { "a": 0, "k": [10, 10] }, "r": { "a": 0, "k": 30 } }
+                    ]
+                }]
+            },
+            { "ty": 3, "ind": 2, "td": 1, "ip": 0, "op": 50, "st": 0, "ks": { "s": { "a": 0, "k": [50, 50] } } },
+            { "ty": 1, "ind": 3, "tt": 1, "ip": 0, "op": 50, "st": 0, "sc": "#336699", "sw": 200, "sh": 100, "ks": {} }
+        ]
+    }"##
+    .parse()
+    .unwrap();
+
+    assert_eq!((animation.width(), animation.height()), (200.0, 100.0));
+    assert_eq!(animation.frame_rate(), 25.0);
+    assert_eq!(animation.duration(), 2.0);
+
+    assert!("{".parse::<LottieAnimation>().is_err());
+    assert!(r#"{ "w": 100, "h": 100 }"#.parse::<LottieAnimation>().is_err());
+
+    let mut canvas = Canvas::new(Void).unwrap();
+    canvas.set_size(200, 100, 1.0);
+
+    let memory_usage = canvas.image_memory_usage();
+
+    for &time in &[0.0, 0.5, 1.0, 5.0] {
+        canvas.draw_lottie(&animation, time);
+    }
+
+    // Masks and mattes live until the frame is rendered
+    assert!(canvas.image_memory_usage() > memory_usage);
+    canvas.flush(None);
+    assert_eq!(canvas.image_memory_usage(), memory_usage);
+}
+
+#[cfg(feature = "wgpu-renderer")]
+#[test]
+fn wgpu_render_into_mipmapped_image() {
+    use femtovg::{
+        renderer::{
+            WGPUContext,
+            WGPUInstance,
+            WGPU,
+        },
+        Size,
+    };
+
+    // Machines without a GPU adapter skip the test
+    let instance = match pollster::block_on(WGPUInstance::new()) {
+        Some(instance) => instance,
+        None => return,
+    };
+
+    let ctx = pollster::block_on(WGPUContext::new(instance)).unwrap();
+
+    let screen = ctx.device().create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: 64,
+            height: 64,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Bgra8Unorm,
+        usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
+    });
+    let screen_view = screen.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let renderer = WGPU::new(&ctx, Size::new(64.0, 64.0), wgpu::TextureFormat::Bgra8Unorm);
+    let mut canvas = Canvas::new(renderer).unwrap();
+    canvas.set_size(64, 64, 1.0);
+
+    let image = canvas
+        .create_image_empty(32, 32, PixelFormat::Rgba8, ImageFlags::GENERATE_MIPMAPS)
+        .unwrap();
+
+    // The render pass attaches only the base level of the mip chain
+    canvas.set_render_target(RenderTarget::Image(image));
+    let mut path = Path::new();
+    path.rect(0.0, 0.0, 32.0, 32.0);
+    canvas.fill_path(&mut path, Paint::color(Color::rgb(255, 0, 0)));
+    canvas.set_render_target(RenderTarget::Screen);
+    canvas.flush(Some(&screen_view));
+
+    let pixels = canvas.read_image(image, Rect::new(0.0, 0.0, 32.0, 32.0)).unwrap();
+    assert!(pixels.pixels().all(|px| px == RGBA8::new(255, 0, 0, 255)));
+}